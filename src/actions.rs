@@ -1,2 +1,10 @@
 pub mod channel_actions;
+pub mod filter_actions;
+pub mod lyrics_actions;
+pub mod pagination_actions;
+pub mod panel_actions;
 pub mod playback_actions;
+pub mod search_actions;
+pub mod session_actions;
+pub mod settings_actions;
+pub mod vote_actions;
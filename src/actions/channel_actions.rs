@@ -5,9 +5,10 @@ use crate::{
         disconnect_handler::DisconnectHandler, error_handler::ErrorHandler,
         inactivity_handler::InactivityHandler,
     },
-    models::{DiscordError, InternalError, RuntimeError},
-    server::Context,
+    models::{DEFAULT_VOLUME, DiscordError, GuildState, InternalError, RuntimeError},
+    server::{Context, ServerState},
 };
+use poise::serenity_prelude::{self, ChannelId, GuildId};
 use songbird::{CoreEvent, Event};
 use tracing::{error, instrument};
 
@@ -33,7 +34,27 @@ pub async fn join_channel(ctx: Context<'_>) -> Result<(), RuntimeError> {
     }
     .ok_or(InternalError::VoiceChannelMissing)?;
 
-    let manager = songbird::get(ctx.serenity_context())
+    join_voice_channel(
+        ctx.serenity_context(),
+        ctx.data(),
+        guild_id,
+        channel_id,
+        ctx.channel_id(),
+    )
+    .await
+}
+
+/// Joins a voice channel, registers the guild's global event handlers and starts tracking the
+/// guild. Notifications are posted to `text_channel_id`.
+#[instrument(skip(serenity_ctx, data))]
+pub async fn join_voice_channel(
+    serenity_ctx: &serenity_prelude::Context,
+    data: &ServerState,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    text_channel_id: ChannelId,
+) -> Result<(), RuntimeError> {
+    let manager = songbird::get(serenity_ctx)
         .await
         .ok_or_else(|| InternalError::DependencyMissing("Songbird Voice Client".to_string()))?;
 
@@ -48,24 +69,39 @@ pub async fn join_channel(ctx: Context<'_>) -> Result<(), RuntimeError> {
     // Triggered when the bot is disconnected or kicked from the voice region channel
     handle.add_global_event(
         Event::Core(CoreEvent::DriverDisconnect),
-        DisconnectHandler::new(&guild_id, ctx.data().guild_map.clone(), manager.clone()),
+        DisconnectHandler::new(
+            &guild_id,
+            data.guild_map.clone(),
+            manager.clone(),
+            data.stream_prefetcher.clone(),
+        ),
     );
 
     // Run the inactivity check loop every 30s to see if humans left
     handle.add_global_event(
         Event::Periodic(Duration::from_secs(30), None),
-        InactivityHandler::new(
-            &guild_id,
-            manager.clone(),
-            ctx.serenity_context().cache.clone(),
-        ),
+        InactivityHandler::new(&guild_id, manager.clone(), serenity_ctx.cache.clone()),
     );
 
     // Intercept media streaming decoding/io errors
     handle.add_global_event(
         Event::Track(songbird::TrackEvent::Error),
-        ErrorHandler::new(ctx.serenity_context().clone(), channel_id),
+        ErrorHandler::new(serenity_ctx.clone(), channel_id),
     );
 
+    let guild_key = guild_id.to_string();
+    let volume = data
+        .guild_settings
+        .read()
+        .await
+        .get(&guild_key)
+        .map_or(DEFAULT_VOLUME, |settings| settings.default_volume);
+
+    data.guild_map
+        .write()
+        .await
+        .entry(guild_key)
+        .or_insert_with(|| GuildState::new(channel_id, text_channel_id, volume));
+
     Ok(())
 }
@@ -0,0 +1,104 @@
+use poise::ChoiceParameter;
+use tracing::{instrument, trace};
+
+use crate::{
+    actions::playback_actions,
+    embeds,
+    models::{AudioFilter, DiscordError, FilterChain, FilterPreset, InternalError, RuntimeError},
+    server::Context,
+};
+
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default(), ?preset))]
+pub async fn set_filter(
+    ctx: &Context<'_>,
+    preset: FilterPreset,
+    amount: Option<f64>,
+    bands: Option<String>,
+) -> Result<(), RuntimeError> {
+    let filter =
+        AudioFilter::from_preset(preset, amount, bands.as_deref()).map_err(RuntimeError::User)?;
+
+    update_filters(ctx, |filters| {
+        filters.set(filter);
+        Ok(())
+    })
+    .await
+}
+
+/// Removes the given filter, or every filter when none is given.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default(), ?preset))]
+pub async fn clear_filter(
+    ctx: &Context<'_>,
+    preset: Option<FilterPreset>,
+) -> Result<(), RuntimeError> {
+    update_filters(ctx, |filters| match preset {
+        Some(preset) if !filters.remove(preset) => {
+            Err(format!("The {} filter is not active.", preset.name()))
+        }
+        Some(_) => Ok(()),
+        None if filters.is_empty() => Err("No filters are active.".to_string()),
+        None => {
+            filters.clear();
+            Ok(())
+        }
+    })
+    .await
+}
+
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn list_filters(ctx: &Context<'_>) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let filters = ctx
+        .data()
+        .guild_map
+        .read()
+        .await
+        .get(&guild_id.to_string())
+        .map(|state| state.filters.clone())
+        .unwrap_or_default();
+
+    ctx.send(poise::CreateReply::default().embed(embeds::create_filter_presets_embed(&filters)))
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+/// Edits the guild's filter chain and restarts the current track where it was so the change is
+/// heard immediately.
+async fn update_filters(
+    ctx: &Context<'_>,
+    edit: impl FnOnce(&mut FilterChain) -> Result<(), String>,
+) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    // Measured before the edit, as the position depends on the filters' playback rate.
+    let position = playback_actions::current_position(&ctx.data().guild_map, guild_id).await;
+
+    let filters = {
+        let mut map_guard = ctx.data().guild_map.write().await;
+        let guild_state = map_guard.get_mut(&guild_id.to_string()).ok_or_else(|| {
+            RuntimeError::User("I need to be in a voice channel to apply filters.".to_string())
+        })?;
+
+        edit(&mut guild_state.filters).map_err(RuntimeError::User)?;
+        guild_state.filters.clone()
+    };
+
+    if let Some(position) = position {
+        trace!(?position, "Restarting stream with updated filters.");
+        playback_actions::restart_stream(ctx.serenity_context(), ctx.data(), guild_id, position)
+            .await?;
+    }
+
+    ctx.send(poise::CreateReply::default().embed(embeds::create_filters_embed(&filters)))
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
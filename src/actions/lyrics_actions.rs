@@ -0,0 +1,52 @@
+use tracing::{instrument, trace};
+
+use crate::{
+    actions::pagination_actions,
+    embeds,
+    models::{InternalError, LyricsError, LyricsQuery, RuntimeError},
+    server::Context,
+};
+
+/// Most characters shown on one page of lyrics.
+const PAGE_LENGTH: usize = 1_500;
+
+/// Looks up the lyrics for `query`, or for the guild's current track when no query is given.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn show_lyrics(ctx: &Context<'_>, query: Option<String>) -> Result<(), RuntimeError> {
+    let query = match query {
+        Some(text) => LyricsQuery::Text(text),
+        None => {
+            let guild_id = ctx
+                .guild_id()
+                .ok_or(InternalError::GuildInformationMissing)?;
+
+            let current = ctx
+                .data()
+                .guild_map
+                .read()
+                .await
+                .get(&guild_id.to_string())
+                .and_then(|state| state.playback_state.get_current_track().clone());
+
+            let track = current.ok_or_else(|| {
+                RuntimeError::User(
+                    "Nothing is currently playing. Tell me which song to look up.".to_string(),
+                )
+            })?;
+            LyricsQuery::from_video(&track.title, &track.artist)
+        }
+    };
+
+    trace!(?query, "Looking up lyrics.");
+    let lyrics = match ctx.data().lyrics_provider.find(&query).await {
+        Ok(lyrics) => lyrics,
+        Err(LyricsError::NotFound) => {
+            return Err(RuntimeError::User(
+                "I couldn't find lyrics for that song.".to_string(),
+            ));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    pagination_actions::paginate(ctx, embeds::create_lyrics_embeds(&lyrics, PAGE_LENGTH)).await
+}
@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use poise::serenity_prelude::{
+    self, ComponentInteractionCollector, ComponentInteractionDataKind, CreateActionRow,
+    CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage, CreateSelectMenu,
+    CreateSelectMenuKind, CreateSelectMenuOption,
+};
+use tracing::{instrument, trace};
+
+use crate::{
+    models::{DiscordError, RuntimeError},
+    server::Context,
+};
+
+/// How long the navigation buttons stay active after the last interaction.
+const PAGINATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Discord caps select menus at 25 options.
+const MAX_SELECT_OPTIONS: usize = 25;
+
+/// Custom IDs of the navigation components, prefixed with the invocation ID to ignore
+/// interactions with other messages.
+struct NavigationIds {
+    first: String,
+    prev: String,
+    next: String,
+    last: String,
+    jump: String,
+}
+
+impl NavigationIds {
+    fn new(ctx_id: &str) -> Self {
+        Self {
+            first: format!("{ctx_id}first"),
+            prev: format!("{ctx_id}prev"),
+            next: format!("{ctx_id}next"),
+            last: format!("{ctx_id}last"),
+            jump: format!("{ctx_id}jump"),
+        }
+    }
+
+    fn components(
+        &self,
+        current_page: usize,
+        n_pages: usize,
+        enabled: bool,
+    ) -> Vec<CreateActionRow> {
+        let buttons = [
+            (&self.first, '⏮'),
+            (&self.prev, '◀'),
+            (&self.next, '▶'),
+            (&self.last, '⏭'),
+        ]
+        .into_iter()
+        .map(|(id, emoji)| CreateButton::new(id).emoji(emoji).disabled(!enabled))
+        .collect();
+
+        // Offer a window of pages around the current one when there are too many to list.
+        let start = current_page
+            .saturating_sub(MAX_SELECT_OPTIONS / 2)
+            .min(n_pages.saturating_sub(MAX_SELECT_OPTIONS));
+        let options = (start..n_pages.min(start + MAX_SELECT_OPTIONS))
+            .map(|page| {
+                CreateSelectMenuOption::new(format!("Page {}", page + 1), page.to_string())
+                    .default_selection(page == current_page)
+            })
+            .collect();
+
+        let jump = CreateSelectMenu::new(&self.jump, CreateSelectMenuKind::String { options })
+            .placeholder("Jump to page")
+            .disabled(!enabled);
+
+        vec![
+            CreateActionRow::Buttons(buttons),
+            CreateActionRow::SelectMenu(jump),
+        ]
+    }
+}
+
+/// Sends the first page and lets the author flip through the rest with navigation buttons and a
+/// page selector until they time out.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default(), n_pages = pages.len()))]
+pub async fn paginate(
+    ctx: &Context<'_>,
+    pages: Vec<serenity_prelude::CreateEmbed>,
+) -> Result<(), RuntimeError> {
+    let Some(first_page) = pages.first().cloned() else {
+        return Ok(());
+    };
+
+    if pages.len() == 1 {
+        ctx.send(poise::CreateReply::default().embed(first_page))
+            .await
+            .map_err(DiscordError::Gateway)?;
+        return Ok(());
+    }
+
+    let ctx_id = ctx.id().to_string();
+    let ids = NavigationIds::new(&ctx_id);
+    let n_pages = pages.len();
+
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(first_page)
+                .components(ids.components(0, n_pages, true)),
+        )
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    let mut current_page: usize = 0;
+    while let Some(press) = ComponentInteractionCollector::new(ctx.serenity_context())
+        .filter({
+            let ctx_id = ctx_id.clone();
+            move |press| press.data.custom_id.starts_with(&ctx_id)
+        })
+        .timeout(PAGINATION_TIMEOUT)
+        .await
+    {
+        let custom_id = &press.data.custom_id;
+        current_page = if *custom_id == ids.first {
+            0
+        } else if *custom_id == ids.prev {
+            current_page.checked_sub(1).unwrap_or(n_pages - 1)
+        } else if *custom_id == ids.next {
+            (current_page + 1) % n_pages
+        } else if *custom_id == ids.last {
+            n_pages - 1
+        } else if let ComponentInteractionDataKind::StringSelect { values } = &press.data.kind
+            && *custom_id == ids.jump
+        {
+            values
+                .first()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|page| *page < n_pages)
+                .unwrap_or(current_page)
+        } else {
+            continue;
+        };
+
+        trace!(current_page, "Navigating to page.");
+        press
+            .create_response(
+                ctx.serenity_context(),
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .embed(pages[current_page].clone())
+                        .components(ids.components(current_page, n_pages, true)),
+                ),
+            )
+            .await
+            .map_err(DiscordError::Gateway)?;
+    }
+
+    // Leave the last page in place but stop advertising dead components.
+    reply
+        .edit(
+            *ctx,
+            poise::CreateReply::default()
+                .embed(pages[current_page].clone())
+                .components(ids.components(current_page, n_pages, false)),
+        )
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
@@ -0,0 +1,394 @@
+use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
+
+use poise::serenity_prelude::{
+    self, ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, EditMessage,
+    GuildId,
+};
+use songbird::tracks::PlayMode;
+use tokio::sync::RwLock;
+use tracing::{instrument, trace};
+
+use crate::{
+    actions::{
+        playback_actions,
+        vote_actions::{self, SkipVote},
+    },
+    checks,
+    embeds::{self, NowPlayingPanel},
+    models::{DiscordError, GuildState, InternalError, RuntimeError},
+    server::ServerState,
+};
+
+/// Prefix shared by the custom IDs of every panel button.
+pub const PANEL_ID_PREFIX: &str = "now_playing:";
+
+/// How often the panel's progress is refreshed while a track plays.
+pub const PANEL_REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, strum::AsRefStr, strum::EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum PanelButton {
+    Previous,
+    PauseResume,
+    Skip,
+    Stop,
+    Loop,
+    Shuffle,
+}
+
+impl PanelButton {
+    fn custom_id(self) -> String {
+        format!("{PANEL_ID_PREFIX}{}", self.as_ref())
+    }
+
+    fn create(self, is_paused: bool) -> CreateButton {
+        let (emoji, style) = match self {
+            PanelButton::Previous => ('⏮', ButtonStyle::Secondary),
+            PanelButton::PauseResume if is_paused => ('▶', ButtonStyle::Success),
+            PanelButton::PauseResume => ('⏸', ButtonStyle::Primary),
+            PanelButton::Skip => ('⏭', ButtonStyle::Secondary),
+            PanelButton::Stop => ('⏹', ButtonStyle::Danger),
+            PanelButton::Loop => ('🔁', ButtonStyle::Secondary),
+            PanelButton::Shuffle => ('🔀', ButtonStyle::Secondary),
+        };
+
+        CreateButton::new(self.custom_id())
+            .emoji(emoji)
+            .style(style)
+    }
+
+    /// The command whose policy governs the button.
+    fn command(self) -> &'static str {
+        match self {
+            PanelButton::Previous => "previous",
+            PanelButton::PauseResume => "pause",
+            PanelButton::Skip => "skip",
+            PanelButton::Stop => "stop",
+            PanelButton::Loop => "loop",
+            PanelButton::Shuffle => "shuffle",
+        }
+    }
+}
+
+fn panel_components(is_paused: bool) -> Vec<CreateActionRow> {
+    use PanelButton::*;
+
+    [
+        [Previous, PauseResume, Skip, Stop].as_slice(),
+        &[Loop, Shuffle],
+    ]
+    .iter()
+    .map(|row| CreateActionRow::Buttons(row.iter().map(|b| b.create(is_paused)).collect()))
+    .collect()
+}
+
+/// Posts or updates the guild's now playing panel to reflect the current playback state.
+#[instrument(skip(serenity_ctx, guild_map))]
+pub async fn refresh_panel(
+    serenity_ctx: &serenity_prelude::Context,
+    guild_map: &Arc<RwLock<HashMap<String, GuildState>>>,
+    guild_id: GuildId,
+) -> Result<(), RuntimeError> {
+    let guild_key = guild_id.to_string();
+
+    let panel_data = {
+        let map_guard = guild_map.read().await;
+        let guild_state = map_guard
+            .get(&guild_key)
+            .ok_or(InternalError::BadGuildState)?;
+        let playback_state = &guild_state.playback_state;
+
+        guild_state
+            .text_channel_id
+            .zip(playback_state.get_current_track().clone().map(|track| {
+                (
+                    track,
+                    playback_state.get_track_handle().clone(),
+                    playback_state.get_loop_mode(),
+                    playback_state.is_radio_mode_enabled(),
+                    playback_state.number_of_tracks_queued(),
+                    guild_state.now_playing_message_id,
+                )
+            }))
+    };
+
+    let Some((channel_id, (track, handle, loop_mode, is_radio_enabled, n_queued, message_id))) =
+        panel_data
+    else {
+        trace!("Nothing playing. Skipping panel refresh.");
+        return Ok(());
+    };
+
+    let info = match handle {
+        Some(handle) => handle.get_info().await.ok(),
+        None => None,
+    };
+
+    let elapsed = match &info {
+        Some(info) => guild_map
+            .read()
+            .await
+            .get(&guild_key)
+            .map(|state| state.track_position(info.position))
+            .unwrap_or_default(),
+        None => Duration::ZERO,
+    };
+
+    let panel = NowPlayingPanel {
+        track: &track,
+        elapsed,
+        total: track.duration,
+        is_paused: info.as_ref().is_some_and(|i| i.playing == PlayMode::Pause),
+        loop_mode,
+        is_radio_enabled,
+        n_queued,
+    };
+
+    let embed = embeds::create_now_playing_panel_embed(&panel);
+    let components = panel_components(panel.is_paused);
+
+    if let Some(message_id) = message_id {
+        let edit = EditMessage::new()
+            .embed(embed.clone())
+            .components(components.clone());
+
+        match channel_id
+            .edit_message(serenity_ctx, message_id, edit)
+            .await
+        {
+            Ok(_) => return Ok(()),
+            Err(e) => trace!(err = %e, "Failed to edit panel. Posting a new one."),
+        }
+    }
+
+    let message = channel_id
+        .send_message(
+            serenity_ctx,
+            CreateMessage::new().embed(embed).components(components),
+        )
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    if let Some(guild_state) = guild_map.write().await.get_mut(&guild_key) {
+        guild_state.now_playing_message_id = Some(message.id);
+    }
+
+    Ok(())
+}
+
+/// Retires the guild's now playing panel, removing its buttons.
+#[instrument(skip(serenity_ctx, guild_map))]
+pub async fn close_panel(
+    serenity_ctx: &serenity_prelude::Context,
+    guild_map: &Arc<RwLock<HashMap<String, GuildState>>>,
+    guild_id: GuildId,
+    message: &str,
+) -> Result<(), RuntimeError> {
+    let panel = guild_map
+        .write()
+        .await
+        .get_mut(&guild_id.to_string())
+        .and_then(|state| {
+            state
+                .text_channel_id
+                .zip(state.now_playing_message_id.take())
+        });
+
+    let Some((channel_id, message_id)) = panel else {
+        return Ok(());
+    };
+
+    channel_id
+        .edit_message(
+            serenity_ctx,
+            message_id,
+            EditMessage::new()
+                .embed(embeds::create_now_playing_closed_embed(message))
+                .components(vec![]),
+        )
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+/// Handles a press of one of the panel's buttons, enforcing the slash command voice channel
+/// rules.
+#[instrument(skip_all, fields(guild_id = ?interaction.guild_id, user_id = %interaction.user.id))]
+pub async fn handle_interaction(
+    serenity_ctx: &serenity_prelude::Context,
+    data: &ServerState,
+    interaction: &ComponentInteraction,
+) -> Result<(), RuntimeError> {
+    let Some(button) = interaction
+        .data
+        .custom_id
+        .strip_prefix(PANEL_ID_PREFIX)
+        .and_then(|id| PanelButton::from_str(id).ok())
+    else {
+        return Ok(());
+    };
+
+    trace!(?button, "Panel button pressed.");
+    let guild_id = interaction
+        .guild_id
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let member = interaction
+        .member
+        .as_ref()
+        .ok_or(InternalError::GuildInformationMissing)?;
+    let settings = data
+        .guild_settings
+        .read()
+        .await
+        .get(&guild_id.to_string())
+        .cloned()
+        .unwrap_or_default();
+
+    let rules = {
+        let bot_id = serenity_ctx.cache.current_user().id;
+        let guild = serenity_ctx
+            .cache
+            .guild(guild_id)
+            .ok_or(InternalError::GuildInformationMissing)?;
+        checks::voice_channel_rules(&guild, interaction.user.id, bot_id)
+            .and_then(|_| checks::policy_rule(&guild, member, bot_id, &settings, button.command()))
+    };
+
+    let outcome = match rules {
+        Ok(()) if matches!(button, PanelButton::Skip) => {
+            match vote_actions::cast_skip_vote(serenity_ctx, data, guild_id, member, 1).await {
+                // Post the tally where the rest of the channel can join the vote.
+                Ok(SkipVote::Pending {
+                    track,
+                    votes,
+                    needed,
+                }) => {
+                    return interaction
+                        .create_response(
+                            serenity_ctx,
+                            CreateInteractionResponse::Message(
+                                CreateInteractionResponseMessage::new()
+                                    .embed(embeds::create_skip_vote_embed(&track, votes, needed))
+                                    .components(vote_actions::vote_components(
+                                        &track.id, votes, needed,
+                                    )),
+                            ),
+                        )
+                        .await
+                        .map_err(|e| DiscordError::Gateway(e).into());
+                }
+                Ok(SkipVote::Passed | SkipVote::Bypassed) => {
+                    apply_button(serenity_ctx, data, guild_id, button).await
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Ok(()) => apply_button(serenity_ctx, data, guild_id, button).await,
+        Err(msg) => Err(RuntimeError::User(msg)),
+    };
+
+    let response = match &outcome {
+        Ok(()) => CreateInteractionResponse::Acknowledge,
+        Err(e) => {
+            let msg = match e {
+                RuntimeError::User(msg) => msg.as_str(),
+                _ => "An unexpected internal error occurred while processing your request.",
+            };
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .embed(embeds::create_error_embed(msg))
+                    .ephemeral(true),
+            )
+        }
+    };
+
+    interaction
+        .create_response(serenity_ctx, response)
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    match outcome {
+        Err(RuntimeError::User(_)) => Ok(()),
+        Err(e) => Err(e),
+        // Track changes refresh the panel from the queue handler.
+        Ok(()) => match button {
+            PanelButton::PauseResume | PanelButton::Loop | PanelButton::Shuffle => {
+                refresh_panel(serenity_ctx, &data.guild_map, guild_id).await
+            }
+            _ => Ok(()),
+        },
+    }
+}
+
+async fn apply_button(
+    serenity_ctx: &serenity_prelude::Context,
+    data: &ServerState,
+    guild_id: GuildId,
+    button: PanelButton,
+) -> Result<(), RuntimeError> {
+    let nothing_playing = || RuntimeError::User("Nothing is currently playing.".to_string());
+
+    match button {
+        PanelButton::PauseResume => {
+            // Songbird is asked for the play mode without holding up other guilds.
+            let track_handle = data
+                .guild_map
+                .read()
+                .await
+                .get(&guild_id.to_string())
+                .and_then(|state| state.playback_state.get_track_handle().clone())
+                .ok_or_else(nothing_playing)?;
+
+            let is_paused = track_handle
+                .get_info()
+                .await
+                .is_ok_and(|info| info.playing == PlayMode::Pause);
+
+            _ = if is_paused {
+                track_handle.play()
+            } else {
+                track_handle.pause()
+            };
+            return Ok(());
+        }
+        PanelButton::Stop => {
+            return playback_actions::stop_guild_playback(serenity_ctx, data, guild_id).await;
+        }
+        _ => {}
+    }
+
+    let mut map_guard = data.guild_map.write().await;
+    let guild_state = map_guard
+        .get_mut(&guild_id.to_string())
+        .ok_or_else(nothing_playing)?;
+    let playback_state = &mut guild_state.playback_state;
+
+    let Some(track_handle) = playback_state.get_track_handle().clone() else {
+        return Err(nothing_playing());
+    };
+
+    match button {
+        PanelButton::Skip => {
+            playback_state.skip_tracks(0);
+            _ = track_handle.stop();
+        }
+        PanelButton::Previous => {
+            playback_state.rewind().ok_or_else(|| {
+                RuntimeError::User("There is no previous track to go back to.".to_string())
+            })?;
+            _ = track_handle.stop();
+        }
+        PanelButton::Loop => {
+            let loop_mode = playback_state.get_loop_mode().next();
+            playback_state.set_loop_mode(loop_mode);
+        }
+        PanelButton::Shuffle => playback_state.shuffle_queue(),
+        // Applied without holding the lock above.
+        PanelButton::PauseResume | PanelButton::Stop => {}
+    }
+
+    Ok(())
+}
@@ -1,28 +1,48 @@
 use crate::{
-    embeds::{self, create_info_embed},
+    actions::{
+        pagination_actions, panel_actions,
+        vote_actions::{self, SkipVote},
+    },
+    checks,
+    embeds::{self, QueuedTrack, create_info_embed},
     event_handlers::queue_handler::QueueHandler,
-    models::{DiscordError, InternalError, QueueElement, RuntimeError},
-    server::Context,
+    models::{
+        DiscordError, GuildState, InternalError, LoopMode, MAX_VOLUME, QueueElement, RuntimeError,
+        SKIP_SEVERAL, Track,
+    },
+    server::{Context, ServerState},
+    stream::{self, StreamPrefetcher},
 };
-use songbird::{Event, TrackEvent};
+use poise::serenity_prelude::{self, GuildId};
+use songbird::tracks::{PlayMode, Track as SongbirdTrack};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::RwLock;
 use tracing::{error, instrument, trace};
 
 #[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
 pub async fn start_queue_playback(ctx: &Context<'_>) -> Result<(), RuntimeError> {
-    trace!("Attempting to start queue playback");
     let guild_id = ctx
         .guild_id()
         .ok_or(InternalError::GuildInformationMissing)?;
 
+    start_guild_playback(ctx.serenity_context(), ctx.data(), guild_id, Duration::ZERO).await
+}
+
+/// Starts playing the guild's queue unless playback is already in progress, beginning the first
+/// track at `start`. Track changes are shown on the now playing panel.
+#[instrument(skip(serenity_ctx, data))]
+pub async fn start_guild_playback(
+    serenity_ctx: &serenity_prelude::Context,
+    data: &ServerState,
+    guild_id: GuildId,
+    start: Duration,
+) -> Result<(), RuntimeError> {
+    trace!("Attempting to start queue playback");
     let guild_key = guild_id.to_string();
-    let channel = ctx
-        .guild_channel()
-        .await
-        .ok_or(InternalError::GuildInformationMissing)?;
 
     // Extract track info and modify queue state
-    let url = {
-        let mut map_guard = ctx.data().guild_map.write().await;
+    let (url, volume, stream_options) = {
+        let mut map_guard = data.guild_map.write().await;
         let guild_state = map_guard
             .get_mut(&guild_key)
             .ok_or(InternalError::BadGuildState)?;
@@ -33,18 +53,23 @@ pub async fn start_queue_playback(ctx: &Context<'_>) -> Result<(), RuntimeError>
         }
 
         guild_state.playback_state.play_next();
-        guild_state
+        let track = guild_state
             .playback_state
             .get_current_track()
             .as_ref()
-            .map(|track| track.url.clone())
             .ok_or_else(|| {
                 error!("Queue state updated but track is missing.");
                 InternalError::BadGuildState
-            })?
+            })?;
+
+        (
+            track.url.clone(),
+            guild_state.volume_gain(),
+            guild_state.stream_options(track, start),
+        )
     };
 
-    let manager = songbird::get(ctx.serenity_context()).await.ok_or_else(|| {
+    let manager = songbird::get(serenity_ctx).await.ok_or_else(|| {
         error!("Failed to get songbird manager from context.");
         InternalError::DependencyMissing("Songibrd".to_string())
     })?;
@@ -52,33 +77,268 @@ pub async fn start_queue_playback(ctx: &Context<'_>) -> Result<(), RuntimeError>
     let manager_lock = manager.get_or_insert(guild_id);
     trace!("Commencing download and audio conversion of video.");
 
-    let track_input = crate::stream::create_audio_stream(&url).map_err(|e| {
+    let track_input = stream::create_audio_stream(&url, &stream_options).map_err(|e| {
         error!(err = %e, "Failed to instantiate custom audio stream pipeline.");
         InternalError::Stream(e)
     })?;
 
     let mut call_guard = manager_lock.lock().await;
     trace!("Attempting to play converted track.");
-    let t_handle = call_guard.play(track_input.into());
-
-    if let Err(e) = t_handle.add_event(
-        Event::Track(TrackEvent::End),
-        QueueHandler::new(
-            ctx.serenity_context().clone(),
-            &guild_id,
-            channel,
-            ctx.data().guild_map.clone(),
-            manager_lock.clone(),
-            ctx.data().youtube_client.clone(),
-        ),
-    ) {
-        error!(err = %e, "Failed to add queue event handler.");
-    }
+    let t_handle = call_guard.play(SongbirdTrack::from(track_input).volume(volume));
+    drop(call_guard);
 
     // Update the track handle reference back in the map safely
-    let mut map_guard = ctx.data().guild_map.write().await;
-    if let Some(guild_state) = map_guard.get_mut(&guild_key) {
-        guild_state.playback_state.set_track_handle(Some(t_handle));
+    {
+        let mut map_guard = data.guild_map.write().await;
+        if let Some(guild_state) = map_guard.get_mut(&guild_key) {
+            guild_state
+                .playback_state
+                .set_track_handle(Some(t_handle.clone()));
+            guild_state.playback_state.set_stream_offset(start);
+        }
+    }
+
+    // Registered after the offset is stored so the crossfade is timed against it.
+    QueueHandler::new(
+        serenity_ctx.clone(),
+        &guild_id,
+        data.guild_map.clone(),
+        manager_lock.clone(),
+        data.metadata_providers.clone(),
+        data.stream_prefetcher.clone(),
+    )
+    .register(&t_handle)
+    .await;
+
+    if let Err(e) = panel_actions::refresh_panel(serenity_ctx, &data.guild_map, guild_id).await {
+        error!(err = %e, "Failed to post now playing panel.");
+    }
+
+    Ok(())
+}
+
+/// The position reached in the guild's current track, if one is playing.
+pub async fn current_position(
+    guild_map: &Arc<RwLock<HashMap<String, GuildState>>>,
+    guild_id: GuildId,
+) -> Option<Duration> {
+    let guild_key = guild_id.to_string();
+    let handle = guild_map
+        .read()
+        .await
+        .get(&guild_key)?
+        .playback_state
+        .get_track_handle()
+        .clone()?;
+
+    let info = handle.get_info().await.ok()?;
+    guild_map
+        .read()
+        .await
+        .get(&guild_key)
+        .map(|state| state.track_position(info.position))
+}
+
+/// Warms up the stream of the track expected to play after the current one, or discards the
+/// guild's prefetched stream when nothing predictable comes next. Runs shortly before the current
+/// track hands over. Queue changes made afterwards are caught when the stream is taken, which
+/// only hands over a stream prepared for the track that actually plays next.
+#[instrument(skip(guild_map, prefetcher))]
+pub async fn prefetch_next_track(
+    guild_map: &Arc<RwLock<HashMap<String, GuildState>>>,
+    prefetcher: &StreamPrefetcher,
+    guild_id: GuildId,
+) {
+    let guild_key = guild_id.to_string();
+    let next = guild_map.read().await.get(&guild_key).and_then(|state| {
+        let playback_state = &state.playback_state;
+        if !playback_state.is_playing() {
+            return None;
+        }
+
+        // A live stream left waiting would fall behind the broadcast.
+        playback_state
+            .expected_next_track()
+            .filter(|track| !track.is_live)
+            .map(|track| {
+                (
+                    track.url.clone(),
+                    state.stream_options(track, Duration::ZERO),
+                )
+            })
+    });
+
+    let Some((url, stream_options)) = next else {
+        prefetcher.discard(&guild_key);
+        return;
+    };
+
+    if let Err(e) = prefetcher.prefetch(&guild_key, &url, &stream_options) {
+        error!(err = %e, "Failed to prefetch the next track.");
+    }
+}
+
+/// Replaces the current track's stream with one starting at `position`, picking up the guild's
+/// current volume and filters. The replaced stream ends without advancing the queue.
+#[instrument(skip(serenity_ctx, data))]
+pub async fn restart_stream(
+    serenity_ctx: &serenity_prelude::Context,
+    data: &ServerState,
+    guild_id: GuildId,
+    position: Duration,
+) -> Result<(), RuntimeError> {
+    let guild_key = guild_id.to_string();
+
+    let (url, old_handle, volume, stream_options) = {
+        let map_guard = data.guild_map.read().await;
+        let guild_state = map_guard
+            .get(&guild_key)
+            .ok_or(InternalError::BadGuildState)?;
+        let playback_state = &guild_state.playback_state;
+
+        let (Some(track), Some(handle)) = (
+            playback_state.get_current_track(),
+            playback_state.get_track_handle(),
+        ) else {
+            return Err(RuntimeError::User(
+                "Nothing is currently playing.".to_string(),
+            ));
+        };
+
+        (
+            track.url.clone(),
+            handle.clone(),
+            guild_state.volume_gain(),
+            guild_state.stream_options(track, position),
+        )
+    };
+
+    let is_paused = old_handle
+        .get_info()
+        .await
+        .is_ok_and(|info| info.playing == PlayMode::Pause);
+
+    let call = songbird::get(serenity_ctx)
+        .await
+        .and_then(|manager| manager.get(guild_id))
+        .ok_or(InternalError::VoiceChannelMissing)?;
+
+    trace!("Restarting stream.");
+    let track_input = stream::create_audio_stream(&url, &stream_options).map_err(|e| {
+        error!(err = %e, "Failed to instantiate custom audio stream pipeline.");
+        InternalError::Stream(e)
+    })?;
+
+    let mut track = SongbirdTrack::from(track_input).volume(volume);
+    if is_paused {
+        track = track.pause();
+    }
+
+    let t_handle = call.lock().await.play(track);
+
+    // Swap the handles before stopping the old stream so its end is recognised as stale.
+    if let Some(guild_state) = data.guild_map.write().await.get_mut(&guild_key) {
+        guild_state
+            .playback_state
+            .set_track_handle(Some(t_handle.clone()));
+        guild_state.playback_state.set_stream_offset(position);
+    }
+
+    // The new stream prefetches the next track once its own end draws near, with the filters
+    // that apply by then.
+    data.stream_prefetcher.discard(&guild_key);
+
+    QueueHandler::new(
+        serenity_ctx.clone(),
+        &guild_id,
+        data.guild_map.clone(),
+        call.clone(),
+        data.metadata_providers.clone(),
+        data.stream_prefetcher.clone(),
+    )
+    .register(&t_handle)
+    .await;
+
+    _ = old_handle.stop();
+    Ok(())
+}
+
+/// Where to move playback to within the current track.
+#[derive(Debug, Clone, Copy)]
+pub enum SeekTarget {
+    Absolute(Duration),
+    Forward(Duration),
+    Backward(Duration),
+}
+
+#[instrument(skip(ctx), fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn seek(ctx: &Context<'_>, target: SeekTarget) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let current = current_position(&ctx.data().guild_map, guild_id)
+        .await
+        .ok_or_else(|| RuntimeError::User("Nothing is currently playing.".to_string()))?;
+
+    let (is_live, duration) = ctx
+        .data()
+        .guild_map
+        .read()
+        .await
+        .get(&guild_id.to_string())
+        .and_then(|state| state.playback_state.get_current_track().as_ref())
+        .map(|track| (track.is_live, track.duration))
+        .unwrap_or_default();
+
+    if is_live {
+        return Err(RuntimeError::User(
+            "Live streams can't be seeked. Playback always follows the broadcast.".to_string(),
+        ));
+    }
+
+    let position = match target {
+        SeekTarget::Absolute(position) => Some(position),
+        SeekTarget::Forward(offset) => current.checked_add(offset),
+        SeekTarget::Backward(offset) => Some(current.saturating_sub(offset)),
+    };
+
+    let position = match (position, duration) {
+        (Some(position), Some(duration)) if position < duration => position,
+        (Some(position), None) => position,
+        (_, Some(duration)) => {
+            return Err(RuntimeError::User(format!(
+                "That's past the end of the track, which is {} long.",
+                embeds::format_duration(duration)
+            )));
+        }
+        (None, None) => {
+            return Err(RuntimeError::User(
+                "That's past the end of the track.".to_string(),
+            ));
+        }
+    };
+
+    restart_stream(ctx.serenity_context(), ctx.data(), guild_id, position).await?;
+
+    let track = ctx
+        .data()
+        .guild_map
+        .read()
+        .await
+        .get(&guild_id.to_string())
+        .and_then(|state| state.playback_state.get_current_track().clone())
+        .ok_or(InternalError::BadGuildState)?;
+
+    trace!(?position, "Seeked.");
+    ctx.send(poise::CreateReply::default().embed(embeds::create_seek_embed(&track, position)))
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    if let Err(e) =
+        panel_actions::refresh_panel(ctx.serenity_context(), &ctx.data().guild_map, guild_id).await
+    {
+        error!(err = %e, "Failed to refresh now playing panel.");
     }
 
     Ok(())
@@ -87,27 +347,114 @@ pub async fn start_queue_playback(ctx: &Context<'_>) -> Result<(), RuntimeError>
 #[instrument(skip(ctx), fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
 pub async fn add_element_to_queue(
     ctx: &Context<'_>,
-    queue_element: QueueElement,
+    mut queue_element: QueueElement,
 ) -> Result<(), RuntimeError> {
     let guild_id = ctx
         .guild_id()
         .ok_or(InternalError::GuildInformationMissing)?;
 
+    let author_id = ctx.author().id;
+    queue_element.set_requester(author_id);
+
+    let settings = ctx
+        .data()
+        .guild_settings
+        .read()
+        .await
+        .get(&guild_id.to_string())
+        .cloned()
+        .unwrap_or_default();
+
+    // DJs are not held to the queue limits.
+    let is_dj = match ctx.author_member().await {
+        Some(member) => ctx
+            .guild()
+            .is_some_and(|guild| checks::is_dj(&guild, &member, &settings)),
+        None => false,
+    };
+
     let mut map_guard = ctx.data().guild_map.write().await;
     let guild_state = map_guard.entry(guild_id.to_string()).or_default();
-    guild_state.playback_state.enqueue(queue_element.clone());
+
+    // The longest track is the guild's to set, so it binds DJs too.
+    let n_overlong = settings
+        .drop_overlong_tracks(&mut queue_element)
+        .map_err(RuntimeError::User)?;
+
+    let n_left_out = if is_dj {
+        n_overlong
+    } else {
+        let (queued_tracks, queued_duration) = guild_state
+            .playback_state
+            .queued_tracks()
+            .filter(|(track, _)| track.requested_by == Some(author_id))
+            .fold((0, Duration::ZERO), |(n, total), (track, _)| {
+                (n + 1, total + track.duration.unwrap_or_default())
+            });
+
+        n_overlong
+            + settings
+                .fit_queue_limits(&mut queue_element, queued_tracks, queued_duration)
+                .map_err(RuntimeError::User)?
+    };
+
+    // The fair queue may place the element anywhere, so it is found again by counting the
+    // author's earlier requests of its first track.
+    let first_track_id = queue_element.tracks().next().map(|t| t.id.clone());
+    let is_first_track = |track: &Track| {
+        Some(&track.id) == first_track_id.as_ref() && track.requested_by == Some(author_id)
+    };
+    let n_earlier = guild_state
+        .playback_state
+        .queued_tracks()
+        .filter(|(track, _)| is_first_track(track))
+        .count();
+
+    guild_state
+        .playback_state
+        .enqueue(queue_element.clone(), settings.fair_queue);
+
+    let queue_position = guild_state
+        .playback_state
+        .queued_tracks()
+        .enumerate()
+        .filter(|(_, (track, _))| is_first_track(track))
+        .nth(n_earlier)
+        .map(|(position, _)| position);
 
     let is_playing = guild_state.playback_state.is_playing();
 
     drop(map_guard);
 
-    let embed = match queue_element {
-        QueueElement::Track(t) if is_playing => embeds::create_queued_track_embed(&t),
+    let eta = match queue_position.filter(|_| is_playing) {
+        Some(queue_position) => {
+            let current_position = current_position(&ctx.data().guild_map, guild_id)
+                .await
+                .unwrap_or_default();
+            ctx.data()
+                .guild_map
+                .read()
+                .await
+                .get(&guild_id.to_string())
+                .and_then(|state| state.time_until(queue_position, current_position))
+        }
+        None => None,
+    };
+
+    let mut embed = match queue_element {
+        QueueElement::Track(t) if is_playing => embeds::create_queued_track_embed(&t, eta),
         QueueElement::Track(t) => embeds::create_playing_track_embed(&t),
-        QueueElement::Playlist(p) if is_playing => embeds::create_queued_playlist_embed(&p),
+        QueueElement::Playlist(p) if is_playing => embeds::create_queued_playlist_embed(&p, eta),
         QueueElement::Playlist(p) => embeds::create_playing_playlist_embed(&p),
     };
 
+    if n_left_out > 0 {
+        trace!(n_left_out, "Tracks left out by the queue limits.");
+        embed = embed.footer(serenity_prelude::CreateEmbedFooter::new(format!(
+            "{n_left_out} track(s) were left out to stay within this server's limits."
+        )));
+    }
+
     ctx.send(poise::CreateReply::default().embed(embed))
         .await
         .map_err(DiscordError::Gateway)?;
@@ -120,16 +467,38 @@ pub async fn stop(ctx: &Context<'_>) -> Result<(), RuntimeError> {
         .guild_id()
         .ok_or(InternalError::GuildInformationMissing)?;
 
+    stop_guild_playback(ctx.serenity_context(), ctx.data(), guild_id).await?;
+
+    ctx.send(poise::CreateReply::default().embed(create_info_embed(
+        "Playback Stopped",
+        "The queue has been cleared and playback has been halted.",
+    )))
+    .await
+    .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+/// Clears the guild's queue and playback modes, drops what was prepared for the next track and
+/// halts the current one.
+#[instrument(skip(serenity_ctx, data))]
+pub async fn stop_guild_playback(
+    serenity_ctx: &serenity_prelude::Context,
+    data: &ServerState,
+    guild_id: GuildId,
+) -> Result<(), RuntimeError> {
     {
-        let mut map_guard = ctx.data().guild_map.write().await;
-        trace!(%guild_id, "Resetting guild state.");
+        let mut map_guard = data.guild_map.write().await;
+        trace!("Resetting guild state.");
         if let Some(state) = map_guard.get_mut(&guild_id.to_string()) {
             state.playback_state.reset();
         }
     }
 
+    data.stream_prefetcher.discard(&guild_id.to_string());
+
     trace!("Stopping current track.");
-    let handler = songbird::get(ctx.serenity_context())
+    let handler = songbird::get(serenity_ctx)
         .await
         .and_then(|manager| manager.get(guild_id));
 
@@ -141,14 +510,6 @@ pub async fn stop(ctx: &Context<'_>) -> Result<(), RuntimeError> {
     };
 
     handle.lock().await.stop();
-
-    ctx.send(poise::CreateReply::default().embed(create_info_embed(
-        "Playback Stopped",
-        "The queue has been cleared and playback has been halted.",
-    )))
-    .await
-    .map_err(DiscordError::Gateway)?;
-
     Ok(())
 }
 
@@ -178,6 +539,12 @@ pub async fn pause(ctx: &Context<'_>) -> Result<(), RuntimeError> {
     ctx.send(poise::CreateReply::default().embed(embeds::create_paused_embed(&current_track)))
         .await
         .map_err(DiscordError::Gateway)?;
+
+    if let Err(e) =
+        panel_actions::refresh_panel(ctx.serenity_context(), &ctx.data().guild_map, guild_id).await
+    {
+        error!(err = %e, "Failed to refresh now playing panel.");
+    }
     Ok(())
 }
 
@@ -210,9 +577,55 @@ pub async fn resume(ctx: &Context<'_>) -> Result<(), RuntimeError> {
     .await
     .map_err(DiscordError::Gateway)?;
 
+    if let Err(e) =
+        panel_actions::refresh_panel(ctx.serenity_context(), &ctx.data().guild_map, guild_id).await
+    {
+        error!(err = %e, "Failed to refresh now playing panel.");
+    }
+
     Ok(())
 }
 
+/// Checks that the author may skip `n` tracks, counting the current one. Skipping several tracks
+/// follows its own policy, and skips may need a vote. Returns `false` when the skip waits on more
+/// votes, after posting the tally.
+async fn authorize_skip(
+    ctx: &Context<'_>,
+    guild_id: GuildId,
+    n: usize,
+) -> Result<bool, RuntimeError> {
+    if n > 1 {
+        checks::enforce_policy(ctx, SKIP_SEVERAL).await?;
+    }
+
+    let voter = ctx
+        .author_member()
+        .await
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let vote =
+        vote_actions::cast_skip_vote(ctx.serenity_context(), ctx.data(), guild_id, &voter, n)
+            .await?;
+
+    if let SkipVote::Pending {
+        track,
+        votes,
+        needed,
+    } = vote
+    {
+        ctx.send(
+            poise::CreateReply::default()
+                .embed(embeds::create_skip_vote_embed(&track, votes, needed))
+                .components(vote_actions::vote_components(&track.id, votes, needed)),
+        )
+        .await
+        .map_err(DiscordError::Gateway)?;
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
 #[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default(), user_id = %ctx.author().id))]
 pub async fn skip(ctx: &Context<'_>, n: usize) -> Result<(), RuntimeError> {
     trace!("Skip executed with n={n}");
@@ -221,6 +634,10 @@ pub async fn skip(ctx: &Context<'_>, n: usize) -> Result<(), RuntimeError> {
         .guild_id()
         .ok_or(InternalError::GuildInformationMissing)?;
 
+    if !authorize_skip(ctx, guild_id, n).await? {
+        return Ok(());
+    }
+
     let mut map_guard = ctx.data().guild_map.write().await;
     let guild_state = map_guard
         .get_mut(&guild_id.to_string())
@@ -230,11 +647,7 @@ pub async fn skip(ctx: &Context<'_>, n: usize) -> Result<(), RuntimeError> {
         return Err(RuntimeError::User("The queue is empty.".to_string()));
     };
 
-    let mut skipped = 0;
-    for _ in 0..(n.saturating_sub(1)) {
-        guild_state.playback_state.dequeue();
-        skipped += 1;
-    }
+    let mut skipped = guild_state.playback_state.skip_tracks(n.saturating_sub(1));
 
     // Stop current audio to trigger the event handler
     _ = track_handle.stop(); // TODO: Handle better
@@ -280,42 +693,39 @@ pub async fn skip(ctx: &Context<'_>, n: usize) -> Result<(), RuntimeError> {
 
 #[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
 pub async fn show_queue(ctx: &Context<'_>) -> Result<(), RuntimeError> {
+    const PAGE_SIZE: usize = 10;
+
     let guild_id = ctx
         .guild_id()
         .ok_or(InternalError::GuildInformationMissing)?;
 
-    let queue_info = {
+    let (current_track, queued) = {
         let map_guard = ctx.data().guild_map.read().await;
-        let guild_state = map_guard
+        let playback_state = &map_guard
             .get(&guild_id.to_string())
-            .ok_or(InternalError::BadGuildState)?;
+            .ok_or(InternalError::BadGuildState)?
+            .playback_state;
 
-        Some((
-            guild_state.playback_state.next_items_queued(5),
-            guild_state.playback_state.number_of_tracks_queued(),
-            guild_state.playback_state.number_of_items_queued(),
-        ))
-    };
+        let queued = playback_state
+            .queued_tracks()
+            .map(|(track, playlist)| QueuedTrack {
+                track: track.clone(),
+                playlist_title: playlist.map(|p| p.title.clone()),
+            })
+            .collect::<Vec<_>>();
 
-    let Some((next_tracks, n_tracks, n_items)) = queue_info else {
-        return Err(RuntimeError::User("The queue is empty.".to_string()));
+        (playback_state.get_current_track().clone(), queued)
     };
 
-    if n_items == 0 {
+    if queued.is_empty() {
         return Err(RuntimeError::User("The queue is empty.".to_string()));
-    } else {
-        ctx.send(
-            poise::CreateReply::default().embed(embeds::create_queue_overview_embed(
-                &next_tracks,
-                n_tracks,
-                n_items,
-            )),
-        )
-        .await
-        .map_err(DiscordError::Gateway)?;
     }
 
-    Ok(())
+    pagination_actions::paginate(
+        ctx,
+        embeds::create_queue_embeds(current_track.as_ref(), &queued, PAGE_SIZE),
+    )
+    .await
 }
 
 #[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
@@ -349,3 +759,480 @@ pub async fn toggle_radio_mode(ctx: &Context<'_>) -> Result<(), RuntimeError> {
 
     Ok(())
 }
+
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default(), user_id = %ctx.author().id))]
+pub async fn previous(ctx: &Context<'_>) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let previous = {
+        let mut map_guard = ctx.data().guild_map.write().await;
+        let guild_state = map_guard
+            .get_mut(&guild_id.to_string())
+            .ok_or(InternalError::BadGuildState)?;
+
+        let Some(track_handle) = guild_state.playback_state.get_track_handle().clone() else {
+            return Err(RuntimeError::User(
+                "Nothing is currently playing.".to_string(),
+            ));
+        };
+
+        let previous = guild_state.playback_state.rewind().ok_or_else(|| {
+            RuntimeError::User("There is no previous track to go back to.".to_string())
+        })?;
+
+        // Stop current audio to trigger the event handler
+        _ = track_handle.stop();
+        previous
+    };
+
+    trace!(track = %previous, "Returning to previous track.");
+    ctx.send(poise::CreateReply::default().embed(embeds::create_previous_track_embed(&previous)))
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default(), user_id = %ctx.author().id))]
+pub async fn replay(ctx: &Context<'_>) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let current = {
+        let mut map_guard = ctx.data().guild_map.write().await;
+        let guild_state = map_guard
+            .get_mut(&guild_id.to_string())
+            .ok_or(InternalError::BadGuildState)?;
+
+        let Some(track_handle) = guild_state.playback_state.get_track_handle().clone() else {
+            return Err(RuntimeError::User(
+                "Nothing is currently playing.".to_string(),
+            ));
+        };
+
+        let current = guild_state
+            .playback_state
+            .replay()
+            .ok_or_else(|| RuntimeError::User("Nothing is currently playing.".to_string()))?;
+
+        // Stop current audio to trigger the event handler
+        _ = track_handle.stop();
+        current
+    };
+
+    trace!(track = %current, "Replaying current track.");
+    ctx.send(poise::CreateReply::default().embed(embeds::create_replay_track_embed(&current)))
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn show_history(ctx: &Context<'_>) -> Result<(), RuntimeError> {
+    const PAGE_SIZE: usize = 10;
+
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let history = {
+        let map_guard = ctx.data().guild_map.read().await;
+        map_guard
+            .get(&guild_id.to_string())
+            .map(|state| {
+                state
+                    .playback_state
+                    .get_history()
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+
+    if history.is_empty() {
+        return Err(RuntimeError::User(
+            "Nothing has been played yet.".to_string(),
+        ));
+    }
+
+    pagination_actions::paginate(ctx, embeds::create_history_embeds(&history, PAGE_SIZE)).await
+}
+
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn set_loop_mode(
+    ctx: &Context<'_>,
+    loop_mode: Option<LoopMode>,
+) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let (loop_mode, is_radio_on) = {
+        let mut map_guard = ctx.data().guild_map.write().await;
+        let guild_state = map_guard
+            .get_mut(&guild_id.to_string())
+            .ok_or(InternalError::GuildInformationMissing)?;
+
+        // Cycle through the modes when no explicit mode is requested.
+        let loop_mode =
+            loop_mode.unwrap_or_else(|| guild_state.playback_state.get_loop_mode().next());
+
+        guild_state.playback_state.set_loop_mode(loop_mode);
+        (
+            loop_mode,
+            guild_state.playback_state.is_radio_mode_enabled(),
+        )
+    };
+
+    trace!(%loop_mode, "Loop mode updated.");
+    ctx.send(
+        poise::CreateReply::default().embed(embeds::create_loop_embed(loop_mode, is_radio_on)),
+    )
+    .await
+    .map_err(DiscordError::Gateway)?;
+
+    if let Err(e) =
+        panel_actions::refresh_panel(ctx.serenity_context(), &ctx.data().guild_map, guild_id).await
+    {
+        error!(err = %e, "Failed to refresh now playing panel.");
+    }
+
+    Ok(())
+}
+
+/// Shows the guild's volume, or changes it for the current and upcoming tracks.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn set_volume(ctx: &Context<'_>, volume: Option<u8>) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let (volume, previous) = {
+        let mut map_guard = ctx.data().guild_map.write().await;
+        let guild_state = map_guard.get_mut(&guild_id.to_string()).ok_or_else(|| {
+            RuntimeError::User("I need to be in a voice channel to change the volume.".to_string())
+        })?;
+
+        let previous = guild_state.volume;
+        if let Some(volume) = volume {
+            guild_state.volume = volume.min(MAX_VOLUME);
+
+            if let Some(handle) = guild_state.playback_state.get_track_handle()
+                && let Err(e) = handle.set_volume(guild_state.volume_gain())
+            {
+                trace!(err = %e, "Track ended before its volume could be changed.");
+            }
+        }
+
+        (guild_state.volume, previous)
+    };
+
+    trace!(volume, previous, "Volume updated.");
+    ctx.send(poise::CreateReply::default().embed(embeds::create_volume_embed(volume, previous)))
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+/// Shows or changes how long consecutive tracks overlap. The current track keeps the timing it
+/// was started with.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn set_crossfade(ctx: &Context<'_>, seconds: Option<u8>) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let crossfade = {
+        let mut map_guard = ctx.data().guild_map.write().await;
+        let guild_state = map_guard.get_mut(&guild_id.to_string()).ok_or_else(|| {
+            RuntimeError::User(
+                "I need to be in a voice channel to change the crossfade.".to_string(),
+            )
+        })?;
+
+        if let Some(seconds) = seconds {
+            guild_state.crossfade = Duration::from_secs(seconds.into());
+        }
+
+        guild_state.crossfade
+    };
+
+    trace!(?crossfade, "Crossfade updated.");
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(embeds::create_crossfade_embed(crossfade, seconds.is_some())),
+    )
+    .await
+    .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn shuffle_queue(ctx: &Context<'_>) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let n_tracks = {
+        let mut map_guard = ctx.data().guild_map.write().await;
+        let guild_state = map_guard
+            .get_mut(&guild_id.to_string())
+            .ok_or(InternalError::BadGuildState)?;
+
+        guild_state.playback_state.shuffle_queue();
+        guild_state.playback_state.number_of_tracks_queued()
+    };
+
+    if n_tracks == 0 {
+        return Err(RuntimeError::User("The queue is empty.".to_string()));
+    }
+
+    ctx.send(poise::CreateReply::default().embed(create_info_embed(
+        "Queue Shuffled",
+        &format!("Shuffled {n_tracks} queued track(s)."),
+    )))
+    .await
+    .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+/// Converts a user facing, one-based track position into a queue index.
+fn queue_index(position: usize, n_tracks: usize) -> Result<usize, String> {
+    if n_tracks == 0 {
+        return Err("The queue is empty.".to_string());
+    }
+
+    if position == 0 || position > n_tracks {
+        return Err(format!(
+            "{position} is not a valid position. Please pick a position between 1 and {n_tracks}."
+        ));
+    }
+
+    Ok(position - 1)
+}
+
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn remove_track(ctx: &Context<'_>, position: usize) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let removed = {
+        let mut map_guard = ctx.data().guild_map.write().await;
+        let guild_state = map_guard
+            .get_mut(&guild_id.to_string())
+            .ok_or(InternalError::BadGuildState)?;
+
+        let n_tracks = guild_state.playback_state.number_of_tracks_queued();
+        let index = queue_index(position, n_tracks).map_err(RuntimeError::User)?;
+        guild_state
+            .playback_state
+            .remove_track(index)
+            .ok_or(InternalError::BadGuildState)?
+    };
+
+    trace!(track = %removed, position, "Removed track from queue.");
+    ctx.send(
+        poise::CreateReply::default().embed(embeds::create_removed_track_embed(&removed, position)),
+    )
+    .await
+    .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn move_track(ctx: &Context<'_>, from: usize, to: usize) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let moved = {
+        let mut map_guard = ctx.data().guild_map.write().await;
+        let guild_state = map_guard
+            .get_mut(&guild_id.to_string())
+            .ok_or(InternalError::BadGuildState)?;
+
+        let n_tracks = guild_state.playback_state.number_of_tracks_queued();
+        let (from_index, to_index) = (
+            queue_index(from, n_tracks).map_err(RuntimeError::User)?,
+            queue_index(to, n_tracks).map_err(RuntimeError::User)?,
+        );
+        guild_state
+            .playback_state
+            .move_track(from_index, to_index)
+            .ok_or(InternalError::BadGuildState)?
+    };
+
+    trace!(track = %moved, from, to, "Moved track within queue.");
+    ctx.send(
+        poise::CreateReply::default().embed(embeds::create_moved_track_embed(&moved, from, to)),
+    )
+    .await
+    .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn swap_tracks(
+    ctx: &Context<'_>,
+    first: usize,
+    second: usize,
+) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    if first == second {
+        return Err(RuntimeError::User(
+            "Please pick two different positions to swap.".to_string(),
+        ));
+    }
+
+    let (first_track, second_track) = {
+        let mut map_guard = ctx.data().guild_map.write().await;
+        let guild_state = map_guard
+            .get_mut(&guild_id.to_string())
+            .ok_or(InternalError::BadGuildState)?;
+
+        let n_tracks = guild_state.playback_state.number_of_tracks_queued();
+        let (first_index, second_index) = (
+            queue_index(first, n_tracks).map_err(RuntimeError::User)?,
+            queue_index(second, n_tracks).map_err(RuntimeError::User)?,
+        );
+        guild_state
+            .playback_state
+            .swap_tracks(first_index, second_index)
+            .ok_or(InternalError::BadGuildState)?
+    };
+
+    trace!(first, second, "Swapped tracks within queue.");
+    ctx.send(
+        poise::CreateReply::default().embed(embeds::create_swapped_tracks_embed(
+            (&first_track, first),
+            (&second_track, second),
+        )),
+    )
+    .await
+    .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn clear_queue(ctx: &Context<'_>) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let n_cleared = {
+        let mut map_guard = ctx.data().guild_map.write().await;
+        let guild_state = map_guard
+            .get_mut(&guild_id.to_string())
+            .ok_or(InternalError::BadGuildState)?;
+
+        let n_tracks = guild_state.playback_state.number_of_tracks_queued();
+        guild_state.playback_state.clear_queue();
+        n_tracks
+    };
+
+    if n_cleared == 0 {
+        return Err(RuntimeError::User(
+            "The queue is already empty.".to_string(),
+        ));
+    }
+
+    ctx.send(poise::CreateReply::default().embed(create_info_embed(
+        "Queue Cleared",
+        &format!("Removed {n_cleared} track(s). The current track will keep playing."),
+    )))
+    .await
+    .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default(), user_id = %ctx.author().id))]
+pub async fn skip_to(ctx: &Context<'_>, position: usize) -> Result<(), RuntimeError> {
+    trace!("Skip to executed with position={position}");
+
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    // Checked before voting so that a bad position neither casts nor uses up a vote.
+    {
+        let map_guard = ctx.data().guild_map.read().await;
+        let guild_state = map_guard
+            .get(&guild_id.to_string())
+            .ok_or(InternalError::BadGuildState)?;
+
+        if guild_state.playback_state.get_track_handle().is_none() {
+            return Err(RuntimeError::User(
+                "Nothing is currently playing.".to_string(),
+            ));
+        }
+
+        let n_tracks = guild_state.playback_state.number_of_tracks_queued();
+        queue_index(position, n_tracks).map_err(RuntimeError::User)?;
+    }
+
+    // Jumping to the first queued track only skips the current one.
+    if !authorize_skip(ctx, guild_id, position).await? {
+        return Ok(());
+    }
+
+    let (next, remaining_queued) = {
+        let mut map_guard = ctx.data().guild_map.write().await;
+        let guild_state = map_guard
+            .get_mut(&guild_id.to_string())
+            .ok_or(InternalError::BadGuildState)?;
+
+        let Some(track_handle) = guild_state.playback_state.get_track_handle().clone() else {
+            return Err(RuntimeError::User(
+                "Nothing is currently playing.".to_string(),
+            ));
+        };
+
+        let n_tracks = guild_state.playback_state.number_of_tracks_queued();
+        let index = queue_index(position, n_tracks).map_err(RuntimeError::User)?;
+        guild_state.playback_state.skip_tracks(index);
+
+        let next = guild_state
+            .playback_state
+            .peek_next_track()
+            .cloned()
+            .ok_or(InternalError::BadGuildState)?;
+
+        // Stop current audio to trigger the event handler
+        _ = track_handle.stop();
+
+        (
+            next,
+            guild_state
+                .playback_state
+                .number_of_tracks_queued()
+                .saturating_sub(1),
+        )
+    };
+
+    ctx.send(
+        poise::CreateReply::default().embed(embeds::create_skip_to_embed(
+            &next,
+            position,
+            remaining_queued,
+        )),
+    )
+    .await
+    .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
@@ -0,0 +1,245 @@
+use std::time::Duration;
+
+use poise::serenity_prelude::{
+    AutocompleteChoice, ButtonStyle, ComponentInteractionCollector, ComponentInteractionDataKind,
+    CreateActionRow, CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+};
+use tracing::{instrument, trace, warn};
+
+use crate::{
+    embeds,
+    models::{DiscordError, QueueElement, RuntimeError, Suggestion},
+    server::Context,
+};
+
+/// How many results members pick from. Discord shows at most 10 embeds per message.
+pub const N_SEARCH_RESULTS: u32 = 5;
+
+/// How long the requester has to pick a result.
+const PICK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Discord caps the labels and descriptions of select menu options, and the names and values of
+/// autocomplete choices, at 100 characters.
+const MAX_OPTION_LENGTH: usize = 100;
+
+/// How many suggestions are offered while a query is typed.
+const N_SUGGESTIONS: u32 = 10;
+
+/// Shortest query suggestions are looked up for.
+const MIN_SUGGESTION_QUERY_LENGTH: usize = 3;
+
+/// Shortens `text` to fit into a select menu option.
+fn fit_option(text: &str) -> String {
+    if text.chars().count() <= MAX_OPTION_LENGTH {
+        return text.to_string();
+    }
+
+    let mut fitted = text.chars().take(MAX_OPTION_LENGTH - 1).collect::<String>();
+    fitted.push('…');
+    fitted
+}
+
+/// Custom IDs of the picker's components, prefixed with the invocation ID to ignore
+/// interactions with other messages.
+struct PickerIds {
+    pick: String,
+    cancel: String,
+}
+
+impl PickerIds {
+    fn new(ctx_id: &str) -> Self {
+        Self {
+            pick: format!("{ctx_id}pick"),
+            cancel: format!("{ctx_id}cancel"),
+        }
+    }
+
+    fn components(&self, results: &[QueueElement]) -> Vec<CreateActionRow> {
+        let options = results
+            .iter()
+            .enumerate()
+            .map(|(i, result)| {
+                let title = match result {
+                    QueueElement::Track(t) => &t.title,
+                    QueueElement::Playlist(p) => &p.title,
+                };
+                CreateSelectMenuOption::new(
+                    fit_option(&format!("{}. {title}", i + 1)),
+                    i.to_string(),
+                )
+                .description(fit_option(&embeds::format_search_result_details(result)))
+            })
+            .collect();
+
+        let pick = CreateSelectMenu::new(&self.pick, CreateSelectMenuKind::String { options })
+            .placeholder("Pick a result to queue");
+        let cancel = CreateButton::new(&self.cancel)
+            .label("Cancel")
+            .style(ButtonStyle::Secondary);
+
+        vec![
+            CreateActionRow::SelectMenu(pick),
+            CreateActionRow::Buttons(vec![cancel]),
+        ]
+    }
+}
+
+/// Shows `results` and lets the author pick one from a select menu. Returns `None` when they
+/// cancel or let the picker time out.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default(), n_results = results.len()))]
+pub async fn pick_result(
+    ctx: &Context<'_>,
+    mut results: Vec<QueueElement>,
+) -> Result<Option<QueueElement>, RuntimeError> {
+    let ctx_id = ctx.id().to_string();
+    let ids = PickerIds::new(&ctx_id);
+
+    let reply = ctx
+        .send(
+            poise::CreateReply {
+                embeds: results
+                    .iter()
+                    .enumerate()
+                    .map(|(i, result)| embeds::create_search_result_embed(i + 1, result))
+                    .collect(),
+                ..Default::default()
+            }
+            .components(ids.components(&results)),
+        )
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    let author_id = ctx.author().id;
+    let press = ComponentInteractionCollector::new(ctx.serenity_context())
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id))
+        .author_id(author_id)
+        .timeout(PICK_TIMEOUT)
+        .await;
+
+    let Some(press) = press else {
+        trace!("Search picker timed out.");
+        reply
+            .edit(
+                *ctx,
+                poise::CreateReply::default()
+                    .embed(embeds::create_search_closed_embed(
+                        "No result was picked in time.",
+                    ))
+                    .components(vec![]),
+            )
+            .await
+            .map_err(DiscordError::Gateway)?;
+        return Ok(None);
+    };
+
+    let picked = match &press.data.kind {
+        ComponentInteractionDataKind::StringSelect { values }
+            if press.data.custom_id == ids.pick =>
+        {
+            values
+                .first()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|i| *i < results.len())
+        }
+        _ => None,
+    };
+
+    let closing_embeds = match picked {
+        Some(i) => vec![embeds::create_search_result_embed(i + 1, &results[i])],
+        None => vec![embeds::create_search_closed_embed(
+            "The search was cancelled.",
+        )],
+    };
+    trace!(?picked, "Search picker closed.");
+
+    press
+        .create_response(
+            ctx.serenity_context(),
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embeds(closing_embeds)
+                    .components(vec![]),
+            ),
+        )
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(picked.map(|i| results.swap_remove(i)))
+}
+
+/// Suggests results for the search query `partial` as it is typed. Each suggestion carries the
+/// result's URL, so picking one plays that exact result. Lookups wait for the member to pause
+/// typing and are cached, and failed lookups suggest nothing.
+#[instrument(skip(ctx), fields(user_id = %ctx.author().id))]
+pub async fn suggest_results(
+    ctx: &Context<'_>,
+    partial: &str,
+    playlists: bool,
+) -> Vec<AutocompleteChoice> {
+    let query = partial.trim();
+    if query.chars().count() < MIN_SUGGESTION_QUERY_LENGTH || url::Url::parse(query).is_ok() {
+        return vec![];
+    }
+
+    let suggestions = &ctx.data().search_suggestions;
+    let results = match suggestions.cached(query, playlists) {
+        Some(results) => {
+            trace!("Serving cached suggestions.");
+            results
+        }
+        None => {
+            if !suggestions.debounce(ctx.author().id).await {
+                trace!("Lookup superseded by a newer keystroke.");
+                return vec![];
+            }
+
+            let providers = &ctx.data().metadata_providers;
+            let found = if playlists {
+                providers
+                    .search_playlists(query, N_SUGGESTIONS)
+                    .await
+                    .map(|results| {
+                        results
+                            .into_iter()
+                            .map(|p| (format!("{} — {}", p.title, p.artist), p.url))
+                            .collect::<Vec<_>>()
+                    })
+            } else {
+                providers
+                    .search_tracks(query, N_SUGGESTIONS)
+                    .await
+                    .map(|results| {
+                        results
+                            .into_iter()
+                            .map(|t| (format!("{} — {}", t.title, t.artist), t.url))
+                            .collect()
+                    })
+            };
+
+            let results = match found {
+                Ok(found) => found
+                    .into_iter()
+                    // A truncated URL would point somewhere else.
+                    .filter(|(_, url)| url.len() <= MAX_OPTION_LENGTH)
+                    .map(|(name, url)| Suggestion {
+                        name: fit_option(&name),
+                        url,
+                    })
+                    .collect::<Vec<_>>(),
+                Err(e) => {
+                    warn!(err = %e, "Failed to look up suggestions.");
+                    return vec![];
+                }
+            };
+
+            suggestions.store(query, playlists, results.clone());
+            results
+        }
+    };
+
+    results
+        .into_iter()
+        .map(|s| AutocompleteChoice::new(s.name, s.url))
+        .collect()
+}
@@ -0,0 +1,213 @@
+use std::{collections::HashMap, str::FromStr, time::Duration};
+
+use poise::serenity_prelude::{self, CreateMessage, GuildId};
+use tracing::{error, info, instrument, trace, warn};
+
+use crate::{
+    actions::{channel_actions, playback_actions},
+    embeds,
+    models::{DiscordError, GuildSnapshot, GuildState, InternalError, RuntimeError, Track},
+    server::{Context, ServerState},
+};
+
+/// Captures every guild with something playing or queued, including the current track position.
+/// Tracks whose links expire are left out.
+#[instrument(skip_all, fields(n_guilds = guild_map.len()))]
+pub async fn capture_snapshots(guild_map: &HashMap<String, GuildState>) -> Vec<GuildSnapshot> {
+    let mut snapshots = Vec::new();
+
+    for (guild_key, guild_state) in guild_map {
+        let playback_state = &guild_state.playback_state;
+        if playback_state.get_current_track().is_none()
+            && playback_state.number_of_tracks_queued() == 0
+        {
+            continue;
+        }
+
+        let Ok(guild_id) = GuildId::from_str(guild_key) else {
+            warn!(%guild_key, "Skipping snapshot of guild with malformed ID.");
+            continue;
+        };
+
+        let mut position = match playback_state.get_track_handle() {
+            Some(handle) => handle
+                .get_info()
+                .await
+                .map(|info| guild_state.track_position(info.position))
+                .unwrap_or_default(),
+            None => Default::default(),
+        };
+
+        let mut guild_state = guild_state.clone();
+        let n_dropped = guild_state
+            .playback_state
+            .drop_tracks(Track::has_expiring_link);
+        let playback_state = &guild_state.playback_state;
+        if playback_state.get_current_track().is_none() {
+            position = Duration::ZERO;
+        }
+
+        if n_dropped > 0 {
+            info!(%guild_id, n_dropped, "Left tracks with expiring links out of snapshot.");
+        }
+        if playback_state.get_current_track().is_none()
+            && playback_state.number_of_tracks_queued() == 0
+        {
+            continue;
+        }
+
+        snapshots.push(GuildSnapshot {
+            guild_id,
+            guild_state,
+            position,
+            n_dropped,
+        });
+    }
+
+    snapshots
+}
+
+/// Loads the sessions saved by the previous run. Depending on configuration they are either
+/// resumed straight away or offered to each guild through `/resume-session`.
+#[instrument(skip_all)]
+pub async fn load_sessions(serenity_ctx: serenity_prelude::Context, data: ServerState) {
+    let vars = &data.configuration_variables;
+    let snapshots = match GuildSnapshot::take_all(vars.session_file()) {
+        Ok(snapshots) => snapshots,
+        Err(e) => {
+            error!(err = %e, "Failed to load saved sessions.");
+            return;
+        }
+    };
+
+    info!(n_sessions = snapshots.len(), "Loaded saved sessions.");
+
+    for snapshot in snapshots {
+        let guild_id = snapshot.guild_id;
+
+        if vars.auto_resume_sessions() {
+            // A session that couldn't be resumed is kept, so it is saved again on shutdown.
+            if let Err(e) = restore_session(&serenity_ctx, &data, snapshot.clone()).await {
+                error!(%guild_id, err = %e, "Failed to automatically resume session.");
+                data.pending_sessions
+                    .write()
+                    .await
+                    .insert(guild_id.to_string(), snapshot);
+            }
+            continue;
+        }
+
+        let Some(text_channel_id) = snapshot.guild_state.text_channel_id else {
+            trace!(%guild_id, "Session has no text channel to announce in. Discarding.");
+            continue;
+        };
+
+        let embed = embeds::create_session_available_embed(&snapshot);
+        data.pending_sessions
+            .write()
+            .await
+            .insert(guild_id.to_string(), snapshot);
+
+        if let Err(e) = text_channel_id
+            .send_message(&serenity_ctx, CreateMessage::default().embed(embed))
+            .await
+        {
+            error!(%guild_id, err = %e, "Failed to announce resumable session.");
+        }
+    }
+}
+
+/// Rejoins the snapshot's voice channel and resumes its queue without user interaction.
+#[instrument(skip_all, fields(guild_id = %snapshot.guild_id))]
+async fn restore_session(
+    serenity_ctx: &serenity_prelude::Context,
+    data: &ServerState,
+    snapshot: GuildSnapshot,
+) -> Result<(), RuntimeError> {
+    let guild_id = snapshot.guild_id;
+    let (Some(voice_channel_id), Some(text_channel_id)) = (
+        snapshot.guild_state.voice_channel_id,
+        snapshot.guild_state.text_channel_id,
+    ) else {
+        return Err(InternalError::BadGuildState.into());
+    };
+
+    channel_actions::join_voice_channel(
+        serenity_ctx,
+        data,
+        guild_id,
+        voice_channel_id,
+        text_channel_id,
+    )
+    .await?;
+
+    let embed = embeds::create_session_restored_embed(&snapshot);
+    let position = apply_snapshot(data, snapshot).await;
+    playback_actions::start_guild_playback(serenity_ctx, data, guild_id, position).await?;
+
+    text_channel_id
+        .send_message(serenity_ctx, CreateMessage::default().embed(embed))
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+/// Resumes the guild's saved session in the author's voice channel.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn resume_session(ctx: &Context<'_>) -> Result<(), RuntimeError> {
+    let guild_key = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?
+        .to_string();
+
+    if ctx.data().guild_map.read().await.contains_key(&guild_key) {
+        return Err(RuntimeError::User(
+            "Playback is already active. Please stop it before resuming the previous session."
+                .to_string(),
+        ));
+    }
+
+    // The snapshot stays pending until the session is playing again, so a failed attempt can
+    // be retried.
+    let snapshot = ctx
+        .data()
+        .pending_sessions
+        .read()
+        .await
+        .get(&guild_key)
+        .cloned()
+        .ok_or_else(|| RuntimeError::User("There is no previous session to resume.".to_string()))?;
+
+    channel_actions::join_channel(*ctx).await?;
+
+    let guild_id = snapshot.guild_id;
+    let embed = embeds::create_session_restored_embed(&snapshot);
+    let position = apply_snapshot(ctx.data(), snapshot).await;
+    playback_actions::start_guild_playback(ctx.serenity_context(), ctx.data(), guild_id, position)
+        .await?;
+    ctx.data().pending_sessions.write().await.remove(&guild_key);
+
+    ctx.send(poise::CreateReply::default().embed(embed))
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+/// Replaces the freshly joined guild's playback state with the saved one. Returns the position
+/// to resume the current track from.
+async fn apply_snapshot(data: &ServerState, snapshot: GuildSnapshot) -> Duration {
+    let guild_key = snapshot.guild_id.to_string();
+    let position = snapshot.position;
+    let restored = snapshot.into_guild_state();
+
+    if let Some(guild_state) = data.guild_map.write().await.get_mut(&guild_key) {
+        guild_state.playback_state = restored.playback_state;
+        guild_state.volume = restored.volume;
+        guild_state.filters = restored.filters;
+        guild_state.crossfade = restored.crossfade;
+    }
+
+    position
+}
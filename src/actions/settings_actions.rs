@@ -0,0 +1,222 @@
+use poise::serenity_prelude::RoleId;
+use std::time::Duration;
+use tracing::{info, instrument};
+
+use crate::{
+    embeds,
+    models::{
+        CommandPolicy, DiscordError, GuildSettings, InternalError, MAX_VOLUME, RuntimeError,
+        SKIP_SEVERAL,
+    },
+    server::{Context, ServerState},
+};
+
+/// Sets the volume new sessions in the guild start at and persists it.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn set_default_volume(ctx: &Context<'_>, volume: u8) -> Result<(), RuntimeError> {
+    let volume = volume.min(MAX_VOLUME);
+    update_settings(ctx, |settings| settings.default_volume = volume).await?;
+
+    info!(volume, "Default volume updated.");
+    ctx.send(poise::CreateReply::default().embed(embeds::create_default_volume_embed(volume)))
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+/// Sets the share of listeners whose votes are needed to skip a track and persists it. `None`
+/// turns vote-skip off.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn set_skip_vote_share(ctx: &Context<'_>, share: Option<u8>) -> Result<(), RuntimeError> {
+    update_settings(ctx, |settings| settings.skip_vote_share = share).await?;
+
+    info!(?share, "Skip vote share updated.");
+    ctx.send(poise::CreateReply::default().embed(embeds::create_skip_vote_settings_embed(share)))
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+/// Updates the queue limits and persists them. A limit left out stays as it is, and a
+/// zero removes it.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn set_queue_limits(
+    ctx: &Context<'_>,
+    tracks: Option<usize>,
+    minutes: Option<u64>,
+    track_minutes: Option<u64>,
+) -> Result<(), RuntimeError> {
+    let as_limit = |minutes: u64| (minutes > 0).then(|| Duration::from_secs(minutes * 60));
+
+    let settings = update_settings(ctx, |settings| {
+        if let Some(tracks) = tracks {
+            settings.max_queued_tracks = (tracks > 0).then_some(tracks);
+        }
+        if let Some(minutes) = minutes {
+            settings.max_queued_duration = as_limit(minutes);
+        }
+        if let Some(track_minutes) = track_minutes {
+            settings.max_track_length = as_limit(track_minutes);
+        }
+        settings.clone()
+    })
+    .await?;
+
+    info!(
+        max_tracks = ?settings.max_queued_tracks,
+        max_duration = ?settings.max_queued_duration,
+        max_track_length = ?settings.max_track_length,
+        "Queue limits updated."
+    );
+    ctx.send(poise::CreateReply::default().embed(embeds::create_queue_limits_embed(&settings)))
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+/// Turns the fair queue on or off and persists it, toggling it when no state is given.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn set_fair_queue(ctx: &Context<'_>, enabled: Option<bool>) -> Result<(), RuntimeError> {
+    let enabled = update_settings(ctx, |settings| {
+        settings.fair_queue = enabled.unwrap_or(!settings.fair_queue);
+        settings.fair_queue
+    })
+    .await?;
+
+    info!(enabled, "Fair queue updated.");
+    ctx.send(poise::CreateReply::default().embed(embeds::create_fair_queue_embed(enabled)))
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+/// Sets the role whose members count as DJs and persists it. `None` falls back to any role named
+/// DJ.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn set_dj_role(ctx: &Context<'_>, role: Option<RoleId>) -> Result<(), RuntimeError> {
+    update_settings(ctx, |settings| settings.dj_role = role).await?;
+
+    info!(?role, "DJ role updated.");
+    ctx.send(poise::CreateReply::default().embed(embeds::create_dj_role_embed(role)))
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+/// Overrides who may run `command` and persists it. `None` restores the default policy.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn set_command_policy(
+    ctx: &Context<'_>,
+    command: &str,
+    policy: Option<CommandPolicy>,
+) -> Result<(), RuntimeError> {
+    let command = command.trim().trim_start_matches('/').to_lowercase();
+    if !policy_commands(ctx).contains(&command) {
+        return Err(RuntimeError::User(format!(
+            "`{command}` is not a command whose policy can be changed."
+        )));
+    }
+
+    let policy = update_settings(ctx, |settings| {
+        match policy {
+            Some(policy) => settings.command_policies.insert(command.clone(), policy),
+            None => settings.command_policies.remove(&command),
+        };
+        settings.policy(&command)
+    })
+    .await?;
+
+    info!(command, %policy, "Command policy updated.");
+    ctx.send(
+        poise::CreateReply::default().embed(embeds::create_command_policy_embed(
+            &command,
+            policy,
+            policy == CommandPolicy::default_for(&command),
+        )),
+    )
+    .await
+    .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn list_command_policies(ctx: &Context<'_>) -> Result<(), RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let settings = ctx
+        .data()
+        .guild_settings
+        .read()
+        .await
+        .get(&guild_id.to_string())
+        .cloned()
+        .unwrap_or_default();
+
+    let policies = policy_commands(ctx)
+        .into_iter()
+        .map(|command| {
+            let policy = settings.policy(&command);
+            (command, policy)
+        })
+        .collect::<Vec<_>>();
+
+    ctx.send(
+        poise::CreateReply::default().embed(embeds::create_command_policies_embed(
+            settings.dj_role,
+            &policies,
+        )),
+    )
+    .await
+    .map_err(DiscordError::Gateway)?;
+
+    Ok(())
+}
+
+/// Qualified names of the commands a policy can be set for, in alphabetical order. Commands
+/// reserved to administrators are left out.
+pub fn policy_commands(ctx: &Context<'_>) -> Vec<String> {
+    fn collect(commands: &[poise::Command<ServerState, RuntimeError>], names: &mut Vec<String>) {
+        for command in commands {
+            if !command.subcommands.is_empty() {
+                collect(&command.subcommands, names);
+            } else if command.required_permissions.is_empty() {
+                names.push(command.qualified_name.clone());
+            }
+        }
+    }
+
+    let mut names = vec![SKIP_SEVERAL.to_string()];
+    collect(&ctx.framework().options().commands, &mut names);
+    names.sort();
+    names
+}
+
+/// Applies `edit` to the guild's settings and persists them.
+async fn update_settings<T>(
+    ctx: &Context<'_>,
+    edit: impl FnOnce(&mut GuildSettings) -> T,
+) -> Result<T, RuntimeError> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let mut settings_guard = ctx.data().guild_settings.write().await;
+    let result = edit(settings_guard.entry(guild_id.to_string()).or_default());
+
+    // Saved under the lock so concurrent changes are written in order.
+    GuildSettings::save_all(
+        ctx.data().configuration_variables.guild_settings_file(),
+        &settings_guard,
+    )
+    .map_err(InternalError::Settings)?;
+
+    Ok(result)
+}
@@ -0,0 +1,215 @@
+use poise::serenity_prelude::{
+    self, ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton,
+    CreateInteractionResponse, CreateInteractionResponseMessage, GuildId, Member,
+};
+use tracing::{instrument, trace};
+
+use crate::{
+    checks, embeds,
+    models::{DiscordError, InternalError, RuntimeError, Track},
+    server::ServerState,
+};
+
+/// Prefix of the vote button's custom ID, followed by the ID of the track being voted on.
+pub const VOTE_SKIP_ID_PREFIX: &str = "vote_skip:";
+
+/// Whether a skip may go ahead.
+#[derive(Debug)]
+pub enum SkipVote {
+    /// Vote-skip is off, or the member requested the track or is a DJ.
+    Bypassed,
+    /// The member's vote carried the skip.
+    Passed,
+    /// More votes are needed before the track is skipped.
+    Pending {
+        track: Track,
+        votes: usize,
+        needed: usize,
+    },
+}
+
+/// Counts `voter`'s vote to skip the current track when the guild requires votes. Skipping more
+/// than the current track is left to DJs.
+#[instrument(skip_all, fields(guild_id = %guild_id, user_id = %voter.user.id))]
+pub async fn cast_skip_vote(
+    serenity_ctx: &serenity_prelude::Context,
+    data: &ServerState,
+    guild_id: GuildId,
+    voter: &Member,
+    n_tracks: usize,
+) -> Result<SkipVote, RuntimeError> {
+    let guild_key = guild_id.to_string();
+
+    let Some(settings) = data.guild_settings.read().await.get(&guild_key).cloned() else {
+        return Ok(SkipVote::Bypassed);
+    };
+
+    let (listeners, is_dj) = {
+        let bot_id = serenity_ctx.cache.current_user().id;
+        let guild = serenity_ctx
+            .cache
+            .guild(guild_id)
+            .ok_or(InternalError::GuildInformationMissing)?;
+        (
+            checks::human_listener_ids(&guild, bot_id).unwrap_or_default(),
+            checks::is_dj(&guild, voter, &settings),
+        )
+    };
+
+    let Some(needed) = settings.skip_votes_needed(listeners.len()) else {
+        return Ok(SkipVote::Bypassed);
+    };
+
+    if is_dj {
+        trace!("DJ bypassed the vote.");
+        return Ok(SkipVote::Bypassed);
+    }
+
+    let mut map_guard = data.guild_map.write().await;
+    let guild_state = map_guard
+        .get_mut(&guild_key)
+        .ok_or_else(|| RuntimeError::User("Nothing is currently playing.".to_string()))?;
+
+    let Some(track) = guild_state.playback_state.get_current_track().clone() else {
+        return Err(RuntimeError::User(
+            "Nothing is currently playing.".to_string(),
+        ));
+    };
+
+    let is_requester = track.requested_by == Some(voter.user.id);
+    if n_tracks > 1 {
+        return Err(RuntimeError::User(
+            "Vote-skip is on, so only DJs can skip several tracks at once.".to_string(),
+        ));
+    } else if is_requester {
+        trace!("Requester bypassed the vote.");
+        return Ok(SkipVote::Bypassed);
+    }
+
+    let skip_votes = guild_state.playback_state.skip_votes_mut();
+    let votes = skip_votes.cast(voter.user.id, &listeners);
+    trace!(
+        votes,
+        needed,
+        n_listeners = listeners.len(),
+        "Skip vote cast."
+    );
+
+    if votes >= needed {
+        skip_votes.clear();
+        Ok(SkipVote::Passed)
+    } else {
+        Ok(SkipVote::Pending {
+            track,
+            votes,
+            needed,
+        })
+    }
+}
+
+/// The button listeners press to add their vote, labelled with the running tally.
+pub fn vote_components(track_id: &str, votes: usize, needed: usize) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{VOTE_SKIP_ID_PREFIX}{track_id}"))
+            .label(format!("Vote to skip ({votes}/{needed})"))
+            .emoji('⏭')
+            .style(ButtonStyle::Primary),
+    ])]
+}
+
+/// Skips the current track once a vote has passed or been bypassed.
+async fn skip_current_track(data: &ServerState, guild_id: GuildId) {
+    if let Some(guild_state) = data.guild_map.write().await.get_mut(&guild_id.to_string())
+        && let Some(track_handle) = guild_state.playback_state.get_track_handle().clone()
+    {
+        guild_state.playback_state.skip_tracks(0);
+        _ = track_handle.stop();
+    }
+}
+
+/// Handles a press of a vote button, enforcing the slash command voice channel rules.
+#[instrument(skip_all, fields(guild_id = ?interaction.guild_id, user_id = %interaction.user.id))]
+pub async fn handle_interaction(
+    serenity_ctx: &serenity_prelude::Context,
+    data: &ServerState,
+    interaction: &ComponentInteraction,
+) -> Result<(), RuntimeError> {
+    let Some(track_id) = interaction.data.custom_id.strip_prefix(VOTE_SKIP_ID_PREFIX) else {
+        return Ok(());
+    };
+
+    let guild_id = interaction
+        .guild_id
+        .ok_or(InternalError::GuildInformationMissing)?;
+    let voter = interaction
+        .member
+        .as_ref()
+        .ok_or(InternalError::GuildInformationMissing)?;
+
+    let rules = {
+        let bot_id = serenity_ctx.cache.current_user().id;
+        let guild = serenity_ctx
+            .cache
+            .guild(guild_id)
+            .ok_or(InternalError::GuildInformationMissing)?;
+        checks::voice_channel_rules(&guild, voter.user.id, bot_id)
+    };
+
+    let is_current = data
+        .guild_map
+        .read()
+        .await
+        .get(&guild_id.to_string())
+        .and_then(|state| state.playback_state.get_current_track().as_ref())
+        .is_some_and(|track| track.id == track_id);
+
+    let outcome = match rules {
+        Err(msg) => Err(RuntimeError::User(msg)),
+        Ok(()) if !is_current => Err(RuntimeError::User(
+            "This vote is for a track that is no longer playing.".to_string(),
+        )),
+        Ok(()) => cast_skip_vote(serenity_ctx, data, guild_id, voter, 1).await,
+    };
+
+    let response = match &outcome {
+        Ok(SkipVote::Pending {
+            track,
+            votes,
+            needed,
+        }) => CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .embed(embeds::create_skip_vote_embed(track, *votes, *needed))
+                .components(vote_components(&track.id, *votes, *needed)),
+        ),
+        Ok(SkipVote::Passed | SkipVote::Bypassed) => CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .embed(embeds::create_skip_vote_passed_embed())
+                .components(vec![]),
+        ),
+        Err(e) => {
+            let msg = match e {
+                RuntimeError::User(msg) => msg.as_str(),
+                _ => "An unexpected internal error occurred while processing your request.",
+            };
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .embed(embeds::create_error_embed(msg))
+                    .ephemeral(true),
+            )
+        }
+    };
+
+    interaction
+        .create_response(serenity_ctx, response)
+        .await
+        .map_err(DiscordError::Gateway)?;
+
+    match outcome {
+        Ok(SkipVote::Passed | SkipVote::Bypassed) => {
+            skip_current_track(data, guild_id).await;
+            Ok(())
+        }
+        Ok(SkipVote::Pending { .. }) | Err(RuntimeError::User(_)) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
@@ -1,29 +1,65 @@
+use poise::serenity_prelude::{Guild, Member, UserId};
+use std::collections::HashSet;
 use tracing::{instrument, trace};
 
 use crate::{
-    models::{InternalError, RuntimeError},
+    models::{CommandPolicy, GuildSettings, InternalError, RuntimeError, SKIP_SEVERAL},
     server::Context,
 };
 
+/// Enforces the guild's policy for the invoked command. Runs before every command.
+#[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
+pub async fn command_policy(ctx: Context<'_>) -> Result<bool, RuntimeError> {
+    enforce_policy(&ctx, &ctx.command().qualified_name)
+        .await
+        .map(|_| true)
+}
+
+/// Enforces the guild's policy for `command`, for commands whose policy depends on their
+/// arguments.
+pub async fn enforce_policy(ctx: &Context<'_>, command: &str) -> Result<(), RuntimeError> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let settings = ctx
+        .data()
+        .guild_settings
+        .read()
+        .await
+        .get(&guild_id.to_string())
+        .cloned()
+        .unwrap_or_default();
+
+    if settings.policy(command) == CommandPolicy::Everyone {
+        return Ok(());
+    }
+
+    let member = ctx
+        .author_member()
+        .await
+        .ok_or(InternalError::GuildInformationMissing)?;
+    let bot_id = ctx.framework().bot_id;
+
+    let result = {
+        let guild = ctx.guild().ok_or(InternalError::GuildInformationMissing)?;
+        policy_rule(&guild, &member, bot_id, &settings, command)
+    };
+
+    result.map_err(RuntimeError::User)
+}
+
 #[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
 pub async fn author_in_voice_channel(ctx: Context<'_>) -> Result<bool, RuntimeError> {
     let author_id = ctx.author().id;
 
     // Scope block to extract data and drop the non-Send guild object
-    let is_in_vc = {
+    let result = {
         let guild = ctx.guild().ok_or(InternalError::GuildInformationMissing)?;
-
-        guild.voice_states.contains_key(&author_id)
+        voice_channel_rule(&guild, author_id)
     };
 
-    if !is_in_vc {
-        trace!("Command dispatched by author not in a voice channel.");
-        return Err(RuntimeError::User(
-            "Please join a voice channel to initiate this command.".to_string(),
-        ));
-    }
-
-    Ok(true)
+    result.map(|_| true).map_err(RuntimeError::User)
 }
 
 #[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
@@ -31,28 +67,12 @@ pub async fn author_in_shared_voice_channel(ctx: Context<'_>) -> Result<bool, Ru
     let author_id = ctx.author().id;
     let bot_id = ctx.framework().bot_id;
 
-    let (author_vc, bot_vc) = {
+    let result = {
         let guild = ctx.guild().ok_or(InternalError::GuildInformationMissing)?;
-
-        (
-            guild
-                .voice_states
-                .get(&author_id)
-                .and_then(|vs| vs.channel_id),
-            guild.voice_states.get(&bot_id).and_then(|vs| vs.channel_id),
-        )
+        shared_voice_channel_rule(&guild, author_id, bot_id)
     };
 
-    match (author_vc, bot_vc) {
-        (Some(a), Some(b)) if a == b => Ok(true),
-        (Some(_), None) => Ok(true),
-        (Some(_), Some(_)) | (None, Some(_)) => Err(RuntimeError::User(
-            "Please join a shared voice channel to issue this command.".to_string(),
-        )),
-        (None, None) => Err(RuntimeError::User(
-            "Please join a voice channel to initiate this command.".to_string(),
-        )),
-    }
+    result.map(|_| true).map_err(RuntimeError::User)
 }
 
 #[instrument(skip_all, fields(guild_id = %ctx.guild_id().unwrap_or_default()))]
@@ -76,3 +96,110 @@ pub async fn track_is_playing(ctx: Context<'_>) -> Result<bool, RuntimeError> {
 
     return Ok(true);
 }
+
+/// Name of the role that lets its members manage playback for everyone.
+const DJ_ROLE_NAME: &str = "DJ";
+
+/// Whether `member` is a DJ: they hold the guild's DJ role or may manage the server.
+pub fn is_dj(guild: &Guild, member: &Member, settings: &GuildSettings) -> bool {
+    if guild.member_permissions(member).manage_guild() {
+        return true;
+    }
+
+    match settings.dj_role {
+        Some(dj_role) => member.roles.contains(&dj_role),
+        None => member.roles.iter().any(|role_id| {
+            guild
+                .roles
+                .get(role_id)
+                .is_some_and(|role| role.name.eq_ignore_ascii_case(DJ_ROLE_NAME))
+        }),
+    }
+}
+
+/// Applies the guild's policy for `command` to a member outside of a command invocation, such as
+/// a button press. DJ commands are open to anyone alone with the bot.
+pub fn policy_rule(
+    guild: &Guild,
+    member: &Member,
+    bot_id: UserId,
+    settings: &GuildSettings,
+    command: &str,
+) -> Result<(), String> {
+    if settings.policy(command) == CommandPolicy::Everyone || is_dj(guild, member, settings) {
+        return Ok(());
+    }
+
+    let shares_channel = shared_voice_channel_rule(guild, member.user.id, bot_id).is_ok();
+    if shares_channel && human_listeners(guild, bot_id) == Some(1) {
+        trace!(command, "Member alone with the bot bypassed the DJ policy.");
+        return Ok(());
+    }
+
+    let usage = match command {
+        SKIP_SEVERAL => "`/skip` on several tracks".to_string(),
+        command => format!("`/{command}`"),
+    };
+
+    Err(format!(
+        "Only DJs can use {usage} in this server, unless they are alone with me."
+    ))
+}
+
+/// Counts the humans in the bot's voice channel, or `None` if the bot is not in one.
+pub fn human_listeners(guild: &Guild, bot_id: UserId) -> Option<usize> {
+    human_listener_ids(guild, bot_id).map(|listeners| listeners.len())
+}
+
+/// The humans in the bot's voice channel, or `None` if the bot is not in one.
+pub fn human_listener_ids(guild: &Guild, bot_id: UserId) -> Option<HashSet<UserId>> {
+    let target_channel = guild.voice_states.get(&bot_id)?.channel_id?;
+
+    let listeners = guild
+        .voice_states
+        .values()
+        .filter(|vs| {
+            vs.channel_id == Some(target_channel) && vs.member.as_ref().is_some_and(|m| !m.user.bot)
+        })
+        .map(|vs| vs.user_id)
+        .collect();
+
+    Some(listeners)
+}
+
+/// Applies the same voice channel rules as the slash command checks to a user outside of a
+/// command invocation, such as a button press.
+pub fn voice_channel_rules(guild: &Guild, author_id: UserId, bot_id: UserId) -> Result<(), String> {
+    voice_channel_rule(guild, author_id)?;
+    shared_voice_channel_rule(guild, author_id, bot_id)
+}
+
+fn voice_channel_rule(guild: &Guild, author_id: UserId) -> Result<(), String> {
+    if !guild.voice_states.contains_key(&author_id) {
+        trace!("Command dispatched by author not in a voice channel.");
+        return Err("Please join a voice channel to initiate this command.".to_string());
+    }
+
+    Ok(())
+}
+
+fn shared_voice_channel_rule(
+    guild: &Guild,
+    author_id: UserId,
+    bot_id: UserId,
+) -> Result<(), String> {
+    let author_vc = guild
+        .voice_states
+        .get(&author_id)
+        .and_then(|vs| vs.channel_id);
+    let bot_vc = guild.voice_states.get(&bot_id).and_then(|vs| vs.channel_id);
+
+    match (author_vc, bot_vc) {
+        (Some(a), Some(b)) if a == b => Ok(()),
+        (Some(_), None) => Ok(()),
+        (Some(_), Some(_)) | (None, Some(_)) => {
+            Err("Please join a shared voice channel to issue this command.".to_string())
+        }
+        (None, None) => Err("Please join a voice channel to initiate this command.".to_string()),
+    }
+}
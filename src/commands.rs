@@ -1,8 +1,24 @@
+pub mod crossfade;
+pub mod default_volume;
+pub mod dj;
+pub mod fair_queue;
+pub mod filter;
+pub mod history;
+pub mod loop_mode;
+pub mod lyrics;
 pub mod pause;
 pub mod play;
+pub mod previous;
 pub mod queue;
+pub mod queue_limits;
 pub mod radio;
+pub mod replay;
 pub mod resume;
+pub mod resume_session;
 pub mod seek;
+pub mod shuffle;
 pub mod skip;
+pub mod skipto;
 pub mod stop;
+pub mod volume;
+pub mod vote_skip;
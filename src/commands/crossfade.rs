@@ -0,0 +1,26 @@
+use tracing::instrument;
+
+use crate::{
+    actions::playback_actions,
+    checks::{author_in_shared_voice_channel, author_in_voice_channel},
+    models::{DiscordError, RuntimeError},
+    server::Context,
+};
+
+/// Show or change how long consecutive tracks overlap.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel"
+)]
+pub async fn crossfade(
+    ctx: Context<'_>,
+    #[description = "Overlap in seconds, 0 to disable. Leave empty to show the current setting."]
+    #[min = 0]
+    #[max = 12]
+    seconds: Option<u8>,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    playback_actions::set_crossfade(&ctx, seconds).await
+}
@@ -0,0 +1,30 @@
+use tracing::instrument;
+
+use crate::{
+    actions::settings_actions,
+    models::{DiscordError, MAX_VOLUME, RuntimeError},
+    server::Context,
+};
+
+// poise only takes a literal as the level's bound, which has to follow MAX_VOLUME.
+const _: () = assert!(MAX_VOLUME == 200);
+
+/// Set the volume new sessions in this server start at.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    rename = "default-volume",
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn default_volume(
+    ctx: Context<'_>,
+    #[description = "Volume as a percentage."]
+    #[min = 0]
+    #[max = 200]
+    level: u8,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    settings_actions::set_default_volume(&ctx, level).await
+}
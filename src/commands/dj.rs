@@ -0,0 +1,62 @@
+use poise::serenity_prelude::Role;
+use tracing::instrument;
+
+use crate::{
+    actions::settings_actions,
+    models::{CommandPolicy, DiscordError, RuntimeError},
+    server::Context,
+};
+
+/// Manage who may run disruptive commands in this server.
+#[poise::command(
+    slash_command,
+    subcommands("role", "policy", "policies"),
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn dj(_: Context<'_>) -> Result<(), RuntimeError> {
+    Ok(())
+}
+
+/// Set the role whose members count as DJs.
+#[instrument(skip(ctx))]
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn role(
+    ctx: Context<'_>,
+    #[description = "The DJ role. Leave empty to recognise any role named DJ."] role: Option<Role>,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    settings_actions::set_dj_role(&ctx, role.map(|r| r.id)).await
+}
+
+/// Choose who may run a command.
+#[instrument(skip(ctx))]
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn policy(
+    ctx: Context<'_>,
+    #[description = "The command, e.g. `stop` or `queue clear`."]
+    #[autocomplete = "autocomplete_command"]
+    command: String,
+    #[description = "Who may run it. Leave empty to restore the default."] policy: Option<
+        CommandPolicy,
+    >,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    settings_actions::set_command_policy(&ctx, &command, policy).await
+}
+
+/// List who may run each command.
+#[instrument(skip(ctx))]
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn policies(ctx: Context<'_>) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    settings_actions::list_command_policies(&ctx).await
+}
+
+async fn autocomplete_command(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    settings_actions::policy_commands(&ctx)
+        .into_iter()
+        .filter(|name| name.starts_with(partial))
+        .take(25)
+        .collect()
+}
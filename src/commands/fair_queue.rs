@@ -0,0 +1,20 @@
+use tracing::instrument;
+
+use crate::{
+    actions::settings_actions,
+    models::{DiscordError, RuntimeError},
+    server::Context,
+};
+
+/// Let queued tracks take turns between the members who requested them.
+#[instrument(skip(ctx))]
+#[poise::command(slash_command, rename = "fair-queue", guild_only)]
+pub async fn fair_queue(
+    ctx: Context<'_>,
+    #[description = "Whether the fair queue is on. Toggles it when left empty."] enabled: Option<
+        bool,
+    >,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    settings_actions::set_fair_queue(&ctx, enabled).await
+}
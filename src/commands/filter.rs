@@ -0,0 +1,54 @@
+use crate::{
+    actions::filter_actions,
+    checks::{author_in_shared_voice_channel, author_in_voice_channel},
+    models::{DiscordError, FilterPreset, RuntimeError},
+    server::Context,
+};
+use tracing::instrument;
+
+/// Apply audio filters to playback.
+#[poise::command(slash_command, subcommands("set", "clear", "list"))]
+pub async fn filter(_: Context<'_>) -> Result<(), RuntimeError> {
+    Ok(())
+}
+
+/// Apply a filter, replacing its previous settings. See `/filter list` for the options.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel"
+)]
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "The filter to apply."] preset: FilterPreset,
+    #[description = "Strength of the bass boost, speed or pitch filters."] amount: Option<f64>,
+    #[description = "Equalizer gains in dB from 31 Hz to 16 kHz, e.g. 4, 3, 1, 0, 0, 0, 1, 2, 3, 3"]
+    bands: Option<String>,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    filter_actions::set_filter(&ctx, preset, amount, bands).await
+}
+
+/// Remove a filter, or all filters when none is given.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel"
+)]
+pub async fn clear(
+    ctx: Context<'_>,
+    #[description = "The filter to remove."] preset: Option<FilterPreset>,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    filter_actions::clear_filter(&ctx, preset).await
+}
+
+/// List the available filters and the ones currently active.
+#[instrument(skip(ctx))]
+#[poise::command(slash_command)]
+pub async fn list(ctx: Context<'_>) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    filter_actions::list_filters(&ctx).await
+}
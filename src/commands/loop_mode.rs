@@ -0,0 +1,25 @@
+use tracing::instrument;
+
+use crate::{
+    actions::playback_actions,
+    checks::{author_in_shared_voice_channel, author_in_voice_channel, track_is_playing},
+    models::{DiscordError, LoopMode, RuntimeError},
+    server::Context,
+};
+
+/// Loop the current track or the whole queue. Cycles through the modes when none is given.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    rename = "loop",
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel",
+    check = "track_is_playing"
+)]
+pub async fn loop_mode(
+    ctx: Context<'_>,
+    #[description = "What to loop. Radio mode pauses while looping."] mode: Option<LoopMode>,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    playback_actions::set_loop_mode(&ctx, mode).await
+}
@@ -0,0 +1,15 @@
+use crate::{
+    actions::lyrics_actions,
+    models::{DiscordError, RuntimeError},
+    server::Context,
+};
+
+/// Show the lyrics of the current track or of a song you search for.
+#[poise::command(slash_command, guild_only)]
+pub async fn lyrics(
+    ctx: Context<'_>,
+    #[description = "Song to look up. Defaults to the current track."] query: Option<String>,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    lyrics_actions::show_lyrics(&ctx, query).await
+}
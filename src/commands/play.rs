@@ -1,9 +1,13 @@
 use crate::{
-    actions::{channel_actions, playback_actions},
+    actions::{channel_actions, playback_actions, search_actions},
     checks::{author_in_shared_voice_channel, author_in_voice_channel},
     models::{DiscordError, QueueElement, RuntimeError},
     server::Context,
 };
+use poise::{
+    ChoiceParameter,
+    serenity_prelude::{self, AutocompleteChoice, ResolvedValue},
+};
 use tracing::{instrument, trace};
 
 #[derive(Debug, Default, poise::ChoiceParameter)]
@@ -13,7 +17,7 @@ pub enum ResourceType {
     Playlist,
 }
 
-#[poise::command(slash_command, subcommands("url", "search"))]
+#[poise::command(slash_command, subcommands("url", "file", "search"))]
 pub async fn play(_: Context<'_>) -> Result<(), RuntimeError> {
     Ok(())
 }
@@ -32,9 +36,34 @@ pub async fn url(
     channel_actions::join_channel(ctx).await?;
     ctx.defer().await.map_err(DiscordError::Gateway)?;
 
-    let metadata = ctx.data().youtube_client.process_url(&path).await;
+    let queue_element = ctx.data().metadata_providers.resolve_url(&path).await?;
+
+    trace!(queue_element=%queue_element, "Adding queue element to queue.");
+    playback_actions::add_element_to_queue(&ctx, queue_element).await?;
+    playback_actions::start_queue_playback(&ctx).await?;
+    Ok(())
+}
+
+/// Play an audio file attached to your message.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel"
+)]
+pub async fn file(
+    ctx: Context<'_>,
+    #[description = "Audio file to play (mp3, ogg, opus, flac or wav)."]
+    attachment: serenity_prelude::Attachment,
+) -> Result<(), RuntimeError> {
+    channel_actions::join_channel(ctx).await?;
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
 
-    let queue_element = metadata.map(QueueElement::from)?;
+    let queue_element = ctx
+        .data()
+        .metadata_providers
+        .resolve_url(&attachment.url)
+        .await?;
 
     trace!(queue_element=%queue_element, "Adding queue element to queue.");
     playback_actions::add_element_to_queue(&ctx, queue_element).await?;
@@ -42,7 +71,7 @@ pub async fn url(
     Ok(())
 }
 
-/// Search for a resource to play using a query. The default resource type is a video/track.
+/// Search for a track or playlist and pick one of the top results to play.
 #[instrument(skip(ctx))]
 #[poise::command(
     slash_command,
@@ -56,29 +85,79 @@ pub async fn search(
         ResourceType,
     >,
 
-    #[description = "Search query to the requested track or playlist."] query: String,
+    #[description = "Search query to the requested track or playlist."]
+    #[autocomplete = "autocomplete_query"]
+    query: String,
+
+    #[description = "Queue the top result right away instead of picking one."] lucky: Option<bool>,
 ) -> Result<(), RuntimeError> {
     channel_actions::join_channel(ctx).await?;
     ctx.defer().await.map_err(DiscordError::Gateway)?;
 
-    let queue_element = match resource_type {
-        Some(ResourceType::Track) | None => ctx
-            .data()
-            .youtube_client
-            .search_video(&query)
-            .await
-            .map(QueueElement::Track),
-        Some(ResourceType::Playlist) => ctx
-            .data()
-            .youtube_client
-            .search_playlist(&query, 50)
-            .await
-            .map(QueueElement::Playlist),
-    }
-    .map_err(RuntimeError::Youtube)?;
+    let providers = &ctx.data().metadata_providers;
+
+    // Suggestions fill in the URL of the exact result they stand for.
+    let queue_element = if url::Url::parse(&query).is_ok() {
+        providers.resolve_url(&query).await?
+    } else {
+        let lucky = lucky.unwrap_or_default();
+        let n_results = if lucky {
+            1
+        } else {
+            search_actions::N_SEARCH_RESULTS
+        };
+
+        let results = match resource_type {
+            Some(ResourceType::Track) | None => providers
+                .search_tracks(&query, n_results)
+                .await?
+                .into_iter()
+                .map(QueueElement::Track)
+                .collect::<Vec<_>>(),
+            Some(ResourceType::Playlist) => providers
+                .search_playlists(&query, n_results)
+                .await?
+                .into_iter()
+                .map(QueueElement::Playlist)
+                .collect(),
+        };
+
+        let picked = if lucky {
+            results.into_iter().next()
+        } else {
+            search_actions::pick_result(&ctx, results).await?
+        };
+        let Some(picked) = picked else {
+            return Ok(());
+        };
+
+        // Playlists come out of searches without their items.
+        match picked {
+            QueueElement::Playlist(playlist) => providers.resolve_url(&playlist.url).await?,
+            track => track,
+        }
+    };
 
     trace!(queue_element=%queue_element, "Adding queue element to queue.");
     playback_actions::add_element_to_queue(&ctx, queue_element).await?;
     playback_actions::start_queue_playback(&ctx).await?;
     Ok(())
 }
+
+async fn autocomplete_query(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    // Discord sends the other options as typed so far, with choices as their index.
+    let playlists = match ctx {
+        poise::Context::Application(app) => app
+            .args
+            .iter()
+            .find(|arg| arg.name == "resource_type")
+            .and_then(|arg| match arg.value {
+                ResolvedValue::Integer(i) => ResourceType::from_index(i as usize),
+                _ => None,
+            })
+            .is_some_and(|t| matches!(t, ResourceType::Playlist)),
+        poise::Context::Prefix(_) => false,
+    };
+
+    search_actions::suggest_results(&ctx, partial, playlists).await
+}
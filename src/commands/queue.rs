@@ -4,6 +4,16 @@ use crate::{
     models::{DiscordError, RuntimeError},
     server::Context,
 };
+use tracing::instrument;
+
+/// View and edit the queue.
+#[poise::command(
+    slash_command,
+    subcommands("show", "remove", "move_track", "swap", "clear")
+)]
+pub async fn queue(_: Context<'_>) -> Result<(), RuntimeError> {
+    Ok(())
+}
 
 /// Display the next items in the queue.
 #[poise::command(
@@ -11,8 +21,78 @@ use crate::{
     check = "author_in_voice_channel",
     check = "author_in_shared_voice_channel"
 )]
-pub async fn queue(ctx: Context<'_>) -> Result<(), RuntimeError> {
+pub async fn show(ctx: Context<'_>) -> Result<(), RuntimeError> {
     ctx.defer().await.map_err(DiscordError::Gateway)?;
     playback_actions::show_queue(&ctx).await?;
     Ok(())
 }
+
+/// Remove a track from the queue.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel"
+)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "Position of the track in the queue."]
+    #[min = 1_usize]
+    position: usize,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    playback_actions::remove_track(&ctx, position).await
+}
+
+/// Move a track to a different position in the queue.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    rename = "move",
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel"
+)]
+pub async fn move_track(
+    ctx: Context<'_>,
+    #[description = "Current position of the track."]
+    #[min = 1_usize]
+    from: usize,
+    #[description = "Desired position of the track."]
+    #[min = 1_usize]
+    to: usize,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    playback_actions::move_track(&ctx, from, to).await
+}
+
+/// Swap the positions of two tracks in the queue.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel"
+)]
+pub async fn swap(
+    ctx: Context<'_>,
+    #[description = "Position of the first track."]
+    #[min = 1_usize]
+    first: usize,
+    #[description = "Position of the second track."]
+    #[min = 1_usize]
+    second: usize,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    playback_actions::swap_tracks(&ctx, first, second).await
+}
+
+/// Clear the queue without stopping the current track.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel"
+)]
+pub async fn clear(ctx: Context<'_>) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    playback_actions::clear_queue(&ctx).await
+}
@@ -0,0 +1,32 @@
+use tracing::instrument;
+
+use crate::{
+    actions::settings_actions,
+    models::{DiscordError, RuntimeError},
+    server::Context,
+};
+
+/// Limit what each member may queue. DJs are exempt from all but the track length.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    rename = "queue-limits",
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn queue_limits(
+    ctx: Context<'_>,
+    #[description = "Most tracks a member may have queued. 0 removes the limit."]
+    #[max = 1000]
+    tracks: Option<usize>,
+    #[description = "Most minutes of music a member may have queued. 0 removes the limit."]
+    #[max = 1440]
+    minutes: Option<u64>,
+    #[description = "Longest track anyone may queue, in minutes. 0 removes the limit."]
+    #[max = 1440]
+    track_minutes: Option<u64>,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    settings_actions::set_queue_limits(&ctx, tracks, minutes, track_minutes).await
+}
@@ -0,0 +1,21 @@
+use tracing::instrument;
+
+use crate::{
+    actions::session_actions,
+    checks::{author_in_shared_voice_channel, author_in_voice_channel},
+    models::{DiscordError, RuntimeError},
+    server::Context,
+};
+
+/// Resume the session that was interrupted by the last restart.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    rename = "resume-session",
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel"
+)]
+pub async fn resume_session(ctx: Context<'_>) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    session_actions::resume_session(&ctx).await
+}
@@ -1,70 +1,207 @@
 use crate::{
-    checks::{author_in_shared_voice_channel, author_in_voice_channel},
-    models::{DiscordError, InternalError, RuntimeError},
+    actions::playback_actions::{self, SeekTarget},
+    checks::{author_in_shared_voice_channel, author_in_voice_channel, track_is_playing},
+    models::{DiscordError, RuntimeError},
     server::Context,
 };
+use std::time::Duration;
 use tracing::instrument;
 
 /// Seek a position in the track.
-#[poise::command(slash_command, subcommands("absolute"))]
+#[poise::command(slash_command, subcommands("absolute", "forward", "backward"))]
 pub async fn seek(_: Context<'_>) -> Result<(), RuntimeError> {
     Ok(())
 }
 
-/// Seek an absolute position in the track using a timestamp with the format HH:MM:SS.
+/// Seek an absolute position in the track, e.g. `90`, `1:30` or `1m30s`.
 #[instrument(skip(ctx))]
 #[poise::command(
     slash_command,
     check = "author_in_voice_channel",
-    check = "author_in_shared_voice_channel"
+    check = "author_in_shared_voice_channel",
+    check = "track_is_playing"
 )]
 pub async fn absolute(
     ctx: Context<'_>,
-    #[description = "The desired timestamp (HH:MM:SS) to seek to."] position: String,
+    #[description = "The timestamp to seek to, e.g. 90, 1:30 or 1m30s."] position: String,
 ) -> Result<(), RuntimeError> {
     ctx.defer().await.map_err(DiscordError::Gateway)?;
+    let position = parse_timestamp(&position).map_err(RuntimeError::User)?;
+    playback_actions::seek(&ctx, SeekTarget::Absolute(position)).await
+}
+
+/// Skip ahead in the track.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel",
+    check = "track_is_playing"
+)]
+pub async fn forward(
+    ctx: Context<'_>,
+    #[description = "How far to skip ahead, e.g. 30, 1:30 or 1m."] duration: String,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    let duration = parse_timestamp(&duration).map_err(RuntimeError::User)?;
+    playback_actions::seek(&ctx, SeekTarget::Forward(duration)).await
+}
 
-    let guild_id = ctx
-        .guild_id()
-        .ok_or(InternalError::GuildInformationMissing)?;
+/// Go back in the track.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel",
+    check = "track_is_playing"
+)]
+pub async fn backward(
+    ctx: Context<'_>,
+    #[description = "How far to go back, e.g. 30, 1:30 or 1m."] duration: String,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    let duration = parse_timestamp(&duration).map_err(RuntimeError::User)?;
+    playback_actions::seek(&ctx, SeekTarget::Backward(duration)).await
+}
 
-    let sliced = position.split(":").collect::<Vec<&str>>();
+/// Parses plain seconds (`90`), clock timestamps (`1:30`, `1:02:03`) and unit suffixes (`1h2m`,
+/// `1m30s`). Units must go from largest to smallest, each at most once.
+fn parse_timestamp(input: &str) -> Result<Duration, String> {
+    let normalized = input
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+    let invalid = || format!("`{input}` is not a valid timestamp. Try `90`, `1:30` or `1m30s`.");
 
-    if sliced.len() != 3 {
-        return Err(RuntimeError::User(format!(
-            "{position} is not a valid timestamp. Please use the HH:MM:SS format"
-        )));
+    if normalized.is_empty() {
+        return Err(invalid());
     }
 
-    let parsed = sliced
-        .iter()
-        .map(|v| v.parse::<u64>())
-        .try_fold(vec![], |mut accum, curr| match curr {
-            Ok(curr) => {
-                accum.push(curr);
-                Ok(accum)
-            }
-            Err(e) => Err(e),
-        });
-
-    let timestamp = parsed
-        .ok()
-        .map(|args| std::time::Duration::from_secs(args[0] * 3600 + args[1] * 60 + args[2]));
-
-    return if let Some(timestamp) = timestamp {
-        let mut guard = ctx.data().guild_map.write().await;
-        let state = guard.get_mut(&guild_id.to_string()).unwrap();
-        let track_handle = state.playback_state.get_track_handle_mut();
-
-        return if let Some(handle) = track_handle {
-            _ = handle.seek(timestamp);
-            Ok(())
-        } else {
-            Err(RuntimeError::User("Nothing currently playing".to_string()))
-        };
+    let seconds = if normalized.contains(':') {
+        let parts = normalized
+            .split(':')
+            .map(|part| part.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+
+        // Minutes and seconds following a larger unit must stay below 60.
+        if parts.len() > 3 || parts[1..].iter().any(|part| *part >= 60) {
+            return Err(invalid());
+        }
+
+        parts
+            .iter()
+            .try_fold(0u64, |total, part| {
+                total.checked_mul(60)?.checked_add(*part)
+            })
+            .ok_or_else(invalid)?
     } else {
-        Err(RuntimeError::User(format!(
-            "{position} is not a valid timestamp. Please use the HH:MM:SS format"
-        )))
+        let mut total = 0u64;
+        let mut digits = String::new();
+        let mut previous_unit = u64::MAX;
+
+        for c in normalized.chars() {
+            if c.is_ascii_digit() {
+                digits.push(c);
+                continue;
+            }
+
+            let unit = match c {
+                'h' => 3600,
+                'm' => 60,
+                's' => 1,
+                _ => return Err(invalid()),
+            };
+            if unit >= previous_unit {
+                return Err(invalid());
+            }
+            previous_unit = unit;
+
+            total = digits
+                .parse::<u64>()
+                .ok()
+                .and_then(|value| value.checked_mul(unit))
+                .and_then(|seconds| total.checked_add(seconds))
+                .ok_or_else(invalid)?;
+            digits.clear();
+        }
+
+        // Trailing digits without a unit are seconds.
+        if !digits.is_empty() {
+            if previous_unit == 1 {
+                return Err(invalid());
+            }
+            total = digits
+                .parse::<u64>()
+                .ok()
+                .and_then(|seconds| total.checked_add(seconds))
+                .ok_or_else(invalid)?;
+        }
+
+        total
     };
+
+    Ok(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(input: &str) -> Result<u64, String> {
+        parse_timestamp(input).map(|d| d.as_secs())
+    }
+
+    #[test]
+    fn parses_plain_seconds() {
+        assert_eq!(secs("90"), Ok(90));
+        assert_eq!(secs(" 0 "), Ok(0));
+    }
+
+    #[test]
+    fn parses_clock_timestamps() {
+        assert_eq!(secs("1:30"), Ok(90));
+        assert_eq!(secs("1:02:03"), Ok(3723));
+        assert_eq!(secs("90:00"), Ok(5400));
+        assert!(secs("1:60").is_err());
+        assert!(secs("1:2:3:4").is_err());
+        assert!(secs("1:").is_err());
+    }
+
+    #[test]
+    fn parses_unit_suffixes() {
+        assert_eq!(secs("1h2m"), Ok(3720));
+        assert_eq!(secs("1m30s"), Ok(90));
+        assert_eq!(secs("1M 30"), Ok(90));
+        assert_eq!(secs("45s"), Ok(45));
+    }
+
+    #[test]
+    fn rejects_repeated_and_out_of_order_units() {
+        assert!(secs("1m1m").is_err());
+        assert!(secs("30s1m").is_err());
+        assert!(secs("1s30").is_err());
+        assert!(secs("m").is_err());
+    }
+
+    #[test]
+    fn rejects_empty_and_mixed_input() {
+        assert!(secs("").is_err());
+        assert!(secs("  ").is_err());
+        assert!(secs("1:30m").is_err());
+        assert!(secs("1m:30").is_err());
+        assert!(secs("-5").is_err());
+        assert!(secs("1d").is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_input() {
+        assert!(secs("99999999999999999999").is_err());
+        assert!(secs("99999999999999999999:0").is_err());
+        assert!(secs("9999999999999999:0:0").is_err());
+        assert!(secs("9999999999999999h").is_err());
+        assert!(secs("5124095576030431h1s").is_ok());
+        assert!(secs("5124095576030431h9999s").is_err());
+    }
 }
@@ -0,0 +1,20 @@
+use tracing::instrument;
+
+use crate::{
+    actions::playback_actions,
+    checks::{author_in_shared_voice_channel, author_in_voice_channel},
+    models::{DiscordError, RuntimeError},
+    server::Context,
+};
+
+/// Shuffle the queued tracks.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel"
+)]
+pub async fn shuffle(ctx: Context<'_>) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    playback_actions::shuffle_queue(&ctx).await
+}
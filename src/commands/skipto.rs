@@ -0,0 +1,22 @@
+use crate::{
+    actions::playback_actions,
+    checks::{author_in_shared_voice_channel, author_in_voice_channel},
+    models::{DiscordError, RuntimeError},
+    server::Context,
+};
+
+/// Jump to a track in the queue, skipping everything before it.
+#[poise::command(
+    slash_command,
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel"
+)]
+pub async fn skipto(
+    ctx: Context<'_>,
+    #[description = "Position of the track in the queue."]
+    #[min = 1_usize]
+    position: usize,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    playback_actions::skip_to(&ctx, position).await
+}
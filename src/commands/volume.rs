@@ -0,0 +1,29 @@
+use tracing::instrument;
+
+use crate::{
+    actions::playback_actions,
+    checks::{author_in_shared_voice_channel, author_in_voice_channel},
+    models::{DiscordError, MAX_VOLUME, RuntimeError},
+    server::Context,
+};
+
+// poise only takes a literal as the level's bound, which has to follow MAX_VOLUME.
+const _: () = assert!(MAX_VOLUME == 200);
+
+/// Show or change the playback volume.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    check = "author_in_voice_channel",
+    check = "author_in_shared_voice_channel"
+)]
+pub async fn volume(
+    ctx: Context<'_>,
+    #[description = "Volume as a percentage. Leave empty to show the current volume."]
+    #[min = 0]
+    #[max = 200]
+    level: Option<u8>,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    playback_actions::set_volume(&ctx, level).await
+}
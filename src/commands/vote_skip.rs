@@ -0,0 +1,27 @@
+use tracing::instrument;
+
+use crate::{
+    actions::settings_actions,
+    models::{DiscordError, RuntimeError},
+    server::Context,
+};
+
+/// Require votes from a share of the listeners before a track is skipped.
+#[instrument(skip(ctx))]
+#[poise::command(
+    slash_command,
+    rename = "vote-skip",
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+pub async fn vote_skip(
+    ctx: Context<'_>,
+    #[description = "Share of the listeners that must vote, as a percentage. 0 turns vote-skip off."]
+    #[min = 0]
+    #[max = 100]
+    share: u8,
+) -> Result<(), RuntimeError> {
+    ctx.defer().await.map_err(DiscordError::Gateway)?;
+    settings_actions::set_skip_vote_share(&ctx, (share > 0).then_some(share)).await
+}
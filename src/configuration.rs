@@ -1,14 +1,33 @@
 use config::{Config, File};
+use std::path::{Path, PathBuf};
 
 #[cfg(not(debug_assertions))]
 const CONFIG_SORUCE: &str = "Secrets.toml";
 #[cfg(debug_assertions)]
 const CONFIG_SORUCE: &str = "Secrets.dev.toml";
 
+const DEFAULT_SESSION_FILE: &str = "data/sessions.json";
+const DEFAULT_GUILD_SETTINGS_FILE: &str = "data/guild_settings.json";
+const DEFAULT_LYRICS_API_URL: &str = "https://lrclib.net/api";
+
+/// Where track metadata and search results come from.
+#[derive(Debug, Clone)]
+pub enum MetadataBackend {
+    /// The YouTube Data API, falling back to yt-dlp when it turns requests away.
+    YoutubeApi { api_key: String },
+    /// yt-dlp alone, which needs no API key.
+    Ytdlp,
+}
+
 #[derive(Debug, Clone)]
 pub struct ConfigurationVariables {
     discord_token: String,
-    youtube_api_key: String,
+    metadata_backend: MetadataBackend,
+    session_file: PathBuf,
+    auto_resume_sessions: bool,
+    guild_settings_file: PathBuf,
+    lyrics_api_url: String,
+    metadata_cache_file: Option<PathBuf>,
     #[cfg(debug_assertions)]
     dev_guild_id: usize,
 }
@@ -32,16 +51,54 @@ impl ConfigurationVariables {
             .get_string("DISCORD_TOKEN")
             .expect("Expected DISCORD_TOKEN.");
 
-        let youtube_api_key = vars
-            .get_string("YOUTUBE_API_KEY")
-            .expect("Expected YOUTUBE_API_KEY.");
+        let youtube_api_key = vars.get_string("YOUTUBE_API_KEY").ok();
+        let metadata_backend = match vars.get_string("METADATA_BACKEND").ok().as_deref() {
+            Some("youtube_api") => MetadataBackend::YoutubeApi {
+                api_key: youtube_api_key
+                    .expect("Expected YOUTUBE_API_KEY for the youtube_api METADATA_BACKEND."),
+            },
+            Some("yt-dlp") => MetadataBackend::Ytdlp,
+            Some(other) => {
+                panic!("Unknown METADATA_BACKEND {other}. Expected youtube_api or yt-dlp.")
+            }
+            None => match youtube_api_key {
+                Some(api_key) => MetadataBackend::YoutubeApi { api_key },
+                None => MetadataBackend::Ytdlp,
+            },
+        };
+
+        let session_file = vars
+            .get_string("SESSION_FILE")
+            .unwrap_or_else(|_| DEFAULT_SESSION_FILE.to_string())
+            .into();
+
+        let auto_resume_sessions = vars.get_bool("AUTO_RESUME_SESSIONS").unwrap_or(false);
+
+        let guild_settings_file = vars
+            .get_string("GUILD_SETTINGS_FILE")
+            .unwrap_or_else(|_| DEFAULT_GUILD_SETTINGS_FILE.to_string())
+            .into();
+
+        let lyrics_api_url = vars
+            .get_string("LYRICS_API_URL")
+            .unwrap_or_else(|_| DEFAULT_LYRICS_API_URL.to_string());
+
+        let metadata_cache_file = vars
+            .get_string("METADATA_CACHE_FILE")
+            .ok()
+            .map(PathBuf::from);
 
         #[cfg(debug_assertions)]
         let dev_guild_id = vars.get::<usize>("GUILD_ID").expect("Expected GUILD_ID.");
 
         Self {
             discord_token,
-            youtube_api_key,
+            metadata_backend,
+            session_file,
+            auto_resume_sessions,
+            guild_settings_file,
+            lyrics_api_url,
+            metadata_cache_file,
             #[cfg(debug_assertions)]
             dev_guild_id,
         }
@@ -51,8 +108,30 @@ impl ConfigurationVariables {
         &self.discord_token
     }
 
-    pub fn youtube_api_key(&self) -> &str {
-        &self.youtube_api_key
+    /// Defaults to the YouTube API when a key is set, and to yt-dlp otherwise.
+    pub fn metadata_backend(&self) -> &MetadataBackend {
+        &self.metadata_backend
+    }
+
+    pub fn session_file(&self) -> &Path {
+        &self.session_file
+    }
+
+    pub fn auto_resume_sessions(&self) -> bool {
+        self.auto_resume_sessions
+    }
+
+    pub fn guild_settings_file(&self) -> &Path {
+        &self.guild_settings_file
+    }
+
+    pub fn lyrics_api_url(&self) -> &str {
+        &self.lyrics_api_url
+    }
+
+    /// Where YouTube metadata is kept between runs. Only kept in memory when unset.
+    pub fn metadata_cache_file(&self) -> Option<&Path> {
+        self.metadata_cache_file.as_deref()
     }
 
     #[cfg(debug_assertions)]
@@ -1,6 +1,13 @@
-use poise::serenity_prelude::{self, Color, Timestamp};
+use poise::serenity_prelude::{self, Color, Mentionable, RoleId, Timestamp};
+use std::time::Duration;
 
-use crate::models::{PlaylistMetadata, QueueElement, VideoMetadata};
+use crate::models::{
+    CommandPolicy, FilterChain, FilterPreset, GuildSettings, GuildSnapshot, LoopMode, Lyrics,
+    Playlist, QueueElement, SKIP_SEVERAL, Track,
+};
+
+/// Marks tracks that are broadcast live.
+const LIVE_BADGE: &str = "🔴 LIVE";
 
 /// Base template with color and timestamp
 fn create_embed_template() -> serenity_prelude::CreateEmbed {
@@ -9,30 +16,84 @@ fn create_embed_template() -> serenity_prelude::CreateEmbed {
         .timestamp(Timestamp::now())
 }
 
+/// Formats a duration as `M:SS`, or `H:MM:SS` once it exceeds an hour.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
 /// Helper to consistently format track data across all embeds
 fn populate_track_info(
     embed: serenity_prelude::CreateEmbed,
-    track: &VideoMetadata,
+    track: &Track,
 ) -> serenity_prelude::CreateEmbed {
-    embed
+    let embed = embed
         .field("Track", format!("[{}]({})", track.title, track.url), false)
-        .field("Channel", &track.channel, true)
-        .thumbnail(track.thumbnail_url.to_string())
+        .field("Artist", &track.artist, true);
+    let embed = with_thumbnail(embed, track.artwork());
+
+    if track.is_live {
+        return embed.field("Length", LIVE_BADGE, true);
+    }
+
+    match track.duration.filter(|d| !d.is_zero()) {
+        Some(duration) => embed.field("Length", format_duration(duration), true),
+        None => embed,
+    }
+}
+
+/// Sets the embed's thumbnail to the artwork, if there is any.
+fn with_thumbnail(
+    embed: serenity_prelude::CreateEmbed,
+    artwork_url: Option<&str>,
+) -> serenity_prelude::CreateEmbed {
+    match artwork_url {
+        Some(url) => embed.thumbnail(url),
+        None => embed,
+    }
+}
+
+/// Formats the combined length of `tracks`, marked with a `+` when some lengths are unknown.
+fn format_total_duration<'a>(tracks: impl IntoIterator<Item = &'a Track>) -> String {
+    let (total, has_unknown) = tracks.into_iter().fold(
+        (Duration::ZERO, false),
+        |(total, unknown), track| match track.duration {
+            Some(duration) => (total + duration, unknown),
+            None => (total, true),
+        },
+    );
+
+    format!(
+        "{}{}",
+        format_duration(total),
+        if has_unknown { "+" } else { "" }
+    )
+}
+
+/// Formats how long until a queued item plays, rounded up to the minute.
+fn format_eta(eta: Duration) -> String {
+    format!("~{} min", eta.as_secs().div_ceil(60).max(1))
 }
 
 /// Helper to consistently format playlist data across all embeds
 fn populate_playlist_info(
     embed: serenity_prelude::CreateEmbed,
-    playlist: &PlaylistMetadata,
+    playlist: &Playlist,
 ) -> serenity_prelude::CreateEmbed {
-    embed
+    let embed = embed
         .field(
             "Playlist",
             format!("[{}]({})", playlist.title, playlist.url),
             false,
         )
-        .field("Channel", &playlist.channel, true)
-        .thumbnail(playlist.thumbnail_url.to_string())
+        .field("Artist", &playlist.artist, true);
+    with_thumbnail(embed, playlist.artwork())
 }
 
 /// Use for general info or status updates
@@ -58,16 +119,22 @@ pub fn create_error_embed(message: &str) -> serenity_prelude::CreateEmbed {
 
 // --- Playlist Embeds ---
 
-pub fn create_queued_playlist_embed(playlist: &PlaylistMetadata) -> serenity_prelude::CreateEmbed {
+pub fn create_queued_playlist_embed(
+    playlist: &Playlist,
+    eta: Option<Duration>,
+) -> serenity_prelude::CreateEmbed {
     let embed = create_embed_template().title("Playlist Queued");
-    populate_playlist_info(embed, playlist).field(
-        "Total Tracks",
-        playlist.items.len().to_string(),
-        true,
-    )
+    let embed = populate_playlist_info(embed, playlist)
+        .field("Total Tracks", playlist.items.len().to_string(), true)
+        .field("Total Length", format_total_duration(&playlist.items), true);
+
+    match eta {
+        Some(eta) => embed.field("Plays In", format_eta(eta), true),
+        None => embed,
+    }
 }
 
-pub fn create_playing_playlist_embed(playlist: &PlaylistMetadata) -> serenity_prelude::CreateEmbed {
+pub fn create_playing_playlist_embed(playlist: &Playlist) -> serenity_prelude::CreateEmbed {
     let mut embed = create_embed_template().title("Now Playing Playlist");
     embed = populate_playlist_info(embed, playlist).field(
         "Total Tracks",
@@ -87,30 +154,150 @@ pub fn create_playing_playlist_embed(playlist: &PlaylistMetadata) -> serenity_pr
 
 // --- Track Embeds ---
 
-pub fn create_queued_track_embed(track: &VideoMetadata) -> serenity_prelude::CreateEmbed {
+pub fn create_queued_track_embed(
+    track: &Track,
+    eta: Option<Duration>,
+) -> serenity_prelude::CreateEmbed {
     let embed = create_embed_template().title("Track Queued");
-    populate_track_info(embed, track)
+    let embed = populate_track_info(embed, track);
+
+    match eta {
+        Some(eta) => embed.field("Plays In", format_eta(eta), true),
+        None => embed,
+    }
 }
 
-pub fn create_playing_track_embed(track: &VideoMetadata) -> serenity_prelude::CreateEmbed {
+pub fn create_playing_track_embed(track: &Track) -> serenity_prelude::CreateEmbed {
     let embed = create_embed_template().title("Now Playing");
     populate_track_info(embed, track)
 }
 
-pub fn create_resume_track_embed(track: &VideoMetadata) -> serenity_prelude::CreateEmbed {
+pub fn create_resume_track_embed(track: &Track) -> serenity_prelude::CreateEmbed {
     let embed = create_embed_template().title("Playback Resumed");
     populate_track_info(embed, track)
 }
 
-pub fn create_paused_embed(track: &VideoMetadata) -> serenity_prelude::CreateEmbed {
+pub fn create_paused_embed(track: &Track) -> serenity_prelude::CreateEmbed {
     let embed = create_embed_template().title("Playback Paused");
     populate_track_info(embed, track)
 }
 
+pub fn create_previous_track_embed(track: &Track) -> serenity_prelude::CreateEmbed {
+    let embed = create_embed_template()
+        .title("Playing Previous Track")
+        .description("The interrupted track is next in the queue.");
+    populate_track_info(embed, track)
+}
+
+pub fn create_replay_track_embed(track: &Track) -> serenity_prelude::CreateEmbed {
+    let embed = create_embed_template().title("Replaying Track");
+    populate_track_info(embed, track)
+}
+
+// --- Search Embeds ---
+
+/// The artist and, for tracks, the length of a search result, as listed in the picker.
+pub fn format_search_result_details(result: &QueueElement) -> String {
+    match result {
+        QueueElement::Track(track) if track.is_live => format!("{} · {LIVE_BADGE}", track.artist),
+        QueueElement::Track(track) => match track.duration.filter(|d| !d.is_zero()) {
+            Some(duration) => format!("{} · {}", track.artist, format_duration(duration)),
+            None => track.artist.clone(),
+        },
+        QueueElement::Playlist(playlist) => format!("Playlist by {}", playlist.artist),
+    }
+}
+
+/// A search result numbered by its 1-based `position` in the picker. Results get an embed each
+/// so each can show its artwork.
+pub fn create_search_result_embed(
+    position: usize,
+    result: &QueueElement,
+) -> serenity_prelude::CreateEmbed {
+    let (title, url, artwork) = match result {
+        QueueElement::Track(t) => (&t.title, &t.url, t.artwork()),
+        QueueElement::Playlist(p) => (&p.title, &p.url, p.artwork()),
+    };
+    let embed = create_embed_template()
+        .title(format!("{position}. {title}"))
+        .url(url)
+        .description(format_search_result_details(result));
+    with_thumbnail(embed, artwork)
+}
+
+pub fn create_search_closed_embed(message: &str) -> serenity_prelude::CreateEmbed {
+    create_embed_template()
+        .title("Search Closed")
+        .description(message)
+}
+
+// --- Now Playing Panel ---
+
+/// Renders playback progress as a bar when the track length is known.
+pub fn format_progress(elapsed: Duration, total: Option<Duration>) -> String {
+    const BAR_LENGTH: usize = 16;
+
+    let Some(total) = total.filter(|t| !t.is_zero()) else {
+        return format!("`{}`", format_duration(elapsed));
+    };
+
+    let ratio = (elapsed.as_secs_f64() / total.as_secs_f64()).clamp(0.0, 1.0);
+    let filled = ((ratio * BAR_LENGTH as f64) as usize).min(BAR_LENGTH - 1);
+
+    format!(
+        "`{}` {}🔘{} `{}`",
+        format_duration(elapsed),
+        "▬".repeat(filled),
+        "▬".repeat(BAR_LENGTH - 1 - filled),
+        format_duration(total)
+    )
+}
+
+/// Snapshot of the playback details shown on the now playing panel.
+pub struct NowPlayingPanel<'a> {
+    pub track: &'a Track,
+    pub elapsed: Duration,
+    pub total: Option<Duration>,
+    pub is_paused: bool,
+    pub loop_mode: LoopMode,
+    pub is_radio_enabled: bool,
+    pub n_queued: usize,
+}
+
+pub fn create_now_playing_panel_embed(panel: &NowPlayingPanel) -> serenity_prelude::CreateEmbed {
+    let embed = create_embed_template()
+        .title(if panel.is_paused {
+            "Now Playing (Paused)"
+        } else {
+            "Now Playing"
+        })
+        .description(if panel.track.is_live {
+            // Live streams have no end to measure progress against.
+            format!("{LIVE_BADGE} • {}", format_progress(panel.elapsed, None))
+        } else {
+            format_progress(panel.elapsed, panel.total)
+        });
+
+    populate_track_info(embed, panel.track)
+        .field("Queued Tracks", panel.n_queued.to_string(), true)
+        .field("Loop", panel.loop_mode.to_string(), true)
+        .field(
+            "Radio",
+            if panel.is_radio_enabled { "On" } else { "Off" },
+            true,
+        )
+}
+
+pub fn create_now_playing_closed_embed(message: &str) -> serenity_prelude::CreateEmbed {
+    create_embed_template()
+        .title("Nothing Playing")
+        .description(message)
+}
+
 // --- Skip Embeds ---
 
 pub fn create_skip_track_embed(
-    track: &VideoMetadata,
+    track: &Track,
     skipped: usize,
     remaining: usize,
 ) -> serenity_prelude::CreateEmbed {
@@ -123,7 +310,7 @@ pub fn create_skip_track_embed(
 }
 
 pub fn create_skip_playlist_embed(
-    playlist: &PlaylistMetadata,
+    playlist: &Playlist,
     skipped: usize,
     remaining: usize,
 ) -> serenity_prelude::CreateEmbed {
@@ -135,56 +322,478 @@ pub fn create_skip_playlist_embed(
     populate_playlist_info(embed, playlist)
 }
 
-// --- Queue Overview ---
+pub fn create_skip_vote_embed(
+    track: &Track,
+    votes: usize,
+    needed: usize,
+) -> serenity_prelude::CreateEmbed {
+    let embed = create_embed_template()
+        .title("Vote to Skip")
+        .description(format!(
+            "**{votes}/{needed}** votes to skip. Press the button below to add yours."
+        ));
+    populate_track_info(embed, track)
+}
+
+pub fn create_skip_vote_passed_embed() -> serenity_prelude::CreateEmbed {
+    create_embed_template()
+        .title("Vote Passed")
+        .description("Skipping the current track.")
+}
 
-pub fn create_queue_overview_embed(
-    next_tracks: &[QueueElement],
-    n_tracks: usize,
-    n_items: usize,
+pub fn create_skip_vote_settings_embed(share: Option<u8>) -> serenity_prelude::CreateEmbed {
+    let description = match share {
+        Some(share) => format!(
+            "Skipping a track now takes votes from **{share}%** of the listeners. Requesters can still skip their own tracks, and DJs can skip anything."
+        ),
+        None => "Vote-skip disabled. Anyone in the voice channel can skip.".to_string(),
+    };
+
+    create_embed_template()
+        .title("Vote-Skip")
+        .description(description)
+}
+
+pub fn create_skip_to_embed(
+    track: &Track,
+    position: usize,
+    remaining: usize,
 ) -> serenity_prelude::CreateEmbed {
-    let mut embed = create_embed_template()
-        .title("Queue Overview")
-        .field("Queued Items", n_items.to_string(), true)
-        .field("Queued Tracks", n_tracks.to_string(), true);
-
-    for (i, element) in next_tracks.iter().enumerate() {
-        match element {
-            QueueElement::Track(track) => {
-                if i == 0 {
-                    embed = embed.thumbnail(&track.thumbnail_url);
-                }
-                embed = embed.field(
-                    format!("{}. {}", i + 1, track.title),
-                    format!("{} | [Link]({})", track.channel, track.url),
-                    false,
-                );
-            }
-            QueueElement::Playlist(playlist) => {
-                if i == 0 {
-                    embed = embed.thumbnail(&playlist.thumbnail_url);
-                }
-                embed = embed.field(
-                    format!("{}. {} [Playlist]", i + 1, playlist.title),
+    let embed = create_embed_template()
+        .title("Jumped Ahead")
+        .description(format!(
+            "Jumped to position {position}. Tracks remaining: {remaining}"
+        ));
+    populate_track_info(embed, track)
+}
+
+// --- Queue Editing Embeds ---
+
+pub fn create_removed_track_embed(track: &Track, position: usize) -> serenity_prelude::CreateEmbed {
+    let embed = create_embed_template()
+        .title("Track Removed")
+        .description(format!("Removed the track at position {position}."));
+    populate_track_info(embed, track)
+}
+
+pub fn create_moved_track_embed(
+    track: &Track,
+    from: usize,
+    to: usize,
+) -> serenity_prelude::CreateEmbed {
+    let embed = create_embed_template()
+        .title("Track Moved")
+        .description(format!("Moved from position {from} to position {to}."));
+    populate_track_info(embed, track)
+}
+
+pub fn create_swapped_tracks_embed(
+    first: (&Track, usize),
+    second: (&Track, usize),
+) -> serenity_prelude::CreateEmbed {
+    let ((first, first_pos), (second, second_pos)) = (first, second);
+
+    create_embed_template()
+        .title("Tracks Swapped")
+        .field(
+            format!("Now at position {second_pos}"),
+            format!("[{}]({})", first.title, first.url),
+            false,
+        )
+        .field(
+            format!("Now at position {first_pos}"),
+            format!("[{}]({})", second.title, second.url),
+            false,
+        )
+}
+
+// --- Queue Overview ---
+
+/// A queued track as listed by the queue browser, with the title of its playlist if any.
+pub struct QueuedTrack {
+    pub track: Track,
+    pub playlist_title: Option<String>,
+}
+
+/// Builds one embed per page of the queue, numbering tracks by their position in play order.
+pub fn create_queue_embeds(
+    current_track: Option<&Track>,
+    queued: &[QueuedTrack],
+    page_size: usize,
+) -> Vec<serenity_prelude::CreateEmbed> {
+    let n_pages = queued.len().div_ceil(page_size);
+    let total_duration = format_total_duration(queued.iter().map(|entry| &entry.track));
+    let footer = |page: usize| {
+        serenity_prelude::CreateEmbedFooter::new(format!(
+            "Page {} of {n_pages} • {} {} queued • {total_duration} total",
+            page + 1,
+            queued.len(),
+            if queued.len() == 1 { "track" } else { "tracks" },
+        ))
+    };
+
+    queued
+        .chunks(page_size)
+        .enumerate()
+        .map(|(page, entries)| {
+            let listing = entries
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| {
+                    let mut line = format!(
+                        "**{}.** [{}]({}) | {}",
+                        page * page_size + i + 1,
+                        entry.track.title,
+                        entry.track.url,
+                        entry.track.artist
+                    );
+                    if entry.track.is_live {
+                        line.push_str(&format!(" | {LIVE_BADGE}"));
+                    }
+                    if let Some(playlist_title) = &entry.playlist_title {
+                        line.push_str(&format!(" | *{playlist_title}*"));
+                    }
+                    if let Some(requester) = entry.track.requested_by {
+                        line.push_str(&format!(" | {}", requester.mention()));
+                    }
+                    line
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            let mut embed = create_embed_template()
+                .title("Queue Overview")
+                .description(listing)
+                .footer(footer(page));
+
+            if let Some(track) = current_track {
+                embed = with_thumbnail(embed, track.artwork()).field(
+                    "Now Playing",
                     format!(
-                        "{} | {} tracks | [Link]({})",
-                        playlist.channel,
-                        playlist.items.len(),
-                        playlist.url
+                        "[{}]({}) | {}{}",
+                        track.title,
+                        track.url,
+                        track.artist,
+                        if track.is_live {
+                            format!(" | {LIVE_BADGE}")
+                        } else {
+                            String::new()
+                        }
                     ),
                     false,
                 );
             }
+
+            embed
+        })
+        .collect()
+}
+
+// --- Seek ---
+
+pub fn create_seek_embed(track: &Track, position: Duration) -> serenity_prelude::CreateEmbed {
+    create_embed_template().title("Seeked").description(format!(
+        "Jumped to **{}** in [{}]({}).",
+        format_duration(position),
+        track.title,
+        track.url
+    ))
+}
+
+// --- Volume ---
+
+pub fn create_volume_embed(volume: u8, previous: u8) -> serenity_prelude::CreateEmbed {
+    let description = if volume == previous {
+        format!("The volume is **{volume}%**.")
+    } else {
+        format!("The volume is now **{volume}%** (was {previous}%).")
+    };
+
+    create_embed_template()
+        .title("Volume")
+        .description(description)
+}
+
+pub fn create_default_volume_embed(volume: u8) -> serenity_prelude::CreateEmbed {
+    create_embed_template()
+        .title("Default Volume")
+        .description(format!(
+            "New sessions in this server now start at **{volume}%**. Use `/volume` to change the volume of the current session."
+        ))
+}
+
+pub fn create_crossfade_embed(crossfade: Duration, changed: bool) -> serenity_prelude::CreateEmbed {
+    let description = match (crossfade.as_secs(), changed) {
+        (0, false) => "Tracks play back to back without a crossfade.".to_string(),
+        (0, true) => "Crossfade disabled. Tracks now play back to back.".to_string(),
+        (secs, false) => format!("Tracks crossfade over **{secs}s**."),
+        (secs, true) => format!(
+            "Tracks now crossfade over **{secs}s**, starting with the next track. Tracks of unknown length still play back to back."
+        ),
+    };
+
+    create_embed_template()
+        .title("Crossfade")
+        .description(description)
+}
+
+pub fn create_fair_queue_embed(enabled: bool) -> serenity_prelude::CreateEmbed {
+    let description = if enabled {
+        "Fair queue enabled. Tracks queued from now on take turns between the members who requested them."
+    } else {
+        "Fair queue disabled. Tracks queued from now on play in the order they were queued."
+    };
+
+    create_embed_template()
+        .title("Fair Queue")
+        .description(description)
+}
+
+pub fn create_queue_limits_embed(settings: &GuildSettings) -> serenity_prelude::CreateEmbed {
+    let minutes = |limit: Option<Duration>| {
+        limit.map_or("Unlimited".to_string(), |max| {
+            format!("{} min", max.as_secs() / 60)
+        })
+    };
+
+    create_embed_template()
+        .title("Queue Limits")
+        .description(
+            "Limits on what each member may queue. DJs are exempt from all but the track length.",
+        )
+        .field(
+            "Tracks per Member",
+            settings
+                .max_queued_tracks
+                .map_or("Unlimited".to_string(), |max| max.to_string()),
+            true,
+        )
+        .field(
+            "Duration per Member",
+            minutes(settings.max_queued_duration),
+            true,
+        )
+        .field("Track Length", minutes(settings.max_track_length), true)
+}
+
+// --- Permissions ---
+
+fn describe_dj_role(role: Option<RoleId>) -> String {
+    match role {
+        Some(role) => role.mention().to_string(),
+        None => "any role named `DJ`".to_string(),
+    }
+}
+
+fn describe_command(command: &str) -> String {
+    match command {
+        SKIP_SEVERAL => "`/skip` (several tracks)".to_string(),
+        command => format!("`/{command}`"),
+    }
+}
+
+pub fn create_dj_role_embed(role: Option<RoleId>) -> serenity_prelude::CreateEmbed {
+    create_embed_template()
+        .title("DJ Role")
+        .description(format!(
+            "Members with {} or the Manage Server permission now count as DJs.",
+            describe_dj_role(role)
+        ))
+}
+
+pub fn create_command_policy_embed(
+    command: &str,
+    policy: CommandPolicy,
+    is_default: bool,
+) -> serenity_prelude::CreateEmbed {
+    let who = match policy {
+        CommandPolicy::Everyone => "Everyone",
+        CommandPolicy::Dj => "Only DJs, or anyone alone with me,",
+    };
+    let default = if is_default {
+        " This is the default."
+    } else {
+        ""
+    };
+
+    create_embed_template()
+        .title("Command Policy")
+        .description(format!(
+            "{who} can now use {}.{default}",
+            describe_command(command)
+        ))
+}
+
+pub fn create_command_policies_embed(
+    dj_role: Option<RoleId>,
+    policies: &[(String, CommandPolicy)],
+) -> serenity_prelude::CreateEmbed {
+    let list = |policy: CommandPolicy| {
+        let commands = policies
+            .iter()
+            .filter(|(_, p)| *p == policy)
+            .map(|(command, _)| describe_command(command))
+            .collect::<Vec<_>>();
+
+        if commands.is_empty() {
+            "None".to_string()
+        } else {
+            commands.join(", ")
+        }
+    };
+
+    create_embed_template()
+        .title("Command Policies")
+        .description(format!(
+            "DJs are members with {} or the Manage Server permission. Anyone alone with me counts as a DJ.",
+            describe_dj_role(dj_role)
+        ))
+        .field("DJ", list(CommandPolicy::Dj), false)
+        .field("Everyone", list(CommandPolicy::Everyone), false)
+}
+
+// --- Filters ---
+
+fn describe_filters(filters: &FilterChain) -> String {
+    if filters.is_empty() {
+        return "No filters are active.".to_string();
+    }
+
+    filters
+        .iter()
+        .map(|filter| format!("• {filter}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn create_filters_embed(filters: &FilterChain) -> serenity_prelude::CreateEmbed {
+    create_embed_template()
+        .title("Filters Updated")
+        .description(describe_filters(filters))
+}
+
+pub fn create_filter_presets_embed(filters: &FilterChain) -> serenity_prelude::CreateEmbed {
+    use poise::ChoiceParameter;
+
+    (0..).map_while(FilterPreset::from_index).fold(
+        create_embed_template()
+            .title("Filters")
+            .field("Active", describe_filters(filters), false),
+        |embed, preset| embed.field(preset.name(), preset.description(), false),
+    )
+}
+
+// --- History ---
+
+/// Builds one embed per page of recently played tracks, most recent first.
+pub fn create_history_embeds(
+    history: &[Track],
+    page_size: usize,
+) -> Vec<serenity_prelude::CreateEmbed> {
+    let n_pages = history.len().div_ceil(page_size);
+
+    history
+        .chunks(page_size)
+        .enumerate()
+        .map(|(page, tracks)| {
+            let listing = tracks
+                .iter()
+                .enumerate()
+                .map(|(i, track)| {
+                    format!(
+                        "**{}.** [{}]({}) | {}",
+                        page * page_size + i + 1,
+                        track.title,
+                        track.url,
+                        track.artist
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            create_embed_template()
+                .title("Recently Played")
+                .description(listing)
+                .footer(serenity_prelude::CreateEmbedFooter::new(format!(
+                    "Page {} of {n_pages}",
+                    page + 1
+                )))
+        })
+        .collect()
+}
+
+// --- Lyrics ---
+
+/// Splits the lyrics into pages of at most `page_length` characters, breaking between lines.
+/// Lines too long for a page of their own are wrapped.
+pub fn create_lyrics_embeds(
+    lyrics: &Lyrics,
+    page_length: usize,
+) -> Vec<serenity_prelude::CreateEmbed> {
+    let mut pages: Vec<String> = vec![String::new()];
+    for line in lyrics
+        .text
+        .lines()
+        .flat_map(|line| wrap_line(line, page_length))
+    {
+        let page = pages.last_mut().expect("pages start non-empty");
+        if !page.is_empty() && page.chars().count() + line.chars().count() + 1 > page_length {
+            pages.push(String::new());
+        }
+
+        let page = pages.last_mut().expect("pages start non-empty");
+        if !page.is_empty() {
+            page.push('\n');
+        }
+        page.push_str(&line);
+    }
+
+    let n_pages = pages.len();
+    pages
+        .into_iter()
+        .enumerate()
+        .map(|(page, text)| {
+            create_embed_template()
+                .title(format!("{} - {}", lyrics.artist, lyrics.title))
+                .description(text)
+                .footer(serenity_prelude::CreateEmbedFooter::new(format!(
+                    "Page {} of {n_pages}",
+                    page + 1
+                )))
+        })
+        .collect()
+}
+
+/// Breaks `line` into pieces of at most `width` characters, at spaces where possible.
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut pieces = Vec::new();
+    let mut rest = line.trim();
+
+    while rest.chars().count() > width {
+        let cut = rest
+            .char_indices()
+            .nth(width)
+            .map_or(rest.len(), |(i, _)| i);
+        let split = if rest[cut..].starts_with(' ') {
+            cut
+        } else {
+            rest[..cut].rfind(' ').filter(|&i| i > 0).unwrap_or(cut)
         };
+
+        pieces.push(rest[..split].trim_end().to_string());
+        rest = rest[split..].trim_start();
     }
 
-    embed
+    pieces.push(rest.to_string());
+    pieces
 }
 
 // --- Radio Mode Embeds ---
 
 pub fn create_radio_embed(
     is_enabled: bool,
-    seed_track: Option<&VideoMetadata>,
+    seed_track: Option<&Track>,
 ) -> serenity_prelude::CreateEmbed {
     let mut embed = create_embed_template()
         .title("Radio Mode")
@@ -195,14 +804,13 @@ pub fn create_radio_embed(
 
     if is_enabled {
         if let Some(track) = seed_track {
-            embed = embed
+            embed = with_thumbnail(embed, track.artwork())
                 .field(
                     "Anchored To",
                     format!("[{}]({})", track.title, track.url),
                     false,
                 )
-                .field("Channel", &track.channel, true)
-                .thumbnail(track.thumbnail_url.to_string());
+                .field("Artist", &track.artist, true);
         } else {
             embed = embed.description(
                 "Radio mode is **ON**.\nIt will start automatically queueing tracks once playback begins.",
@@ -213,9 +821,124 @@ pub fn create_radio_embed(
     embed
 }
 
-pub fn create_radio_playing_embed(track: &VideoMetadata) -> serenity_prelude::CreateEmbed {
+// --- Loop Mode Embeds ---
+
+pub fn create_loop_embed(
+    loop_mode: LoopMode,
+    is_radio_enabled: bool,
+) -> serenity_prelude::CreateEmbed {
+    let description = match loop_mode {
+        LoopMode::Off => "Looping is now **OFF**.",
+        LoopMode::Track => {
+            "Now looping the **current track**. Skipping moves on to the next track."
+        }
+        LoopMode::Queue => {
+            "Now looping the **queue**. Finished and skipped tracks return to the back of the queue."
+        }
+    };
+
+    let mut embed = create_embed_template()
+        .title("Loop Mode")
+        .description(description);
+
+    if is_radio_enabled && loop_mode != LoopMode::Off {
+        embed = embed.field(
+            "Radio Mode",
+            "Radio mode is paused while looping and resumes once looping is turned off.",
+            false,
+        );
+    }
+
+    embed
+}
+
+// --- Session Embeds ---
+
+/// Helper to summarize what a saved session will pick back up
+fn populate_session_info(
+    mut embed: serenity_prelude::CreateEmbed,
+    snapshot: &GuildSnapshot,
+) -> serenity_prelude::CreateEmbed {
+    let playback_state = &snapshot.guild_state.playback_state;
+
+    if let Some(track) = playback_state.get_current_track() {
+        embed = populate_track_info(embed, track).field(
+            "Left Off At",
+            format_duration(snapshot.position),
+            true,
+        );
+    }
+
+    embed = embed.field(
+        "Queued Tracks",
+        playback_state.number_of_tracks_queued().to_string(),
+        true,
+    );
+
+    if snapshot.n_dropped > 0 {
+        embed = embed.field(
+            "Not Restored",
+            format!(
+                "{} attached file(s), as their links expire. Please queue them again.",
+                snapshot.n_dropped
+            ),
+            false,
+        );
+    }
+
+    embed
+}
+
+pub fn create_session_available_embed(snapshot: &GuildSnapshot) -> serenity_prelude::CreateEmbed {
     let embed = create_embed_template()
-        .title("Radio Auto-Play")
-        .description("Automatically queued based on your listening history.");
-    populate_track_info(embed, track)
+        .title("Previous Session Available")
+        .description(
+            "Playback was interrupted by a restart. Join a voice channel and use `/resume-session` to pick up where you left off.",
+        );
+    populate_session_info(embed, snapshot)
+}
+
+pub fn create_session_restored_embed(snapshot: &GuildSnapshot) -> serenity_prelude::CreateEmbed {
+    let embed = create_embed_template()
+        .title("Session Restored")
+        .description("Picking up where you left off.");
+    populate_session_info(embed, snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track_lasting(secs: Option<u64>) -> Track {
+        Track {
+            duration: secs.map(Duration::from_secs),
+            ..Track::test("t")
+        }
+    }
+
+    #[test]
+    fn format_duration_shows_hours_only_when_needed() {
+        assert_eq!(format_duration(Duration::from_secs(59)), "0:59");
+        assert_eq!(format_duration(Duration::from_secs(61 * 60 + 5)), "1:01:05");
+    }
+
+    #[test]
+    fn format_total_duration_marks_unknown_lengths() {
+        let known = [track_lasting(Some(90)), track_lasting(Some(30))];
+        assert_eq!(format_total_duration(&known), "2:00");
+
+        let partly_known = [track_lasting(Some(90)), track_lasting(None)];
+        assert_eq!(format_total_duration(&partly_known), "1:30+");
+
+        assert_eq!(format_total_duration(&[]), "0:00");
+    }
+
+    #[test]
+    fn wrap_line_breaks_long_lines_at_spaces() {
+        assert_eq!(wrap_line("short", 10), ["short"]);
+        assert_eq!(wrap_line("one two three", 7), ["one two", "three"]);
+        assert_eq!(wrap_line("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(wrap_line("ääää öö", 4), ["ääää", "öö"]);
+        assert_eq!(wrap_line("", 4), [""]);
+    }
 }
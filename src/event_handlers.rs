@@ -1,4 +1,7 @@
+pub mod crossfade_handler;
 pub mod disconnect_handler;
 pub mod error_handler;
 pub mod inactivity_handler;
+pub mod now_playing_handler;
+pub mod prefetch_handler;
 pub mod queue_handler;
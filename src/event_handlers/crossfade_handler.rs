@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+use songbird::{Event, EventContext, EventHandler};
+use tracing::instrument;
+
+use crate::event_handlers::queue_handler::QueueHandler;
+
+/// Fires shortly before a track ends to fade the next one in over it.
+#[derive(Debug, Clone)]
+pub struct CrossfadeHandler {
+    queue_handler: QueueHandler,
+}
+
+impl CrossfadeHandler {
+    pub fn new(queue_handler: QueueHandler) -> Self {
+        Self { queue_handler }
+    }
+}
+
+#[async_trait]
+impl EventHandler for CrossfadeHandler {
+    #[instrument(skip_all)]
+    async fn act(&self, e: &EventContext<'_>) -> Option<Event> {
+        if let EventContext::Track(track_events) = e
+            && let Some((_, handle)) = track_events.first()
+        {
+            self.queue_handler.crossfade((*handle).clone()).await;
+        }
+
+        None
+    }
+}
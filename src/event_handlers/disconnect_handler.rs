@@ -6,13 +6,14 @@ use songbird::{Event, EventContext, EventHandler};
 use tokio::sync::RwLock;
 use tracing::{error, instrument, trace};
 
-use crate::models::GuildState;
+use crate::{models::GuildState, stream::StreamPrefetcher};
 
 #[derive(Debug)]
 pub struct DisconnectHandler {
     guild_id: GuildId,
     guild_map: Arc<RwLock<HashMap<String, GuildState>>>,
     handler: Arc<songbird::Songbird>,
+    stream_prefetcher: StreamPrefetcher,
 }
 
 impl DisconnectHandler {
@@ -20,11 +21,13 @@ impl DisconnectHandler {
         guild_id: &GuildId,
         guild_map: Arc<RwLock<HashMap<String, GuildState>>>,
         handler: Arc<songbird::Songbird>,
+        stream_prefetcher: StreamPrefetcher,
     ) -> Self {
         Self {
             guild_id: *guild_id,
             guild_map,
             handler,
+            stream_prefetcher,
         }
     }
 }
@@ -45,6 +48,8 @@ impl EventHandler for DisconnectHandler {
             }
         } // `guard` is explicitly dropped here automatically
 
+        self.stream_prefetcher.discard(&guild_key);
+
         // Ensure songbird removes its internal voice call handle regardless of map state
         if let Err(e) = self.handler.remove(self.guild_id).await {
             error!(err = %e, "Failed to remove guild songbird state from manager.");
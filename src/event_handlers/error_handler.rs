@@ -28,9 +28,9 @@ impl EventHandler for ErrorHandler {
             for (state, handle) in *track_events {
                 if let songbird::tracks::PlayMode::Errored(err) = &state.playing {
                     error!(err = %err, "Track playback error detected.");
-                    let embed = crate::embeds::create_error_embed(&format!(
+                    let embed = crate::embeds::create_error_embed(
                         "An error occurred while playing the next track",
-                    ));
+                    );
 
                     let _ = self
                         .channel_id
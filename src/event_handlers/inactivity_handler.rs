@@ -4,6 +4,8 @@ use songbird::{Event, EventContext, EventHandler};
 use std::sync::Arc;
 use tracing::{error, instrument, trace};
 
+use crate::checks;
+
 #[derive(Debug)]
 pub struct InactivityHandler {
     guild_id: GuildId,
@@ -31,21 +33,8 @@ impl EventHandler for InactivityHandler {
     async fn act(&self, _e: &EventContext<'_>) -> Option<Event> {
         let should_leave = {
             let guild = self.cache.guild(self.guild_id)?;
-
             let bot_id = self.cache.current_user().id;
-            let target_channel = guild.voice_states.get(&bot_id)?.channel_id?;
-
-            // Filter and count active human members inside our current channel
-            let human_count = guild
-                .voice_states
-                .values()
-                .filter(|vs| {
-                    vs.channel_id == Some(target_channel)
-                        && vs.member.as_ref().is_some_and(|m| !m.user.bot)
-                })
-                .count();
-
-            Some(human_count == 0)
+            checks::human_listeners(&guild, bot_id).map(|human_count| human_count == 0)
         }
         .unwrap_or(false);
 
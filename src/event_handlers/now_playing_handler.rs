@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use poise::serenity_prelude::{self, GuildId};
+use songbird::{Event, EventContext, EventHandler};
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::RwLock;
+use tracing::{error, instrument};
+
+use crate::{actions::panel_actions, models::GuildState};
+
+/// Keeps the now playing panel's progress up to date while a track plays.
+#[derive(Debug, Clone)]
+pub struct NowPlayingHandler {
+    serenity_ctx: serenity_prelude::Context,
+    guild_id: GuildId,
+    guild_map: Arc<RwLock<HashMap<String, GuildState>>>,
+}
+
+impl NowPlayingHandler {
+    pub fn new(
+        serenity_ctx: serenity_prelude::Context,
+        guild_id: &GuildId,
+        guild_map: Arc<RwLock<HashMap<String, GuildState>>>,
+    ) -> Self {
+        Self {
+            serenity_ctx,
+            guild_id: *guild_id,
+            guild_map,
+        }
+    }
+}
+
+#[async_trait]
+impl EventHandler for NowPlayingHandler {
+    #[instrument(skip_all, fields(guild_id = %self.guild_id))]
+    async fn act(&self, _e: &EventContext<'_>) -> Option<Event> {
+        if let Err(e) =
+            panel_actions::refresh_panel(&self.serenity_ctx, &self.guild_map, self.guild_id).await
+        {
+            error!(err = %e, "Failed to refresh now playing panel.");
+        }
+
+        None
+    }
+}
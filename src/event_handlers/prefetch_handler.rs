@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use songbird::{Event, EventContext, EventHandler};
+use tracing::instrument;
+
+use crate::event_handlers::queue_handler::QueueHandler;
+
+/// Fires shortly before the next track takes over to warm up its stream.
+#[derive(Debug, Clone)]
+pub struct PrefetchHandler {
+    queue_handler: QueueHandler,
+}
+
+impl PrefetchHandler {
+    pub fn new(queue_handler: QueueHandler) -> Self {
+        Self { queue_handler }
+    }
+}
+
+#[async_trait]
+impl EventHandler for PrefetchHandler {
+    #[instrument(skip_all)]
+    async fn act(&self, _: &EventContext<'_>) -> Option<Event> {
+        self.queue_handler.prefetch_next().await;
+        None
+    }
+}
@@ -1,96 +1,289 @@
 use async_trait::async_trait;
-use poise::serenity_prelude::{self, CacheHttp, GuildChannel, GuildId};
-use songbird::{Event, EventContext, EventHandler};
-use std::{collections::HashMap, sync::Arc};
+use poise::serenity_prelude::{self, GuildId};
+use songbird::{
+    Event, EventContext, EventHandler,
+    tracks::{PlayMode, Track as SongbirdTrack, TrackHandle},
+};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::sync::{Mutex, RwLock};
 use tracing::{error, instrument, trace};
 
 use crate::{
-    embeds,
-    models::{GuildState, VideoMetadata, YoutubeClient},
-    stream,
+    actions::{panel_actions, playback_actions},
+    event_handlers::{
+        crossfade_handler::CrossfadeHandler, now_playing_handler::NowPlayingHandler,
+        prefetch_handler::PrefetchHandler,
+    },
+    models::{GuildState, MetadataProviders, Track},
+    stream::{StreamOptions, StreamPrefetcher},
 };
 
+/// Interval between volume adjustments while crossfading.
+const CROSSFADE_STEP: Duration = Duration::from_millis(100);
+
 #[derive(Debug, Clone)]
 pub struct QueueHandler {
     serenity_ctx: serenity_prelude::Context,
     guild_id: GuildId,
-    guild_channel: GuildChannel,
     guild_map: Arc<RwLock<HashMap<String, GuildState>>>,
     handler: Arc<Mutex<songbird::Call>>,
-    youtube_client: YoutubeClient,
+    metadata_providers: MetadataProviders,
+    stream_prefetcher: StreamPrefetcher,
 }
 
 impl QueueHandler {
     pub fn new(
         serenity_ctx: serenity_prelude::Context,
         guild_id: &GuildId,
-        guild_channel: GuildChannel,
         guild_map: Arc<RwLock<HashMap<String, GuildState>>>,
         handler: Arc<Mutex<songbird::Call>>,
-        youtube_client: YoutubeClient,
+        metadata_providers: MetadataProviders,
+        stream_prefetcher: StreamPrefetcher,
     ) -> Self {
         Self {
             serenity_ctx,
             guild_id: *guild_id,
-            guild_channel,
             guild_map,
             handler,
-            youtube_client,
+            metadata_providers,
+            stream_prefetcher,
         }
     }
+
+    /// Attaches the handlers that follow a track through its playback. The track must already be
+    /// stored as the guild's current track handle.
+    pub async fn register(&self, track_handle: &TrackHandle) {
+        if let Err(e) =
+            track_handle.add_event(Event::Track(songbird::TrackEvent::End), self.clone())
+        {
+            error!(err = %e, "Failed to add queue event handler.");
+        }
+
+        if let Err(e) = track_handle.add_event(
+            Event::Periodic(panel_actions::PANEL_REFRESH_INTERVAL, None),
+            NowPlayingHandler::new(
+                self.serenity_ctx.clone(),
+                &self.guild_id,
+                self.guild_map.clone(),
+            ),
+        ) {
+            error!(err = %e, "Failed to add now playing event handler.");
+        }
+
+        let crossfade_start = self
+            .guild_map
+            .read()
+            .await
+            .get(&self.guild_id.to_string())
+            .and_then(GuildState::crossfade_start);
+
+        if let Some(start) = crossfade_start
+            && let Err(e) =
+                track_handle.add_event(Event::Delayed(start), CrossfadeHandler::new(self.clone()))
+        {
+            error!(err = %e, "Failed to add crossfade event handler.");
+        }
+
+        let prefetch_start = self
+            .guild_map
+            .read()
+            .await
+            .get(&self.guild_id.to_string())
+            .and_then(GuildState::prefetch_start);
+
+        if let Some(start) = prefetch_start
+            && let Err(e) =
+                track_handle.add_event(Event::Delayed(start), PrefetchHandler::new(self.clone()))
+        {
+            error!(err = %e, "Failed to add prefetch event handler.");
+        }
+    }
+
+    /// Warms up the stream of the track expected to play next.
+    pub async fn prefetch_next(&self) {
+        playback_actions::prefetch_next_track(
+            &self.guild_map,
+            &self.stream_prefetcher,
+            self.guild_id,
+        )
+        .await;
+    }
+
+    /// Starts the next track underneath `fading` and ramps the two volumes against each other.
+    /// Once the ramp completes, `fading` is stopped and its end is ignored as stale.
+    #[instrument(skip_all, fields(guild_id = %self.guild_id))]
+    pub async fn crossfade(&self, fading: TrackHandle) {
+        let guild_key = self.guild_id.to_string();
+
+        let settings = self
+            .guild_map
+            .read()
+            .await
+            .get(&guild_key)
+            .and_then(|state| {
+                let playback_state = &state.playback_state;
+                let has_next = playback_state.expected_next_track().is_some()
+                    || playback_state.get_radio_seed().is_some();
+
+                has_next.then_some((state.crossfade, state.volume_gain()))
+            });
+
+        let Some((fade, volume)) = settings else {
+            trace!("Nothing to crossfade into. Letting the track finish.");
+            return;
+        };
+
+        // If the next track cannot be resolved after all, the fading track plays out and its end
+        // finds the queue already exhausted.
+        let Advance::Next(track) = self.advance(Some(fading.clone()), false).await else {
+            return;
+        };
+
+        trace!(?track, ?fade, "Crossfading into next track.");
+        let Some(incoming) = self.play_and_notify(track, &guild_key, true).await else {
+            return;
+        };
+
+        // Ramp in the background so the driver's other events are not held up.
+        tokio::spawn(async move {
+            let steps = (fade.as_millis() / CROSSFADE_STEP.as_millis()).max(1) as u32;
+            let mut interval = tokio::time::interval(CROSSFADE_STEP);
+
+            for step in 1..=steps {
+                interval.tick().await;
+                let progress = step as f32 / steps as f32;
+                _ = fading.set_volume(volume * (1.0 - progress));
+                _ = incoming.set_volume(volume * progress);
+            }
+
+            _ = fading.stop();
+        });
+    }
+}
+
+/// What follows a track once it stops being the current one.
+enum Advance {
+    /// The track had already been replaced, so the queue was left alone.
+    Stale,
+    /// Nothing is left to play.
+    Finished,
+    Next(Track),
 }
 
 #[async_trait]
 impl EventHandler for QueueHandler {
     #[instrument(skip_all, fields(guild_id = %self.guild_id))]
-    async fn act(&self, _e: &EventContext<'_>) -> Option<Event> {
+    async fn act(&self, e: &EventContext<'_>) -> Option<Event> {
         trace!("Track has ended. Handler called to action.");
         let guild_key = self.guild_id.to_string();
 
-        let (queued_track, radio_seed) = {
+        // Errored tracks must not be replayed by track loop mode.
+        let (errored, ended) = match e {
+            EventContext::Track(track_events) => (
+                track_events
+                    .iter()
+                    .any(|(state, _)| matches!(state.playing, PlayMode::Errored(_))),
+                track_events.first().map(|(_, handle)| (*handle).clone()),
+            ),
+            _ => (false, None),
+        };
+
+        match self.advance(ended, errored).await {
+            Advance::Stale => {}
+            Advance::Finished => {
+                trace!("No track to play, stopping playback.");
+                self.stream_prefetcher.discard(&guild_key);
+                if let Err(e) = panel_actions::close_panel(
+                    &self.serenity_ctx,
+                    &self.guild_map,
+                    self.guild_id,
+                    "Playback has ended. Queue something new with `/play`.",
+                )
+                .await
+                {
+                    error!(err = %e, "Failed to close now playing panel.");
+                }
+            }
+            Advance::Next(track) => {
+                trace!(?track, "Next track resolved for playback.");
+                self.play_and_notify(track, &guild_key, false).await;
+            }
+        }
+
+        None
+    }
+}
+
+impl QueueHandler {
+    /// Steps the queue past the track whose stream is `ended` and resolves what plays next.
+    async fn advance(&self, ended: Option<TrackHandle>, errored: bool) -> Advance {
+        let guild_key = self.guild_id.to_string();
+
+        let (queued_track, radio_seed, recently_played, repeated) = {
             let mut map_guard = self.guild_map.write().await;
-            let guild_state = map_guard.get_mut(&guild_key)?;
+            let Some(guild_state) = map_guard.get_mut(&guild_key) else {
+                return Advance::Stale;
+            };
 
-            guild_state.playback_state.play_next();
+            // A stream replaced by a restart or crossfade ends while playback carries on.
+            if let (Some(ended), Some(current)) =
+                (ended, guild_state.playback_state.get_track_handle())
+                && current.uuid() != ended.uuid()
+            {
+                trace!("Replaced stream ended. Ignoring.");
+                return Advance::Stale;
+            }
+
+            if errored {
+                guild_state.playback_state.skip_current_track();
+            }
+
+            let repeated = guild_state.playback_state.play_next();
             (
                 guild_state.playback_state.get_current_track().clone(),
                 guild_state.playback_state.get_radio_seed(),
+                guild_state
+                    .playback_state
+                    .get_history()
+                    .map(|t| t.id.clone())
+                    .collect::<Vec<_>>(),
+                repeated,
             )
         };
 
-        let Some((track, embed)) = self
-            .resolve_next_track(queued_track, radio_seed, &guild_key)
-            .await
-        else {
-            trace!("No track to play, stopping playback.");
-            return None;
-        };
-
-        trace!(?track, "Next track resolved for playback.");
-        self.play_and_notify(track, embed, &guild_key).await;
+        if repeated {
+            trace!(?queued_track, "Track loop mode active. Replaying track.");
+        }
 
-        None
+        match self
+            .resolve_next_track(queued_track, radio_seed, &recently_played, &guild_key)
+            .await
+        {
+            Some(track) => Advance::Next(track),
+            None => Advance::Finished,
+        }
     }
-}
 
-impl QueueHandler {
-    #[instrument(skip(self))]
+    #[instrument(skip(self, recently_played))]
     async fn resolve_next_track(
         &self,
-        queued_track: Option<VideoMetadata>,
+        queued_track: Option<Track>,
         radio_seed: Option<String>,
+        recently_played: &[String],
         guild_key: &str,
-    ) -> Option<(VideoMetadata, serenity_prelude::CreateEmbed)> {
-        if let Some(track) = queued_track {
-            let embed = embeds::create_playing_track_embed(&track);
-            return Some((track, embed));
+    ) -> Option<Track> {
+        if queued_track.is_some() {
+            return queued_track;
         }
 
         let seed_url = radio_seed?;
         trace!(%seed_url, "Queue empty. Radio mode active. Fetching related track.");
 
-        let radio_track = match self.youtube_client.get_related_video(&seed_url).await {
+        let radio_track = match self
+            .metadata_providers
+            .related_track(&seed_url, recently_played)
+            .await
+        {
             Ok(t) => t,
             Err(e) => {
                 error!(err = %e, "Radio mode failed to fetch a related track.");
@@ -107,49 +300,63 @@ impl QueueHandler {
             guild_state.playback_state.set_playing(true);
         }
 
-        let embed = embeds::create_radio_playing_embed(&radio_track);
-        Some((radio_track, embed))
+        Some(radio_track)
     }
 
-    #[instrument(skip(self, embed))]
+    /// Plays `track` as the guild's current track. When fading in, it starts silent and is left
+    /// for the caller to ramp up.
+    #[instrument(skip(self))]
     async fn play_and_notify(
         &self,
-        track: VideoMetadata,
-        embed: serenity_prelude::CreateEmbed,
+        track: Track,
         guild_key: &str,
-    ) {
-        let (mut call_guard, message_res) = tokio::join!(
-            self.handler.lock(),
-            self.guild_channel.send_message(
-                self.serenity_ctx.http(),
-                poise::serenity_prelude::CreateMessage::default().embed(embed)
-            )
-        );
+        fade_in: bool,
+    ) -> Option<TrackHandle> {
+        let (volume, stream_options) = self
+            .guild_map
+            .read()
+            .await
+            .get(guild_key)
+            .map(|state| {
+                (
+                    state.volume_gain(),
+                    state.stream_options(&track, Duration::ZERO),
+                )
+            })
+            .unwrap_or((1.0, StreamOptions::default()));
 
-        if let Err(e) = message_res {
-            error!(err = %e, "Failed to send playback embed notification.");
-        }
+        let track_input =
+            match self
+                .stream_prefetcher
+                .take_or_create(guild_key, &track.url, &stream_options)
+            {
+                Ok(track_input) => track_input,
+                Err(err_msg) => {
+                    error!(err = %err_msg, "Failed to transition to the next track stream.");
+                    return None;
+                }
+            };
+
+        let track_handle = self
+            .handler
+            .lock()
+            .await
+            .play(SongbirdTrack::from(track_input).volume(if fade_in { 0.0 } else { volume }));
 
-        match stream::create_audio_stream(&track.url) {
-            Ok(track_input) => {
-                let track_handle = call_guard.play(track_input.into());
+        if let Some(guild_state) = self.guild_map.write().await.get_mut(guild_key) {
+            guild_state
+                .playback_state
+                .set_track_handle(Some(track_handle.clone()));
+        }
 
-                if let Err(e) =
-                    track_handle.add_event(Event::Track(songbird::TrackEvent::End), self.clone())
-                {
-                    error!(err = %e, "Failed to cascade next track event handler.");
-                }
+        self.register(&track_handle).await;
 
-                let mut map_guard = self.guild_map.write().await;
-                if let Some(guild_state) = map_guard.get_mut(guild_key) {
-                    guild_state
-                        .playback_state
-                        .set_track_handle(Some(track_handle));
-                }
-            }
-            Err(err_msg) => {
-                error!(err = %err_msg, "Failed to transition to the next track stream.");
-            }
+        if let Err(e) =
+            panel_actions::refresh_panel(&self.serenity_ctx, &self.guild_map, self.guild_id).await
+        {
+            error!(err = %e, "Failed to refresh now playing panel.");
         }
+
+        Some(track_handle)
     }
 }
@@ -14,4 +14,11 @@ pub enum Metric {
     #[strum(serialize = "stream_startup_duration_seconds")]
     StreamStartupDuration,
     AudioBytesStreamedTotal,
+    StreamPrefetchTotal,
+
+    // Metadata lookups
+    MetadataCacheTotal,
+    YoutubeQuotaUnitsTotal,
+    YoutubeApiErrorsTotal,
+    YoutubeFallbackTotal,
 }
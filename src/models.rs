@@ -1,19 +1,42 @@
+mod audio_file;
+mod audio_filter;
+mod command_policy;
+mod guild_settings;
+mod guild_snapshot;
 mod guild_state;
+mod lyrics;
+mod metadata_provider;
 mod playback_state;
+mod playlist;
+mod public_address;
 mod queue_element;
+mod search_suggestions;
+mod skip_votes;
+mod track;
+mod ytdlp;
 
 mod youtube;
 
+pub use audio_file::{AudioFileError, AudioFileProvider, input_format, open_audio_file};
+pub use audio_filter::{AudioFilter, FilterChain, FilterPreset};
+pub use command_policy::{CommandPolicy, SKIP_SEVERAL};
+pub use guild_settings::{DEFAULT_VOLUME, GuildSettings, MAX_VOLUME, SettingsError};
+pub use guild_snapshot::{GuildSnapshot, SnapshotError};
 pub use guild_state::GuildState;
-pub use playback_state::PlaybackState;
+pub use lyrics::{LrclibProvider, Lyrics, LyricsError, LyricsProvider, LyricsQuery};
+pub use metadata_provider::{MetadataError, MetadataProvider, MetadataProviders};
+pub use playback_state::{LoopMode, PlaybackState};
+pub use playlist::Playlist;
+pub use public_address::{AddressError, PublicResolver};
 pub use queue_element::QueueElement;
+pub use search_suggestions::{SearchSuggestions, Suggestion};
+pub use skip_votes::SkipVotes;
+pub use track::{StreamLocator, Track, TrackSource, UNKNOWN_ARTIST};
+pub use ytdlp::{EXTRACTORS, YtdlpError, YtdlpProvider};
 
-pub use youtube::{
-    YoutubeClient, YoutubeMetadata, playlist_metadata::PlaylistMetadata,
-    video_metadata::VideoMetadata,
-};
+pub use youtube::{CacheError, MetadataCache, YoutubeClient, YoutubeError};
 
-use crate::{models::youtube::YoutubeError, stream::StreamError};
+use crate::stream::StreamError;
 
 #[derive(thiserror::Error, Debug)]
 pub enum LunaError {
@@ -31,8 +54,11 @@ pub enum RuntimeError {
     #[error("Discord error occurred. {0}")]
     Discord(#[from] DiscordError),
 
-    #[error("Youtube error occurred. {0}")]
-    Youtube(#[from] YoutubeError),
+    #[error("Metadata error occurred. {0}")]
+    Metadata(MetadataError),
+
+    #[error("Lyrics error occurred. {0}")]
+    Lyrics(#[from] LyricsError),
 
     #[error("{0}")]
     User(String),
@@ -41,6 +67,16 @@ pub enum RuntimeError {
     Unimplemented,
 }
 
+/// Errors caused by the request itself, such as an unsupported link, are reported as is.
+impl From<MetadataError> for RuntimeError {
+    fn from(value: MetadataError) -> Self {
+        match value.user_message() {
+            Some(msg) => RuntimeError::User(msg),
+            None => RuntimeError::Metadata(value),
+        }
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum DiscordError {
     #[error("Gateway error: {0}")]
@@ -66,4 +102,10 @@ pub enum InternalError {
 
     #[error("Streaming error: {0}")]
     Stream(#[from] StreamError),
+
+    #[error("Session snapshot error: {0}")]
+    Snapshot(#[from] SnapshotError),
+
+    #[error("Guild settings error: {0}")]
+    Settings(#[from] SettingsError),
 }
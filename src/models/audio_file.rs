@@ -0,0 +1,301 @@
+use async_trait::async_trait;
+use percent_encoding::percent_decode_str;
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    io::Cursor,
+    sync::LazyLock,
+    time::Duration,
+};
+
+use symphonia::core::{
+    formats::{FormatOptions, FormatReader},
+    io::MediaSourceStream,
+    meta::{MetadataOptions, MetadataRevision, StandardTagKey},
+    probe::Hint,
+};
+use tracing::{instrument, trace};
+use url::Url;
+
+use super::{
+    AddressError, MetadataError, MetadataProvider, QueueElement, StreamLocator, Track, TrackSource,
+    UNKNOWN_ARTIST, public_address,
+};
+
+/// File extensions of the audio formats that can be played from a direct link.
+pub const AUDIO_EXTENSIONS: [&str; 6] = ["mp3", "ogg", "oga", "opus", "flac", "wav"];
+
+/// Most bytes downloaded to read a file's tags. The length of longer files is estimated from
+/// their size and the audio in the bytes read.
+const MAX_PROBE_BYTES: usize = 8 * 1024 * 1024;
+
+/// How long downloading the start of a file may take altogether.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Client for streaming files while they play, which may take as long as the file lasts.
+static STREAM_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    public_address::client_builder()
+        .build()
+        .expect("Failed to build HTTP client")
+});
+
+#[derive(thiserror::Error, Debug)]
+pub enum AudioFileError {
+    #[error("Only http(s) links to {} files can be played.", AUDIO_EXTENSIONS.join("/"))]
+    Unsupported,
+
+    #[error("{0}")]
+    Address(#[from] AddressError),
+
+    #[error("I couldn't download that file.")]
+    Request(#[from] reqwest::Error),
+
+    #[error("That file doesn't look like audio I can play.")]
+    Probe(#[from] symphonia::core::errors::Error),
+}
+
+/// The lowercase extension of the file `url` points at, if it is a supported audio format.
+fn audio_extension(url: &Url) -> Option<String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+
+    let file_name = url.path_segments()?.next_back()?;
+    let (_, extension) = file_name.rsplit_once('.')?;
+    let extension = extension.to_lowercase();
+    AUDIO_EXTENSIONS
+        .contains(&extension.as_str())
+        .then_some(extension)
+}
+
+/// The ffmpeg demuxer for the audio file `url` points at, so that ffmpeg doesn't guess the format
+/// from what the server sends.
+pub fn input_format(url: &Url) -> Option<&'static str> {
+    match audio_extension(url)?.as_str() {
+        "mp3" => Some("mp3"),
+        "ogg" | "oga" | "opus" => Some("ogg"),
+        "flac" => Some("flac"),
+        "wav" => Some("wav"),
+        _ => None,
+    }
+}
+
+/// Plays audio files linked directly, such as Discord attachments. Links are only followed to
+/// public addresses.
+#[derive(Debug, Clone)]
+pub struct AudioFileProvider {
+    client: reqwest::Client,
+}
+
+impl AudioFileProvider {
+    pub fn new() -> Self {
+        let client = public_address::client_builder()
+            .timeout(PROBE_TIMEOUT)
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Self { client }
+    }
+}
+
+impl Default for AudioFileProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MetadataProvider for AudioFileProvider {
+    fn supports_url(&self, url: &Url) -> bool {
+        audio_extension(url).is_some()
+    }
+
+    async fn resolve_url(&self, url: &Url) -> Result<QueueElement, MetadataError> {
+        Ok(QueueElement::Track(
+            probe_audio_file(&self.client, url).await?,
+        ))
+    }
+}
+
+/// Requests the audio file at `url` for playback, leaving its body to be read as it plays.
+pub async fn open_audio_file(url: &Url) -> Result<reqwest::Response, AudioFileError> {
+    audio_extension(url).ok_or(AudioFileError::Unsupported)?;
+    public_address::ensure_public(url).await?;
+
+    Ok(STREAM_CLIENT
+        .get(url.clone())
+        .send()
+        .await?
+        .error_for_status()?)
+}
+
+/// Downloads the start of the audio file at `url` and reads its title, artist and length from
+/// its tags. Files without tags are named after the file.
+#[instrument(skip(client))]
+async fn probe_audio_file(client: &reqwest::Client, url: &Url) -> Result<Track, AudioFileError> {
+    let extension = audio_extension(url).ok_or(AudioFileError::Unsupported)?;
+    public_address::ensure_public(url).await?;
+
+    let mut response = client.get(url.clone()).send().await?.error_for_status()?;
+    let file_size = response.content_length();
+    let mut bytes = Vec::new();
+    let mut is_complete = true;
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() >= MAX_PROBE_BYTES {
+            is_complete = false;
+            break;
+        }
+    }
+    trace!(n_bytes = bytes.len(), is_complete, "Downloaded audio file.");
+
+    let mut hint = Hint::new();
+    hint.with_extension(&extension);
+
+    let n_bytes = bytes.len();
+    let stream = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+    let mut probed = symphonia::default::get_probe().format(
+        &hint,
+        stream,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+
+    // Tags may sit in front of the container, as ID3 does, or inside it.
+    let (mut title, mut artist) = probed
+        .metadata
+        .get()
+        .and_then(|metadata| metadata.current().map(read_tags))
+        .unwrap_or_default();
+    if let Some(revision) = probed.format.metadata().current() {
+        let (container_title, container_artist) = read_tags(revision);
+        title = title.or(container_title);
+        artist = artist.or(container_artist);
+    }
+
+    // The length in the headers can't be trusted when only part of the file was read.
+    let duration = if is_complete {
+        probed
+            .format
+            .default_track()
+            .map(|track| &track.codec_params)
+            .and_then(|params| Some(params.time_base?.calc_time(params.n_frames?)))
+            .map(|time| Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac))
+    } else {
+        file_size.and_then(|size| estimate_duration(probed.format.as_mut(), n_bytes, size))
+    };
+
+    let file_name = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .map(|segment| percent_decode_str(segment).decode_utf8_lossy().into_owned())
+        .unwrap_or_default();
+    let fallback_title = file_name
+        .rsplit_once('.')
+        .map_or(file_name.as_str(), |(stem, _)| stem)
+        .replace('_', " ");
+
+    Ok(Track {
+        id: file_id(url),
+        title: title.unwrap_or(fallback_title),
+        artist: artist.unwrap_or_else(|| UNKNOWN_ARTIST.to_string()),
+        url: url.to_string(),
+        artwork_url: None,
+        duration,
+        is_live: false,
+        source: TrackSource::File,
+        locator: StreamLocator::Direct,
+        requested_by: None,
+    })
+}
+
+/// Estimates the length of a file of `file_size` bytes from how much audio its first `n_read`
+/// bytes hold.
+fn estimate_duration(
+    format: &mut dyn FormatReader,
+    n_read: usize,
+    file_size: u64,
+) -> Option<Duration> {
+    let track = format.default_track()?;
+    let (track_id, time_base) = (track.id, track.codec_params.time_base?);
+
+    let mut end = 0;
+    while let Ok(packet) = format.next_packet() {
+        if packet.track_id() == track_id {
+            end = packet.ts + packet.dur;
+        }
+    }
+
+    let time = time_base.calc_time(end);
+    let seconds_read = time.seconds as f64 + time.frac;
+    if seconds_read <= 0.0 || n_read == 0 {
+        return None;
+    }
+
+    Duration::try_from_secs_f64(seconds_read * file_size as f64 / n_read as f64).ok()
+}
+
+/// A short ID for the file at `url`, which must fit into component custom IDs. The query is left
+/// out as Discord refreshes the signatures of attachment links.
+fn file_id(url: &Url) -> String {
+    let mut hasher = DefaultHasher::new();
+    (url.host_str(), url.path()).hash(&mut hasher);
+    format!("file:{:016x}", hasher.finish())
+}
+
+/// The title and artist tags of a metadata revision.
+fn read_tags(revision: &MetadataRevision) -> (Option<String>, Option<String>) {
+    let find = |key: StandardTagKey| {
+        revision
+            .tags()
+            .iter()
+            .find(|tag| tag.std_key == Some(key))
+            .map(|tag| tag.value.to_string())
+            .filter(|value| !value.trim().is_empty())
+    };
+
+    (
+        find(StandardTagKey::TrackTitle),
+        find(StandardTagKey::Artist),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn input_format_follows_the_extension() {
+        assert_eq!(input_format(&url("https://example.com/a.MP3")), Some("mp3"));
+        assert_eq!(
+            input_format(&url("https://example.com/a.opus?x=1")),
+            Some("ogg")
+        );
+        assert_eq!(input_format(&url("https://example.com/a.txt")), None);
+        assert_eq!(input_format(&url("file:///tmp/a.mp3")), None);
+    }
+
+    #[test]
+    fn file_id_ignores_the_query() {
+        assert_eq!(
+            file_id(&url("https://cdn.discordapp.com/a.mp3?ex=1")),
+            file_id(&url("https://cdn.discordapp.com/a.mp3?ex=2"))
+        );
+    }
+
+    #[test]
+    fn attachment_links_expire() {
+        let file = |url: &str| Track {
+            url: url.to_string(),
+            locator: StreamLocator::Direct,
+            ..Track::test("a")
+        };
+
+        assert!(file("https://cdn.discordapp.com/attachments/1/2/a.mp3?ex=1").has_expiring_link());
+        assert!(!file("https://example.com/a.mp3").has_expiring_link());
+        assert!(!Track::test("a").has_expiring_link());
+    }
+}
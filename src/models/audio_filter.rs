@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Sample rate the filters resample to, so rate-changing presets behave the same for every source.
+const FILTER_SAMPLE_RATE: u32 = 48_000;
+
+/// Center frequencies, in Hz, of the equalizer's bands.
+pub const EQUALIZER_BANDS: [u32; 10] = [31, 62, 125, 250, 500, 1_000, 2_000, 4_000, 8_000, 16_000];
+
+const EQUALIZER_GAIN_RANGE: (f64, f64) = (-20.0, 20.0);
+const BASS_BOOST_RANGE: (f64, f64) = (1.0, 20.0);
+const SPEED_RANGE: (f64, f64) = (0.5, 2.0);
+const PITCH_RANGE: (f64, f64) = (-12.0, 12.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum FilterPreset {
+    #[name = "Bass Boost"]
+    BassBoost,
+    Nightcore,
+    Vaporwave,
+    Speed,
+    Pitch,
+    #[name = "8D"]
+    EightD,
+    Equalizer,
+}
+
+impl FilterPreset {
+    pub fn description(self) -> &'static str {
+        match self {
+            FilterPreset::BassBoost => "Boosts low frequencies. `amount`: gain in dB (1 to 20).",
+            FilterPreset::Nightcore => "Speeds up and raises the pitch.",
+            FilterPreset::Vaporwave => "Slows down and lowers the pitch.",
+            FilterPreset::Speed => {
+                "Changes the tempo without touching the pitch. `amount`: factor (0.5 to 2)."
+            }
+            FilterPreset::Pitch => {
+                "Shifts the pitch without touching the tempo. `amount`: semitones (-12 to 12)."
+            }
+            FilterPreset::EightD => "Pans the audio around your head. Best with headphones.",
+            FilterPreset::Equalizer => {
+                "Ten band equalizer. `bands`: up to ten gains in dB (-20 to 20), from 31 Hz to 16 kHz."
+            }
+        }
+    }
+}
+
+/// A configured filter, translated into an ffmpeg audio filter when a stream is created.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AudioFilter {
+    BassBoost { gain_db: f64 },
+    Nightcore,
+    Vaporwave,
+    Speed { factor: f64 },
+    Pitch { semitones: f64 },
+    EightD,
+    Equalizer { gains_db: [f64; 10] },
+}
+
+impl AudioFilter {
+    /// Builds a filter from a preset and the user's optional settings, falling back to sensible
+    /// defaults.
+    pub fn from_preset(
+        preset: FilterPreset,
+        amount: Option<f64>,
+        bands: Option<&str>,
+    ) -> Result<Self, String> {
+        let filter = match preset {
+            FilterPreset::BassBoost => AudioFilter::BassBoost {
+                gain_db: checked_amount(amount.unwrap_or(10.0), BASS_BOOST_RANGE, "Gain")?,
+            },
+            FilterPreset::Nightcore => AudioFilter::Nightcore,
+            FilterPreset::Vaporwave => AudioFilter::Vaporwave,
+            FilterPreset::Speed => AudioFilter::Speed {
+                factor: checked_amount(amount.unwrap_or(1.25), SPEED_RANGE, "Speed")?,
+            },
+            FilterPreset::Pitch => AudioFilter::Pitch {
+                semitones: checked_amount(amount.unwrap_or(2.0), PITCH_RANGE, "Pitch")?,
+            },
+            FilterPreset::EightD => AudioFilter::EightD,
+            FilterPreset::Equalizer => AudioFilter::Equalizer {
+                gains_db: parse_bands(bands.ok_or_else(|| {
+                    "Please provide the equalizer `bands`, e.g. `4, 3, 1, 0, 0, 0, 1, 2, 3, 3`."
+                        .to_string()
+                })?)?,
+            },
+        };
+
+        Ok(filter)
+    }
+
+    pub fn preset(&self) -> FilterPreset {
+        match self {
+            AudioFilter::BassBoost { .. } => FilterPreset::BassBoost,
+            AudioFilter::Nightcore => FilterPreset::Nightcore,
+            AudioFilter::Vaporwave => FilterPreset::Vaporwave,
+            AudioFilter::Speed { .. } => FilterPreset::Speed,
+            AudioFilter::Pitch { .. } => FilterPreset::Pitch,
+            AudioFilter::EightD => FilterPreset::EightD,
+            AudioFilter::Equalizer { .. } => FilterPreset::Equalizer,
+        }
+    }
+
+    /// How many seconds of the source are played per second of output.
+    pub fn playback_rate(&self) -> f64 {
+        match self {
+            AudioFilter::Nightcore => 1.25,
+            AudioFilter::Vaporwave => 0.8,
+            AudioFilter::Speed { factor } => *factor,
+            _ => 1.0,
+        }
+    }
+
+    fn to_ffmpeg(&self) -> String {
+        let resample = |ratio: f64| {
+            format!(
+                "aresample={FILTER_SAMPLE_RATE},asetrate={FILTER_SAMPLE_RATE}*{ratio},aresample={FILTER_SAMPLE_RATE}"
+            )
+        };
+
+        match self {
+            AudioFilter::BassBoost { gain_db } => format!("bass=g={gain_db}:f=110:w=0.6"),
+            AudioFilter::Nightcore | AudioFilter::Vaporwave => resample(self.playback_rate()),
+            AudioFilter::Speed { factor } => format!("atempo={factor}"),
+            AudioFilter::Pitch { semitones } => {
+                let ratio = 2f64.powf(semitones / 12.0);
+                format!("{},atempo={}", resample(ratio), 1.0 / ratio)
+            }
+            AudioFilter::EightD => "apulsator=hz=0.125".to_string(),
+            AudioFilter::Equalizer { gains_db } => EQUALIZER_BANDS
+                .iter()
+                .zip(gains_db)
+                .filter(|(_, gain)| **gain != 0.0)
+                .map(|(freq, gain)| format!("equalizer=f={freq}:t=o:w=1:g={gain}"))
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+impl Display for AudioFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AudioFilter::BassBoost { gain_db } => write!(f, "Bass Boost (+{gain_db} dB)"),
+            AudioFilter::Nightcore => write!(f, "Nightcore"),
+            AudioFilter::Vaporwave => write!(f, "Vaporwave"),
+            AudioFilter::Speed { factor } => write!(f, "Speed (×{factor})"),
+            AudioFilter::Pitch { semitones } => write!(f, "Pitch ({semitones:+} semitones)"),
+            AudioFilter::EightD => write!(f, "8D"),
+            AudioFilter::Equalizer { gains_db } => write!(
+                f,
+                "Equalizer ({})",
+                gains_db
+                    .iter()
+                    .map(|g| format!("{g:+}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+fn checked_amount(amount: f64, (min, max): (f64, f64), name: &str) -> Result<f64, String> {
+    if !(min..=max).contains(&amount) {
+        return Err(format!("{name} must be between {min} and {max}."));
+    }
+
+    Ok(amount)
+}
+
+fn parse_bands(bands: &str) -> Result<[f64; 10], String> {
+    let values = bands
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            v.parse::<f64>()
+                .map_err(|_| format!("`{v}` is not a valid gain."))
+                .and_then(|gain| checked_amount(gain, EQUALIZER_GAIN_RANGE, "Each gain"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if values.is_empty() || values.len() > EQUALIZER_BANDS.len() {
+        return Err(format!(
+            "Please provide between 1 and {} band gains.",
+            EQUALIZER_BANDS.len()
+        ));
+    }
+
+    // Bands that are left out stay flat.
+    let mut gains_db = [0.0; 10];
+    gains_db[..values.len()].copy_from_slice(&values);
+    Ok(gains_db)
+}
+
+/// The filters applied to a guild's playback, in the order they were added. Only one filter of
+/// each preset is kept.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FilterChain(Vec<AudioFilter>);
+
+impl FilterChain {
+    /// Adds the filter, replacing any filter of the same preset in place.
+    pub fn set(&mut self, filter: AudioFilter) {
+        match self.0.iter_mut().find(|f| f.preset() == filter.preset()) {
+            Some(existing) => *existing = filter,
+            None => self.0.push(filter),
+        }
+    }
+
+    /// Removes the filter of the given preset. Returns whether one was active.
+    pub fn remove(&mut self, preset: FilterPreset) -> bool {
+        let len = self.0.len();
+        self.0.retain(|f| f.preset() != preset);
+        self.0.len() != len
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AudioFilter> {
+        self.0.iter()
+    }
+
+    /// How many seconds of the source are played per second of output by the whole chain.
+    pub fn playback_rate(&self) -> f64 {
+        self.0.iter().map(AudioFilter::playback_rate).product()
+    }
+
+    /// The chain without the filters that change the playback rate, for sources that must be
+    /// played in real time.
+    pub fn without_rate_changes(&self) -> FilterChain {
+        FilterChain(
+            self.0
+                .iter()
+                .filter(|f| f.playback_rate() == 1.0)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// The ffmpeg `-af` argument for the chain, if any filter is active.
+    pub fn to_ffmpeg(&self) -> Option<String> {
+        let chain = self
+            .0
+            .iter()
+            .map(AudioFilter::to_ffmpeg)
+            .filter(|f| !f.is_empty())
+            .collect::<Vec<_>>();
+
+        (!chain.is_empty()).then(|| chain.join(","))
+    }
+}
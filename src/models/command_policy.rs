@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Policy key for skipping more than one track with `/skip`, which is stricter than skipping the
+/// current track.
+pub const SKIP_SEVERAL: &str = "skip several";
+
+/// Commands that can disrupt everyone's listening and are restricted to DJs unless a guild says
+/// otherwise, by their qualified names.
+const DJ_COMMANDS: [&str; 13] = [
+    "stop",
+    SKIP_SEVERAL,
+    "skipto",
+    "queue clear",
+    "queue move",
+    "queue swap",
+    "queue remove",
+    "filter set",
+    "filter clear",
+    "volume",
+    "radio",
+    "crossfade",
+    "fair-queue",
+];
+
+/// Who may run a command in a guild.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum CommandPolicy {
+    Everyone,
+    /// Members with the DJ role, or anyone alone with the bot.
+    #[name = "DJ"]
+    Dj,
+}
+
+impl CommandPolicy {
+    /// The policy a command follows until a guild overrides it.
+    pub fn default_for(command: &str) -> Self {
+        if DJ_COMMANDS.contains(&command) {
+            CommandPolicy::Dj
+        } else {
+            CommandPolicy::Everyone
+        }
+    }
+}
+
+impl Display for CommandPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandPolicy::Everyone => write!(f, "Everyone"),
+            CommandPolicy::Dj => write!(f, "DJ"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_editing_others_queue_entries_default_to_djs() {
+        for command in [
+            "queue move",
+            "queue swap",
+            "queue remove",
+            "queue clear",
+            "skipto",
+        ] {
+            assert_eq!(
+                CommandPolicy::default_for(command),
+                CommandPolicy::Dj,
+                "{command}"
+            );
+        }
+    }
+
+    #[test]
+    fn other_commands_default_to_everyone() {
+        for command in ["play url", "queue show", "skip", "lyrics"] {
+            assert_eq!(
+                CommandPolicy::default_for(command),
+                CommandPolicy::Everyone,
+                "{command}"
+            );
+        }
+    }
+}
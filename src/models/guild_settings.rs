@@ -0,0 +1,377 @@
+use poise::serenity_prelude::RoleId;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, io, path::Path, time::Duration};
+use tracing::{instrument, trace};
+
+use super::{CommandPolicy, QueueElement, Track};
+
+/// Playback volume, as a percentage, used when a guild has not configured its own default.
+pub const DEFAULT_VOLUME: u8 = 100;
+
+/// The loudest volume, as a percentage, a guild may select.
+pub const MAX_VOLUME: u8 = 200;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SettingsError {
+    #[error("Failed to access settings file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to (de)serialize settings: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+/// Per-guild preferences configured by the guild's administrators. Unlike `GuildState`, these
+/// outlive the bot's voice sessions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildSettings {
+    #[serde(default = "default_volume")]
+    pub default_volume: u8,
+    /// Share of the human listeners, as a percentage, that must vote before a track is skipped.
+    /// Vote-skip is off when unset.
+    #[serde(default)]
+    pub skip_vote_share: Option<u8>,
+    /// Role whose members count as DJs. Until one is set, a role named `DJ` is recognised.
+    #[serde(default)]
+    pub dj_role: Option<RoleId>,
+    /// Policies overriding the defaults, keyed by qualified command name.
+    #[serde(default)]
+    pub command_policies: HashMap<String, CommandPolicy>,
+    /// Most tracks a member may have queued at once. Unlimited when unset.
+    #[serde(default)]
+    pub max_queued_tracks: Option<usize>,
+    /// Longest total duration a member may have queued at once. Unlimited when unset.
+    #[serde(default)]
+    pub max_queued_duration: Option<Duration>,
+    /// Longest track anyone may queue. Unlimited when unset.
+    #[serde(default)]
+    pub max_track_length: Option<Duration>,
+    /// Whether newly queued tracks take turns between requesters instead of joining the back of
+    /// the queue.
+    #[serde(default)]
+    pub fair_queue: bool,
+}
+
+fn default_volume() -> u8 {
+    DEFAULT_VOLUME
+}
+
+impl Default for GuildSettings {
+    fn default() -> Self {
+        Self {
+            default_volume: DEFAULT_VOLUME,
+            skip_vote_share: None,
+            dj_role: None,
+            command_policies: HashMap::new(),
+            max_queued_tracks: None,
+            max_queued_duration: None,
+            max_track_length: None,
+            fair_queue: false,
+        }
+    }
+}
+
+impl GuildSettings {
+    /// The policy `command` follows in the guild.
+    pub fn policy(&self, command: &str) -> CommandPolicy {
+        self.command_policies
+            .get(command)
+            .copied()
+            .unwrap_or_else(|| CommandPolicy::default_for(command))
+    }
+
+    /// Votes required to skip a track with `listeners` humans in the voice channel, or `None` if
+    /// vote-skip is off.
+    pub fn skip_votes_needed(&self, listeners: usize) -> Option<usize> {
+        let share = usize::from(self.skip_vote_share?);
+        Some((listeners * share).div_ceil(100).max(1))
+    }
+
+    /// Removes the tracks of `element` that are longer than the guild allows. Live streams and
+    /// tracks of unknown length could run for any time, so they are removed too. Returns how many
+    /// tracks were left out.
+    pub fn drop_overlong_tracks(&self, element: &mut QueueElement) -> Result<usize, String> {
+        let Some(max) = self.max_track_length else {
+            return Ok(0);
+        };
+
+        let fits = |track: &Track| !track.is_live && track.duration.is_some_and(|d| d <= max);
+        let n_tracks = element.number_of_tracks();
+
+        let n_left = match element {
+            QueueElement::Track(track) => usize::from(fits(track)),
+            QueueElement::Playlist(p) => {
+                p.items.retain(fits);
+                p.items.len()
+            }
+        };
+
+        if n_left == 0 {
+            return Err(format!(
+                "Tracks longer than {} minutes, live streams and tracks of unknown length can't be queued in this server.",
+                max.as_secs() / 60
+            ));
+        }
+
+        Ok(n_tracks - n_left)
+    }
+
+    /// Trims `element` to what a member may still queue on top of the `queued_tracks` tracks
+    /// lasting `queued_duration` they already have queued. While queued minutes are limited, the
+    /// element is cut short at its first live stream or track of unknown length. Returns how many
+    /// tracks were left out.
+    pub fn fit_queue_limits(
+        &self,
+        element: &mut QueueElement,
+        queued_tracks: usize,
+        queued_duration: Duration,
+    ) -> Result<usize, String> {
+        let (mut n_tracks, mut duration) = (queued_tracks, queued_duration);
+        let mut n_fit = 0;
+        let mut exceeded = None;
+
+        for track in element.tracks() {
+            n_tracks += 1;
+            duration += track.duration.unwrap_or_default();
+
+            if let Some(max) = self.max_queued_duration
+                && (track.is_live || track.duration.is_none())
+            {
+                exceeded = Some(format!(
+                    "Members can have at most {} minutes of music queued in this server, so live streams and tracks of unknown length can't be queued.",
+                    max.as_secs() / 60
+                ));
+                break;
+            }
+
+            if let Some(max) = self.max_queued_tracks
+                && n_tracks > max
+            {
+                exceeded = Some(format!(
+                    "Members can have at most {max} tracks queued in this server."
+                ));
+                break;
+            }
+
+            if let Some(max) = self.max_queued_duration
+                && duration > max
+            {
+                exceeded = Some(format!(
+                    "Members can have at most {} minutes of music queued in this server.",
+                    max.as_secs() / 60
+                ));
+                break;
+            }
+
+            n_fit += 1;
+        }
+
+        match exceeded {
+            Some(msg) if n_fit == 0 => Err(msg),
+            _ => {
+                let n_left_out = element.number_of_tracks() - n_fit;
+                element.truncate(n_fit);
+                Ok(n_left_out)
+            }
+        }
+    }
+
+    /// Reads the settings of every guild, keyed by guild ID.
+    #[instrument]
+    pub fn load_all(path: &Path) -> Result<HashMap<String, GuildSettings>, SettingsError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                trace!("No settings file found.");
+                Ok(HashMap::new())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the settings of every guild to `path`, replacing any previous file.
+    #[instrument(skip(settings), fields(n_guilds = settings.len()))]
+    pub fn save_all(
+        path: &Path,
+        settings: &HashMap<String, GuildSettings>,
+    ) -> Result<(), SettingsError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, settings)?;
+        trace!("Settings written to disk.");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_vote_share(share: Option<u8>) -> GuildSettings {
+        GuildSettings {
+            skip_vote_share: share,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn skip_votes_needed_rounds_up_to_at_least_one() {
+        let settings = with_vote_share(Some(50));
+        assert_eq!(settings.skip_votes_needed(4), Some(2));
+        assert_eq!(settings.skip_votes_needed(5), Some(3));
+        assert_eq!(settings.skip_votes_needed(1), Some(1));
+        assert_eq!(settings.skip_votes_needed(0), Some(1));
+
+        assert_eq!(with_vote_share(Some(100)).skip_votes_needed(3), Some(3));
+        assert_eq!(with_vote_share(Some(1)).skip_votes_needed(3), Some(1));
+    }
+
+    #[test]
+    fn skip_votes_needed_is_none_when_vote_skip_is_off() {
+        assert_eq!(with_vote_share(None).skip_votes_needed(5), None);
+    }
+
+    fn minutes(n: u64) -> Duration {
+        Duration::from_secs(n * 60)
+    }
+
+    fn timed_playlist(lengths: &[u64]) -> QueueElement {
+        QueueElement::Playlist(crate::models::Playlist {
+            id: "p".to_string(),
+            title: "p".to_string(),
+            artist: String::new(),
+            url: "https://example.com/p".to_string(),
+            artwork_url: None,
+            source: Default::default(),
+            items: lengths
+                .iter()
+                .enumerate()
+                .map(|(i, &length)| Track {
+                    duration: Some(minutes(length)),
+                    ..Track::test(&i.to_string())
+                })
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn fit_queue_limits_cuts_playlists_short() {
+        let settings = GuildSettings {
+            max_queued_tracks: Some(5),
+            ..Default::default()
+        };
+        let mut element = timed_playlist(&[3, 3, 3, 3]);
+
+        assert_eq!(
+            settings.fit_queue_limits(&mut element, 2, Duration::ZERO),
+            Ok(1)
+        );
+        assert_eq!(element.number_of_tracks(), 3);
+    }
+
+    #[test]
+    fn fit_queue_limits_counts_queued_minutes() {
+        let settings = GuildSettings {
+            max_queued_duration: Some(minutes(10)),
+            ..Default::default()
+        };
+
+        let mut element = timed_playlist(&[3, 3, 3]);
+        assert_eq!(
+            settings.fit_queue_limits(&mut element, 1, minutes(4)),
+            Ok(1)
+        );
+        assert_eq!(element.number_of_tracks(), 2);
+
+        let mut element = timed_playlist(&[3]);
+        assert!(
+            settings
+                .fit_queue_limits(&mut element, 3, minutes(9))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn fit_queue_limits_keeps_everything_without_limits() {
+        let mut element = timed_playlist(&[30, 30]);
+        assert_eq!(
+            GuildSettings::default().fit_queue_limits(&mut element, 100, minutes(600)),
+            Ok(0)
+        );
+        assert_eq!(element.number_of_tracks(), 2);
+    }
+
+    fn with_track_limit(max_minutes: u64) -> GuildSettings {
+        GuildSettings {
+            max_track_length: Some(minutes(max_minutes)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn drop_overlong_tracks_removes_long_tracks() {
+        let mut element = timed_playlist(&[3, 12, 5]);
+        assert_eq!(
+            with_track_limit(5).drop_overlong_tracks(&mut element),
+            Ok(1)
+        );
+        assert_eq!(element.number_of_tracks(), 2);
+
+        let mut element = timed_playlist(&[12]);
+        assert!(
+            with_track_limit(5)
+                .drop_overlong_tracks(&mut element)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn drop_overlong_tracks_removes_tracks_of_unknown_length() {
+        let mut unknown = QueueElement::Track(Track::test("a"));
+        assert!(
+            with_track_limit(5)
+                .drop_overlong_tracks(&mut unknown)
+                .is_err()
+        );
+
+        let mut live = QueueElement::Track(Track {
+            is_live: true,
+            ..Track::test("a")
+        });
+        assert!(with_track_limit(5).drop_overlong_tracks(&mut live).is_err());
+
+        assert_eq!(
+            GuildSettings::default().drop_overlong_tracks(&mut unknown),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    fn fit_queue_limits_stops_at_unknown_lengths_when_minutes_are_limited() {
+        let mut element = timed_playlist(&[3, 3]);
+        if let QueueElement::Playlist(p) = &mut element {
+            p.items[1].duration = None;
+        }
+
+        let settings = GuildSettings {
+            max_queued_duration: Some(minutes(60)),
+            ..Default::default()
+        };
+        assert_eq!(
+            settings.fit_queue_limits(&mut element, 0, Duration::ZERO),
+            Ok(1)
+        );
+        assert_eq!(element.number_of_tracks(), 1);
+
+        let settings = GuildSettings {
+            max_queued_tracks: Some(10),
+            ..Default::default()
+        };
+        let mut unknown = QueueElement::Track(Track::test("a"));
+        assert_eq!(
+            settings.fit_queue_limits(&mut unknown, 0, Duration::ZERO),
+            Ok(0)
+        );
+    }
+}
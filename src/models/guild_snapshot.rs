@@ -0,0 +1,140 @@
+use poise::serenity_prelude::GuildId;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, fs, io, path::Path, time::Duration};
+use tracing::{instrument, trace, warn};
+
+use super::GuildState;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SnapshotError {
+    #[error("Failed to access snapshot file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to (de)serialize snapshot: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+/// A guild's playback session as written to disk on shutdown.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildSnapshot {
+    pub guild_id: GuildId,
+    pub guild_state: GuildState,
+    /// How far into the current track playback had progressed.
+    pub position: Duration,
+    /// Tracks left out because their links expire before the session could be resumed.
+    #[serde(default)]
+    pub n_dropped: usize,
+}
+
+impl Display for GuildSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "GuildSnapshot {{ guild_id: {}, position: {:?}, guild_state: {} }}",
+            self.guild_id, self.position, self.guild_state
+        )
+    }
+}
+
+impl GuildSnapshot {
+    /// Writes all snapshots to `path`, replacing any previous file. The snapshots are written to
+    /// a temporary file first, so a crash while writing leaves the previous file intact.
+    #[instrument(skip(snapshots), fields(n_snapshots = snapshots.len()))]
+    pub fn save_all(path: &Path, snapshots: &[GuildSnapshot]) -> Result<(), SnapshotError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+
+        let file = fs::File::create(&temp_path)?;
+        serde_json::to_writer_pretty(&file, snapshots)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        trace!("Snapshots written to disk.");
+        Ok(())
+    }
+
+    /// Reads and removes the snapshot file so a session is only ever restored once. A file that
+    /// can't be parsed is set aside with a `.bad` suffix rather than deleted.
+    #[instrument]
+    pub fn take_all(path: &Path) -> Result<Vec<GuildSnapshot>, SnapshotError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                trace!("No snapshot file found.");
+                return Ok(Vec::new());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let snapshots = match serde_json::from_str(&contents) {
+            Ok(snapshots) => snapshots,
+            Err(e) => {
+                let mut bad_path = path.as_os_str().to_owned();
+                bad_path.push(".bad");
+                fs::rename(path, &bad_path)?;
+                warn!(bad_path = ?bad_path, "Set aside unreadable snapshot file.");
+                return Err(e.into());
+            }
+        };
+
+        fs::remove_file(path)?;
+        Ok(snapshots)
+    }
+
+    /// Converts the snapshot into a guild state that is ready to begin playback again.
+    pub fn into_guild_state(self) -> GuildState {
+        let mut guild_state = self.guild_state;
+        guild_state.playback_state.requeue_current_track();
+        guild_state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn scratch_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("luna-snapshot-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn take_all_removes_the_file_once_read() {
+        let path = scratch_file("sessions.json");
+        GuildSnapshot::save_all(&path, &[]).unwrap();
+
+        assert!(GuildSnapshot::take_all(&path).unwrap().is_empty());
+        assert!(!path.exists());
+        assert!(GuildSnapshot::take_all(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn save_all_replaces_the_file_without_leaving_a_temporary_one() {
+        let path = scratch_file("replaced.json");
+        fs::write(&path, "[{").unwrap();
+
+        GuildSnapshot::save_all(&path, &[]).unwrap();
+        assert!(!path.with_extension("json.tmp").exists());
+        assert!(GuildSnapshot::take_all(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn take_all_sets_unreadable_files_aside() {
+        let path = scratch_file("corrupt.json");
+        fs::write(&path, "[{").unwrap();
+
+        assert!(matches!(
+            GuildSnapshot::take_all(&path),
+            Err(SnapshotError::Serde(_))
+        ));
+        assert!(!path.exists());
+
+        let bad_path = path.with_extension("json.bad");
+        assert_eq!(fs::read_to_string(&bad_path).unwrap(), "[{");
+        fs::remove_file(bad_path).unwrap();
+    }
+}
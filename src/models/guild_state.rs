@@ -1,10 +1,161 @@
-use std::fmt::Display;
+use poise::serenity_prelude::{ChannelId, MessageId};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, time::Duration};
 
-use super::PlaybackState;
+use super::{
+    DEFAULT_VOLUME, FilterChain, LoopMode, MAX_VOLUME, PlaybackState, StreamLocator, Track,
+    TrackSource,
+};
+use crate::stream::StreamOptions;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuildState {
     pub playback_state: PlaybackState,
+    pub voice_channel_id: Option<ChannelId>,
+    pub text_channel_id: Option<ChannelId>,
+    /// Playback volume as a percentage of the source's loudness.
+    #[serde(default = "default_volume")]
+    pub volume: u8,
+    /// Audio filters applied to every track streamed in the guild.
+    #[serde(default)]
+    pub filters: FilterChain,
+    /// How long consecutive tracks overlap. Zero plays them back to back.
+    #[serde(default)]
+    pub crossfade: Duration,
+    /// The now playing panel posted in the text channel. Stale after a restart, so not persisted.
+    #[serde(skip)]
+    pub now_playing_message_id: Option<MessageId>,
+}
+
+/// How long before the next track takes over its stream is warmed up. Long enough for yt-dlp to
+/// extract it, short enough that the warm stream isn't left waiting on a full pipe for long.
+const PREFETCH_LEAD: Duration = Duration::from_secs(20);
+
+fn default_volume() -> u8 {
+    DEFAULT_VOLUME
+}
+
+impl Default for GuildState {
+    fn default() -> Self {
+        Self {
+            playback_state: PlaybackState::default(),
+            voice_channel_id: None,
+            text_channel_id: None,
+            volume: DEFAULT_VOLUME,
+            filters: FilterChain::default(),
+            crossfade: Duration::ZERO,
+            now_playing_message_id: None,
+        }
+    }
+}
+
+impl GuildState {
+    pub fn new(voice_channel_id: ChannelId, text_channel_id: ChannelId, volume: u8) -> Self {
+        Self {
+            playback_state: PlaybackState::default(),
+            voice_channel_id: Some(voice_channel_id),
+            text_channel_id: Some(text_channel_id),
+            volume: volume.min(MAX_VOLUME),
+            filters: FilterChain::default(),
+            crossfade: Duration::ZERO,
+            now_playing_message_id: None,
+        }
+    }
+
+    /// The volume as the gain songbird expects, where `1.0` leaves the source untouched.
+    pub fn volume_gain(&self) -> f32 {
+        f32::from(self.volume) / 100.0
+    }
+
+    /// Maps time elapsed on the current stream to a position in the current track, accounting for
+    /// where the stream started and how fast the filters play it back.
+    pub fn track_position(&self, elapsed: Duration) -> Duration {
+        self.playback_state
+            .get_stream_offset()
+            .saturating_add(elapsed.mul_f64(self.playback_rate()))
+    }
+
+    /// How many seconds of the current track are played per second of output. Live streams can't
+    /// be played faster or slower than they are broadcast, so they ignore rate-changing filters.
+    fn playback_rate(&self) -> f64 {
+        match self.playback_state.get_current_track() {
+            Some(track) if track.is_live => 1.0,
+            _ => self.filters.playback_rate(),
+        }
+    }
+
+    /// Time on the current stream at which the next track should start fading in. Only known
+    /// when the guild crossfades and the current track's length is known.
+    /// Lengths are filled in when tracks are resolved, from YouTube's content details for links,
+    /// searches, playlists and radio picks alike, or from yt-dlp's info.
+    pub fn crossfade_start(&self) -> Option<Duration> {
+        if self.crossfade.is_zero() {
+            return None;
+        }
+
+        let duration = self.playback_state.get_current_track().as_ref()?.duration?;
+        duration
+            .checked_sub(self.playback_state.get_stream_offset())?
+            .div_f64(self.playback_rate())
+            .checked_sub(self.crossfade)
+    }
+
+    /// Time on the current stream at which the next track's stream should be warmed up, shortly
+    /// before it fades in or takes over. Only known when the current track's length is known.
+    pub fn prefetch_start(&self) -> Option<Duration> {
+        let duration = self.playback_state.get_current_track().as_ref()?.duration?;
+        let remaining = duration
+            .checked_sub(self.playback_state.get_stream_offset())?
+            .div_f64(self.playback_rate());
+        let handover = self.crossfade_start().unwrap_or(remaining);
+
+        Some(handover.saturating_sub(PREFETCH_LEAD))
+    }
+
+    /// Roughly how long until the queued track at the zero-based `position` starts, given how far
+    /// into the current track playback is. Unknown while the current track loops or when a track
+    /// ahead has no known length.
+    pub fn time_until(&self, position: usize, current_position: Duration) -> Option<Duration> {
+        let playback_state = &self.playback_state;
+        if playback_state.get_loop_mode() == LoopMode::Track {
+            return None;
+        }
+
+        let remaining = match playback_state.get_current_track() {
+            Some(track) => track.duration?.saturating_sub(current_position),
+            None => Duration::ZERO,
+        };
+
+        let ahead = playback_state
+            .queued_tracks()
+            .take(position)
+            .map(|(track, _)| track.duration)
+            .sum::<Option<Duration>>()?;
+
+        Some((remaining + ahead).div_f64(self.playback_rate()))
+    }
+
+    /// Options for streaming `track` from `start` with the guild's filters. Live streams always
+    /// start at the live edge.
+    pub fn stream_options(&self, track: &Track, start: Duration) -> StreamOptions {
+        if track.is_live {
+            return StreamOptions {
+                start: Duration::ZERO,
+                filters: self.filters.without_rate_changes().to_ffmpeg(),
+                live: true,
+                direct: false,
+                transcode: track.source != TrackSource::Youtube,
+            };
+        }
+
+        StreamOptions {
+            start,
+            filters: self.filters.to_ffmpeg(),
+            live: false,
+            direct: track.locator == StreamLocator::Direct,
+            transcode: track.source != TrackSource::Youtube,
+        }
+    }
 }
 
 impl Display for GuildState {
@@ -16,3 +167,51 @@ impl Display for GuildState {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playing(secs: u64, offset: u64) -> GuildState {
+        let mut state = GuildState::default();
+        state.playback_state.set_current_track(Some(Track {
+            duration: Some(Duration::from_secs(secs)),
+            ..Track::test("t")
+        }));
+        state
+            .playback_state
+            .set_stream_offset(Duration::from_secs(offset));
+        state
+    }
+
+    #[test]
+    fn prefetch_starts_ahead_of_the_end() {
+        assert_eq!(
+            playing(200, 0).prefetch_start(),
+            Some(Duration::from_secs(180))
+        );
+        assert_eq!(
+            playing(200, 150).prefetch_start(),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(playing(10, 0).prefetch_start(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn prefetch_starts_ahead_of_the_crossfade() {
+        let mut state = playing(200, 0);
+        state.crossfade = Duration::from_secs(10);
+        assert_eq!(state.prefetch_start(), Some(Duration::from_secs(170)));
+    }
+
+    #[test]
+    fn prefetch_waits_for_tracks_of_unknown_length() {
+        let mut state = GuildState::default();
+        assert_eq!(state.prefetch_start(), None);
+
+        state
+            .playback_state
+            .set_current_track(Some(Track::test("t")));
+        assert_eq!(state.prefetch_start(), None);
+    }
+}
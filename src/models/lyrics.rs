@@ -0,0 +1,251 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::{fmt::Debug, time::Duration};
+use tracing::{instrument, trace};
+
+/// Words that mark a bracketed part of a video title as decoration rather than the song's name.
+const TITLE_NOISE: [&str; 15] = [
+    "official",
+    "video",
+    "audio",
+    "lyric",
+    "lyrics",
+    "visualizer",
+    "visualiser",
+    "mv",
+    "m/v",
+    "hd",
+    "hq",
+    "4k",
+    "remastered",
+    "feat",
+    "ft",
+];
+
+/// How long a lyrics lookup may take before it is given up on.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Suffixes YouTube and labels add to artists' channel names.
+const CHANNEL_NOISE: [&str; 3] = [" - Topic", "VEVO", " Official"];
+
+#[derive(thiserror::Error, Debug)]
+pub enum LyricsError {
+    #[error("Lyrics request failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("Failed to parse lyrics response: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("No lyrics found.")]
+    NotFound,
+}
+
+/// What to look lyrics up by.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LyricsQuery {
+    /// Free text typed by a member.
+    Text(String),
+    /// A song's name and, when known, its artist.
+    Song {
+        title: String,
+        artist: Option<String>,
+    },
+}
+
+impl LyricsQuery {
+    /// Guesses the song behind a video from its title and channel. Decorations such as
+    /// "(Official Video)" are dropped, and "Artist - Title" titles are split up.
+    pub fn from_video(title: &str, channel: &str) -> Self {
+        let cleaned = strip_noise(title);
+        // Anything after a pipe is usually the label or the channel's tagline.
+        let cleaned = cleaned.split('|').next().unwrap_or_default();
+
+        let (artist, title) = match [" - ", " – ", " — "]
+            .iter()
+            .find_map(|separator| cleaned.split_once(separator))
+        {
+            Some((artist, title)) => (artist.to_string(), title),
+            None => {
+                let artist = CHANNEL_NOISE
+                    .iter()
+                    .fold(channel, |name, noise| name.trim_end_matches(noise));
+                (artist.to_string(), cleaned)
+            }
+        };
+
+        let title = title.trim().trim_matches(['"', '\'']).trim().to_string();
+        let artist = artist.trim().to_string();
+
+        LyricsQuery::Song {
+            title,
+            artist: (!artist.is_empty()).then_some(artist),
+        }
+    }
+}
+
+/// Removes the bracketed parts of `title` that contain a decoration word.
+fn strip_noise(title: &str) -> String {
+    let mut cleaned = String::with_capacity(title.len());
+    let mut rest = title;
+
+    while let Some(open) = rest.find(['(', '[', '【']) {
+        let opener = rest[open..].chars().next().unwrap_or_default();
+        let closer = match opener {
+            '(' => ')',
+            '[' => ']',
+            _ => '】',
+        };
+
+        let inner_start = open + opener.len_utf8();
+        let Some(len) = rest[inner_start..].find(closer) else {
+            break;
+        };
+
+        let inner = rest[inner_start..inner_start + len].to_lowercase();
+        let is_noise = inner
+            .split(|c: char| !c.is_alphanumeric() && c != '/')
+            .any(|word| TITLE_NOISE.contains(&word));
+
+        let end = inner_start + len + closer.len_utf8();
+        if is_noise {
+            cleaned.push_str(&rest[..open]);
+        } else {
+            cleaned.push_str(&rest[..end]);
+        }
+        rest = &rest[end..];
+    }
+
+    cleaned.push_str(rest);
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A song's lyrics as returned by a provider.
+#[derive(Debug, Clone)]
+pub struct Lyrics {
+    pub title: String,
+    pub artist: String,
+    pub text: String,
+}
+
+/// A service `/lyrics` can look lyrics up with.
+#[async_trait]
+pub trait LyricsProvider: Debug + Send + Sync {
+    async fn find(&self, query: &LyricsQuery) -> Result<Lyrics, LyricsError>;
+}
+
+/// Looks lyrics up with the LRCLIB API, served by the public instance or a self-hosted one.
+#[derive(Debug, Clone)]
+pub struct LrclibProvider {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LrclibRecord {
+    track_name: String,
+    artist_name: String,
+    plain_lyrics: Option<String>,
+}
+
+impl LrclibProvider {
+    pub fn new(client: reqwest::Client, base_url: &str) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl LyricsProvider for LrclibProvider {
+    #[instrument(skip(self))]
+    async fn find(&self, query: &LyricsQuery) -> Result<Lyrics, LyricsError> {
+        let params = match query {
+            LyricsQuery::Text(text) => vec![("q", text.as_str())],
+            LyricsQuery::Song { title, artist } => {
+                let mut params = vec![("track_name", title.as_str())];
+                if let Some(artist) = artist {
+                    params.push(("artist_name", artist.as_str()));
+                }
+                params
+            }
+        };
+
+        let body = self
+            .client
+            .get(format!("{}/search", self.base_url))
+            .query(&params)
+            .timeout(LOOKUP_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let records: Vec<LrclibRecord> = serde_json::from_str(&body)?;
+        trace!(n_records = records.len(), "Lyrics search completed.");
+
+        // Instrumentals come without lyrics.
+        records
+            .into_iter()
+            .find_map(|record| {
+                let text = record.plain_lyrics.filter(|text| !text.trim().is_empty())?;
+                Some(Lyrics {
+                    title: record.track_name,
+                    artist: record.artist_name,
+                    text,
+                })
+            })
+            .ok_or(LyricsError::NotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song(title: &str, artist: Option<&str>) -> LyricsQuery {
+        LyricsQuery::Song {
+            title: title.to_string(),
+            artist: artist.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn strip_noise_drops_decorations_only() {
+        assert_eq!(strip_noise("Song (Official Video)"), "Song");
+        assert_eq!(strip_noise("Song [HD] 【MV】"), "Song");
+        assert_eq!(
+            strip_noise("Song (Live at Wembley)"),
+            "Song (Live at Wembley)"
+        );
+        assert_eq!(strip_noise("Song (feat. Someone)"), "Song");
+        assert_eq!(strip_noise("Song (unclosed"), "Song (unclosed");
+    }
+
+    #[test]
+    fn from_video_splits_artist_and_title() {
+        assert_eq!(
+            LyricsQuery::from_video("Artist - Song (Official Audio)", "Label"),
+            song("Song", Some("Artist"))
+        );
+        assert_eq!(
+            LyricsQuery::from_video("Artist – \"Song\" | Label Records", "Label"),
+            song("Song", Some("Artist"))
+        );
+    }
+
+    #[test]
+    fn from_video_falls_back_to_the_channel() {
+        assert_eq!(
+            LyricsQuery::from_video("Song [Lyrics]", "ArtistVEVO"),
+            song("Song", Some("Artist"))
+        );
+        assert_eq!(
+            LyricsQuery::from_video("Song", "Artist - Topic"),
+            song("Song", Some("Artist"))
+        );
+        assert_eq!(LyricsQuery::from_video("Song", ""), song("Song", None));
+    }
+}
@@ -0,0 +1,162 @@
+use async_trait::async_trait;
+use std::{fmt::Debug, sync::Arc};
+use tracing::{instrument, trace};
+use url::Url;
+
+use super::{AudioFileError, Playlist, QueueElement, Track, YoutubeError, YtdlpError};
+
+#[derive(thiserror::Error, Debug)]
+pub enum MetadataError {
+    #[error("No provider recognises the URL.")]
+    UnsupportedUrl,
+
+    #[error("The provider does not support this request.")]
+    NotSupported,
+
+    #[error("YouTube error: {0}")]
+    Youtube(#[from] YoutubeError),
+
+    #[error("Audio file error: {0}")]
+    AudioFile(#[from] AudioFileError),
+
+    #[error("yt-dlp error: {0}")]
+    Ytdlp(#[from] YtdlpError),
+}
+
+impl MetadataError {
+    /// What to tell members when the error comes down to their request rather than to the bot.
+    pub fn user_message(&self) -> Option<String> {
+        match self {
+            MetadataError::UnsupportedUrl | MetadataError::Youtube(YoutubeError::Url) => {
+                Some("I don't know how to play that link.".to_string())
+            }
+            MetadataError::Youtube(YoutubeError::Unsupported(msg)) => Some(msg.clone()),
+            MetadataError::Youtube(YoutubeError::NotFound) => {
+                Some("I couldn't find anything matching that.".to_string())
+            }
+            MetadataError::Youtube(YoutubeError::Fallback(e)) => e.user_message(),
+            MetadataError::AudioFile(e) => Some(e.to_string()),
+            MetadataError::Ytdlp(e) => e.user_message(),
+            _ => None,
+        }
+    }
+}
+
+/// A backend tracks can be resolved with. Requests a backend can't serve default to
+/// `MetadataError::NotSupported`.
+#[async_trait]
+pub trait MetadataProvider: Debug + Send + Sync {
+    /// Whether `url` points at something this provider can resolve.
+    fn supports_url(&self, url: &Url) -> bool;
+
+    /// Resolves `url` into a track or playlist.
+    async fn resolve_url(&self, url: &Url) -> Result<QueueElement, MetadataError>;
+
+    /// Up to `n_results` tracks matching `query`, best match first.
+    async fn search_tracks(
+        &self,
+        _query: &str,
+        _n_results: u32,
+    ) -> Result<Vec<Track>, MetadataError> {
+        Err(MetadataError::NotSupported)
+    }
+
+    /// Up to `n_results` playlists matching `query`, best match first. They may come without
+    /// their items, which are fetched by resolving the playlist's URL.
+    async fn search_playlists(
+        &self,
+        _query: &str,
+        _n_results: u32,
+    ) -> Result<Vec<Playlist>, MetadataError> {
+        Err(MetadataError::NotSupported)
+    }
+
+    /// A track related to the one at `seed`, preferring tracks whose IDs are not in
+    /// `recently_played`.
+    async fn related_track(
+        &self,
+        _seed: &Url,
+        _recently_played: &[String],
+    ) -> Result<Track, MetadataError> {
+        Err(MetadataError::NotSupported)
+    }
+}
+
+/// The providers the bot resolves tracks with, in order of preference.
+#[derive(Debug, Clone)]
+pub struct MetadataProviders(Vec<Arc<dyn MetadataProvider>>);
+
+impl MetadataProviders {
+    pub fn new(providers: Vec<Arc<dyn MetadataProvider>>) -> Self {
+        Self(providers)
+    }
+
+    /// The first provider that recognises `url`, along with the parsed URL.
+    fn provider_for(&self, url: &str) -> Option<(&dyn MetadataProvider, Url)> {
+        let url = Url::parse(url)
+            .inspect_err(|e| trace!(err = %e, "Could not parse URL."))
+            .ok()?;
+
+        self.0
+            .iter()
+            .find(|provider| provider.supports_url(&url))
+            .map(|provider| (provider.as_ref(), url))
+    }
+
+    /// Resolves `url` with the first provider that recognises it.
+    #[instrument(skip(self))]
+    pub async fn resolve_url(&self, url: &str) -> Result<QueueElement, MetadataError> {
+        let (provider, url) = self
+            .provider_for(url)
+            .ok_or(MetadataError::UnsupportedUrl)?;
+        trace!(?provider, "Resolving URL.");
+        provider.resolve_url(&url).await
+    }
+
+    /// Searches tracks with the first provider that supports searching.
+    #[instrument(skip(self))]
+    pub async fn search_tracks(
+        &self,
+        query: &str,
+        n_results: u32,
+    ) -> Result<Vec<Track>, MetadataError> {
+        for provider in &self.0 {
+            match provider.search_tracks(query, n_results).await {
+                Err(MetadataError::NotSupported) => continue,
+                result => return result,
+            }
+        }
+
+        Err(MetadataError::NotSupported)
+    }
+
+    /// Searches playlists with the first provider that supports searching.
+    #[instrument(skip(self))]
+    pub async fn search_playlists(
+        &self,
+        query: &str,
+        n_results: u32,
+    ) -> Result<Vec<Playlist>, MetadataError> {
+        for provider in &self.0 {
+            match provider.search_playlists(query, n_results).await {
+                Err(MetadataError::NotSupported) => continue,
+                result => return result,
+            }
+        }
+
+        Err(MetadataError::NotSupported)
+    }
+
+    /// Finds a track related to the one at `seed_url` with the provider it came from.
+    #[instrument(skip(self, recently_played))]
+    pub async fn related_track(
+        &self,
+        seed_url: &str,
+        recently_played: &[String],
+    ) -> Result<Track, MetadataError> {
+        let (provider, seed) = self
+            .provider_for(seed_url)
+            .ok_or(MetadataError::UnsupportedUrl)?;
+        provider.related_track(&seed, recently_played).await
+    }
+}
@@ -1,24 +1,82 @@
+use poise::serenity_prelude::UserId;
+use serde::{Deserialize, Serialize};
 use songbird::tracks::TrackHandle;
-use std::{collections::VecDeque, fmt::Display};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    time::Duration,
+};
 
-use super::{QueueElement, VideoMetadata};
+use super::{Playlist, QueueElement, SkipVotes, Track};
 
-#[derive(Debug, Clone, Default)]
+/// Maximum number of finished tracks remembered per guild.
+const HISTORY_CAP: usize = 50;
+
+/// Runtime-only fields are skipped when serialized, so a deserialized state is never playing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PlaybackState {
+    #[serde(skip)]
     playing: bool,
-    current_track: Option<VideoMetadata>,
+    current_track: Option<Track>,
+    #[serde(skip)]
     track_handle: Option<TrackHandle>,
     queue: VecDeque<QueueElement>,
+    history: VecDeque<Track>,
     radio_mode: RadioMode,
+    loop_mode: LoopMode,
+    #[serde(skip)]
+    skip_current: bool,
+    /// Whether the current track was already sent to the back of the queue by a skip in queue
+    /// loop mode, ahead of the tracks skipped with it.
+    #[serde(skip)]
+    current_requeued: bool,
+    /// Position in the current track at which its stream started.
+    #[serde(skip)]
+    stream_offset: Duration,
+    #[serde(skip)]
+    skip_votes: SkipVotes,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub enum RadioMode {
     On(Option<String>),
     #[default]
     Off,
 }
 
+/// Determines what happens to a track once it finishes. Looping takes precedence over radio
+/// mode, which only steps in once the queue is exhausted.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter,
+)]
+pub enum LoopMode {
+    #[default]
+    Off,
+    Track,
+    Queue,
+}
+
+impl LoopMode {
+    /// The mode that follows this one when cycling through them.
+    pub fn next(self) -> Self {
+        match self {
+            LoopMode::Off => LoopMode::Track,
+            LoopMode::Track => LoopMode::Queue,
+            LoopMode::Queue => LoopMode::Off,
+        }
+    }
+}
+
+impl Display for LoopMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoopMode::Off => write!(f, "Off"),
+            LoopMode::Track => write!(f, "Track"),
+            LoopMode::Queue => write!(f, "Queue"),
+        }
+    }
+}
+
 impl Display for PlaybackState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -41,11 +99,11 @@ impl PlaybackState {
         self.playing = play_state;
     }
 
-    pub fn get_current_track(&self) -> &Option<VideoMetadata> {
+    pub fn get_current_track(&self) -> &Option<Track> {
         &self.current_track
     }
 
-    pub fn set_current_track(&mut self, current_track: Option<VideoMetadata>) {
+    pub fn set_current_track(&mut self, current_track: Option<Track>) {
         self.current_track = current_track;
 
         if let RadioMode::On(ref mut seed) = self.radio_mode
@@ -63,19 +121,84 @@ impl PlaybackState {
         &self.track_handle
     }
 
+    pub fn get_stream_offset(&self) -> Duration {
+        self.stream_offset
+    }
+
+    pub fn set_stream_offset(&mut self, offset: Duration) {
+        self.stream_offset = offset;
+    }
+
+    /// Votes to skip the current track.
+    pub fn skip_votes_mut(&mut self) -> &mut SkipVotes {
+        &mut self.skip_votes
+    }
+
     pub fn get_track_handle_mut(&mut self) -> &mut Option<TrackHandle> {
         &mut self.track_handle
     }
 
-    pub fn enqueue(&mut self, element: QueueElement) {
-        self.queue.push_back(element)
+    /// Adds `element` to the queue. With `fair` set, its tracks are slotted into the rotation
+    /// between requesters instead of joining the back, leaving the order of the tracks already
+    /// queued untouched.
+    pub fn enqueue(&mut self, element: QueueElement, fair: bool) {
+        if !fair {
+            self.queue.push_back(element);
+            return;
+        }
+
+        for single in element.into_single_tracks() {
+            let index = self.split_queue_at(self.fair_position(single.requester()));
+
+            // Keep runs of the same playlist together.
+            match (index.checked_sub(1).map(|i| &mut self.queue[i]), single) {
+                (Some(QueueElement::Playlist(tail)), QueueElement::Playlist(p))
+                    if tail.id == p.id
+                        && tail.items.front().map(|t| t.requested_by)
+                            == p.items.front().map(|t| t.requested_by) =>
+                {
+                    tail.items.extend(p.items)
+                }
+                (_, single) => self.queue.insert(index, single),
+            }
+        }
+    }
+
+    /// The track position at which a new track from `requester` takes its turn. Each queued track
+    /// belongs to a round, counting its requester's tracks ahead of it, and the new track goes
+    /// after every track of its own round or earlier that follows its requester's last track. The
+    /// requester of the current track starts a round behind.
+    fn fair_position(&self, requester: Option<UserId>) -> usize {
+        let current_requester = self.current_track.as_ref().map(|t| t.requested_by);
+        let head_start = |r: Option<UserId>| usize::from(current_requester == Some(r));
+
+        let requesters: Vec<Option<UserId>> =
+            self.queued_tracks().map(|(t, _)| t.requested_by).collect();
+        let own = requesters.iter().filter(|&&r| r == requester).count();
+        let round = own + head_start(requester);
+        let after_own = requesters
+            .iter()
+            .rposition(|&r| r == requester)
+            .map_or(0, |i| i + 1);
+
+        let mut counts: HashMap<Option<UserId>, usize> = HashMap::new();
+        requesters
+            .iter()
+            .enumerate()
+            .position(|(i, &r)| {
+                let count = counts.entry(r).or_default();
+                let track_round = *count + head_start(r);
+                *count += 1;
+                i >= after_own && track_round > round
+            })
+            .unwrap_or(requesters.len())
     }
 
     pub fn next(&self) -> Option<&QueueElement> {
         self.queue.front()
     }
 
-    pub fn dequeue(&mut self) -> Option<VideoMetadata> {
+    pub fn dequeue(&mut self) -> Option<Track> {
         match self.queue.pop_front() {
             Some(QueueElement::Track(t)) => Some(t),
             Some(QueueElement::Playlist(mut p)) => {
@@ -90,25 +213,273 @@ impl PlaybackState {
     }
 
     pub fn number_of_tracks_queued(&self) -> usize {
-        self.queue.iter().fold(0, |accum, curr| match curr {
-            QueueElement::Track(_) => accum + 1,
-            QueueElement::Playlist(p) => accum + p.items.len(),
-        })
+        self.queue.iter().map(QueueElement::number_of_tracks).sum()
+    }
+
+    /// Returns the track that will play once the current one ends.
+    pub fn peek_next_track(&self) -> Option<&Track> {
+        match self.queue.front()? {
+            QueueElement::Track(t) => Some(t),
+            QueueElement::Playlist(p) => p.items.front(),
+        }
+    }
+
+    /// The track expected to play once the current one ends, following the loop mode. Radio picks
+    /// are only resolved once the queue runs out, so they are never expected.
+    pub fn expected_next_track(&self) -> Option<&Track> {
+        let current = self.current_track.as_ref();
+
+        match self.loop_mode {
+            LoopMode::Track if !self.skip_current => current,
+            LoopMode::Queue => self.peek_next_track().or(current),
+            _ => self.peek_next_track(),
+        }
+    }
+
+    /// Removes the track at the zero-based track `position`.
+    pub fn remove_track(&mut self, position: usize) -> Option<Track> {
+        if position >= self.number_of_tracks_queued() {
+            return None;
+        }
+
+        let index = self.split_queue_at(position);
+        self.split_queue_at(position + 1);
+
+        match self.queue.remove(index)? {
+            QueueElement::Track(t) => Some(t),
+            QueueElement::Playlist(mut p) => p.items.pop_front(),
+        }
+    }
+
+    /// Inserts a track so that it occupies the zero-based track `position`.
+    /// Positions past the end of the queue append the track.
+    pub fn insert_track(&mut self, position: usize, track: Track) {
+        let index = self.split_queue_at(position);
+        self.queue.insert(index, QueueElement::Track(track));
     }
 
-    pub fn number_of_items_queued(&self) -> usize {
+    /// Moves a track between two zero-based track positions.
+    pub fn move_track(&mut self, from: usize, to: usize) -> Option<Track> {
+        if to >= self.number_of_tracks_queued() {
+            return None;
+        }
+
+        let track = self.remove_track(from)?;
+        self.insert_track(to, track.clone());
+        Some(track)
+    }
+
+    /// Swaps the tracks at two distinct zero-based track positions.
+    pub fn swap_tracks(&mut self, a: usize, b: usize) -> Option<(Track, Track)> {
+        let (low, high) = (a.min(b), a.max(b));
+        if low == high || high >= self.number_of_tracks_queued() {
+            return None;
+        }
+
+        let high_track = self.remove_track(high)?;
+        let low_track = self.remove_track(low)?;
+        self.insert_track(low, high_track.clone());
+        self.insert_track(high, low_track.clone());
+
+        if a <= b {
+            Some((low_track, high_track))
+        } else {
+            Some((high_track, low_track))
+        }
+    }
+
+    /// Skips the current track and the next `n` queued tracks, returning how many queued tracks
+    /// were passed over. In queue loop mode the current track and then the skipped tracks are
+    /// moved to the back of the queue, keeping the loop in order.
+    pub fn skip_tracks(&mut self, n: usize) -> usize {
+        self.skip_current_track();
+
+        let n = n.min(self.number_of_tracks_queued());
+        let index = self.split_queue_at(n);
+        let skipped = self.queue.drain(..index).collect::<Vec<_>>();
+
+        if self.loop_mode == LoopMode::Queue {
+            if let Some(current) = &self.current_track
+                && !self.current_requeued
+            {
+                self.queue.push_back(QueueElement::Track(current.clone()));
+                self.current_requeued = true;
+            }
+            self.queue.extend(skipped);
+        }
+        n
+    }
+
+    /// Prevents the current track from being repeated in track loop mode once it ends.
+    pub fn skip_current_track(&mut self) {
+        self.skip_current = true;
+    }
+
+    /// Shuffles every queued track. Playlists are broken up into their individual tracks.
+    pub fn shuffle_queue(&mut self) {
+        use rand::seq::SliceRandom;
+
+        let mut tracks = self
+            .queue
+            .drain(..)
+            .flat_map(|element| match element {
+                QueueElement::Track(t) => vec![t],
+                QueueElement::Playlist(p) => p.items.into(),
+            })
+            .collect::<Vec<_>>();
+
+        tracks.shuffle(&mut rand::rng());
+        self.queue
+            .extend(tracks.into_iter().map(QueueElement::Track));
+    }
+
+    /// Empties the queue while leaving the current track untouched.
+    pub fn clear_queue(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Ensures a queue element boundary exists right before the zero-based track `position`,
+    /// splitting a playlist in two if the position falls inside it. Returns the index of the
+    /// element that starts at `position`, or the queue length if it is past the end.
+    fn split_queue_at(&mut self, position: usize) -> usize {
+        let mut offset = 0;
+
+        for index in 0..self.queue.len() {
+            if offset == position {
+                return index;
+            }
+
+            let n_tracks = self.queue[index].number_of_tracks();
+            if position < offset + n_tracks {
+                if let QueueElement::Playlist(p) = &mut self.queue[index] {
+                    let items = p.items.split_off(position - offset);
+                    let tail = p.with_items(items);
+                    self.queue.insert(index + 1, QueueElement::Playlist(tail));
+                }
+                return index + 1;
+            }
+
+            offset += n_tracks;
+        }
+
         self.queue.len()
     }
 
-    pub fn next_items_queued(&self, n: usize) -> Vec<QueueElement> {
-        self.queue.iter().take(n).cloned().collect()
+    /// Every queued track in play order, with playlists expanded, alongside the playlist each
+    /// track belongs to.
+    pub fn queued_tracks(&self) -> impl Iterator<Item = (&Track, Option<&Playlist>)> {
+        self.queue.iter().flat_map(|element| {
+            let (track, playlist) = match element {
+                QueueElement::Track(track) => (Some(track), None),
+                QueueElement::Playlist(playlist) => (None, Some(playlist)),
+            };
+            track
+                .into_iter()
+                .chain(playlist.into_iter().flat_map(|p| p.items.iter()))
+                .map(move |track| (track, playlist))
+        })
     }
 
-    pub fn play_next(&mut self) {
-        let next = self.dequeue();
+    /// Advances to the next track according to the loop mode. Returns whether the finished track
+    /// is being replayed.
+    pub fn play_next(&mut self) -> bool {
+        let finished = self.current_track.take();
+        let skipped = std::mem::take(&mut self.skip_current);
+        let requeued = std::mem::take(&mut self.current_requeued);
+        self.stream_offset = Duration::ZERO;
+        self.skip_votes.clear();
+
+        if let Some(track) = &finished
+            && (skipped || self.loop_mode != LoopMode::Track)
+        {
+            self.push_history(track.clone());
+        }
+
+        let (next, repeated) = match (self.loop_mode, finished) {
+            (LoopMode::Track, Some(track)) if !skipped => (Some(track), true),
+            (LoopMode::Queue, Some(track)) => {
+                if !requeued {
+                    self.queue.push_back(QueueElement::Track(track));
+                }
+                (self.dequeue(), false)
+            }
+            _ => (self.dequeue(), false),
+        };
+
         self.set_playing(next.is_some());
         self.set_current_track(next);
-        self.set_track_handle(None)
+        self.set_track_handle(None);
+        repeated
+    }
+
+    /// Lines up the most recently finished track to play next, followed by the current track.
+    /// Neither is recorded in the history once the current track stops.
+    pub fn rewind(&mut self) -> Option<Track> {
+        let previous = self.history.pop_back()?;
+
+        if let Some(current) = self.current_track.take() {
+            self.queue.push_front(QueueElement::Track(current));
+        }
+        self.queue.push_front(QueueElement::Track(previous.clone()));
+
+        Some(previous)
+    }
+
+    /// Lines up the current track to play again from the start once it stops.
+    pub fn replay(&mut self) -> Option<Track> {
+        let current = self.current_track.take()?;
+        self.queue.push_front(QueueElement::Track(current.clone()));
+
+        Some(current)
+    }
+
+    /// Removes every track matching `drop`, returning how many current and queued ones went.
+    pub fn drop_tracks(&mut self, drop: impl Fn(&Track) -> bool) -> usize {
+        let n_before = self.number_of_tracks_queued();
+        self.queue.retain_mut(|element| match element {
+            QueueElement::Track(track) => !drop(track),
+            QueueElement::Playlist(p) => {
+                p.items.retain(|track| !drop(track));
+                !p.items.is_empty()
+            }
+        });
+        self.history.retain(|track| !drop(track));
+
+        let dropped_current = self.current_track.as_ref().is_some_and(&drop);
+        if dropped_current {
+            self.current_track = None;
+        }
+
+        n_before - self.number_of_tracks_queued() + usize::from(dropped_current)
+    }
+
+    /// Moves the current track back to the front of the queue so playback can be started afresh.
+    pub fn requeue_current_track(&mut self) {
+        if let Some(current) = self.current_track.take() {
+            self.queue.push_front(QueueElement::Track(current));
+        }
+        self.set_track_handle(None);
+        self.set_playing(false);
+    }
+
+    /// Finished tracks, most recent first.
+    pub fn get_history(&self) -> impl Iterator<Item = &Track> {
+        self.history.iter().rev()
+    }
+
+    fn push_history(&mut self, track: Track) {
+        if self.history.len() == HISTORY_CAP {
+            self.history.pop_front();
+        }
+        self.history.push_back(track);
+    }
+
+    pub fn get_loop_mode(&self) -> LoopMode {
+        self.loop_mode
+    }
+
+    pub fn set_loop_mode(&mut self, loop_mode: LoopMode) {
+        self.loop_mode = loop_mode;
     }
 
     pub fn toggle_radio_mode(&mut self) {
@@ -139,6 +510,257 @@ impl PlaybackState {
         self.set_track_handle(None);
         self.set_playing(false);
         self.radio_mode = RadioMode::Off;
+        self.loop_mode = LoopMode::Off;
+        self.skip_current = false;
+        self.current_requeued = false;
+        self.stream_offset = Duration::ZERO;
+        self.skip_votes.clear();
         self.queue.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TrackSource;
+
+    fn track(id: &str) -> Track {
+        Track::test(id)
+    }
+
+    fn playlist(id: &str, items: &[&str]) -> QueueElement {
+        QueueElement::Playlist(Playlist {
+            id: id.to_string(),
+            title: id.to_string(),
+            artist: String::new(),
+            url: format!("https://example.com/{id}"),
+            artwork_url: None,
+            source: TrackSource::default(),
+            items: items.iter().map(|id| track(id)).collect(),
+        })
+    }
+
+    fn state_with(elements: Vec<QueueElement>) -> PlaybackState {
+        let mut state = PlaybackState::default();
+        elements.into_iter().for_each(|e| state.enqueue(e, false));
+        state
+    }
+
+    fn queued_ids(state: &PlaybackState) -> Vec<&str> {
+        state.queued_tracks().map(|(t, _)| t.id.as_str()).collect()
+    }
+
+    #[test]
+    fn split_queue_at_splits_playlists_at_the_position() {
+        let mut state = state_with(vec![
+            QueueElement::Track(track("a")),
+            playlist("p", &["b", "c", "d"]),
+        ]);
+
+        assert_eq!(state.split_queue_at(0), 0);
+        assert_eq!(state.split_queue_at(1), 1);
+        assert_eq!(state.queue.len(), 2);
+
+        assert_eq!(state.split_queue_at(3), 2);
+        assert_eq!(state.queue.len(), 3);
+        assert_eq!(state.queue[1].number_of_tracks(), 2);
+        assert_eq!(state.queue[2].number_of_tracks(), 1);
+        assert_eq!(queued_ids(&state), ["a", "b", "c", "d"]);
+
+        assert_eq!(state.split_queue_at(10), 3);
+    }
+
+    #[test]
+    fn remove_track_takes_tracks_out_of_playlists() {
+        let mut state = state_with(vec![playlist("p", &["a", "b", "c"])]);
+
+        assert_eq!(state.remove_track(1).map(|t| t.id), Some("b".into()));
+        assert_eq!(queued_ids(&state), ["a", "c"]);
+        assert!(state.remove_track(2).is_none());
+    }
+
+    #[test]
+    fn insert_track_lands_at_the_position() {
+        let mut state = state_with(vec![playlist("p", &["a", "b"])]);
+
+        state.insert_track(1, track("x"));
+        state.insert_track(10, track("y"));
+        assert_eq!(queued_ids(&state), ["a", "x", "b", "y"]);
+    }
+
+    #[test]
+    fn move_and_swap_keep_every_track() {
+        let mut state = state_with(vec![
+            QueueElement::Track(track("a")),
+            playlist("p", &["b", "c"]),
+            QueueElement::Track(track("d")),
+        ]);
+
+        assert_eq!(state.move_track(0, 2).map(|t| t.id), Some("a".into()));
+        assert_eq!(queued_ids(&state), ["b", "c", "a", "d"]);
+
+        let (first, second) = state.swap_tracks(3, 0).unwrap();
+        assert_eq!((first.id.as_str(), second.id.as_str()), ("d", "b"));
+        assert_eq!(queued_ids(&state), ["d", "c", "a", "b"]);
+
+        assert!(state.swap_tracks(1, 1).is_none());
+        assert!(state.move_track(0, 4).is_none());
+    }
+
+    fn playing(mut state: PlaybackState, loop_mode: LoopMode) -> PlaybackState {
+        state.set_loop_mode(loop_mode);
+        state.play_next();
+        state
+    }
+
+    #[test]
+    fn queue_loop_skips_keep_the_loop_order() {
+        let mut state = playing(
+            state_with(
+                ["a", "b", "c", "d"]
+                    .map(|id| QueueElement::Track(track(id)))
+                    .into(),
+            ),
+            LoopMode::Queue,
+        );
+
+        assert_eq!(state.skip_tracks(2), 2);
+        state.play_next();
+
+        assert_eq!(state.get_current_track().as_ref().unwrap().id, "d");
+        assert_eq!(queued_ids(&state), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn queue_loop_appends_finished_tracks_once() {
+        let mut state = playing(
+            state_with(vec![
+                QueueElement::Track(track("a")),
+                QueueElement::Track(track("b")),
+            ]),
+            LoopMode::Queue,
+        );
+
+        state.skip_tracks(0);
+        state.skip_tracks(0);
+        state.play_next();
+        state.play_next();
+
+        assert_eq!(state.get_current_track().as_ref().unwrap().id, "a");
+        assert_eq!(queued_ids(&state), ["b"]);
+    }
+
+    #[test]
+    fn track_loop_repeats_until_skipped() {
+        let mut state = playing(
+            state_with(vec![
+                QueueElement::Track(track("a")),
+                QueueElement::Track(track("b")),
+            ]),
+            LoopMode::Track,
+        );
+
+        assert!(state.play_next());
+        assert_eq!(state.get_current_track().as_ref().unwrap().id, "a");
+
+        state.skip_tracks(0);
+        assert!(!state.play_next());
+        assert_eq!(state.get_current_track().as_ref().unwrap().id, "b");
+        assert_eq!(state.get_history().next().unwrap().id, "a");
+    }
+
+    #[test]
+    fn skip_votes_start_over_when_a_track_replays() {
+        let voter = UserId::new(1);
+        let listeners = [voter, UserId::new(2)].into();
+        let mut state = playing(
+            state_with(vec![QueueElement::Track(track("a"))]),
+            LoopMode::Track,
+        );
+
+        state.skip_votes_mut().cast(voter, &listeners);
+        assert!(state.play_next());
+        assert_eq!(state.skip_votes_mut().cast(UserId::new(2), &listeners), 1);
+    }
+
+    fn requested(mut element: QueueElement, user: u64) -> QueueElement {
+        element.set_requester(UserId::new(user));
+        element
+    }
+
+    fn fair_queue_with(elements: Vec<QueueElement>) -> PlaybackState {
+        let mut state = PlaybackState::default();
+        elements.into_iter().for_each(|e| state.enqueue(e, true));
+        state
+    }
+
+    #[test]
+    fn fair_queue_takes_turns_between_requesters() {
+        let state = fair_queue_with(vec![
+            requested(playlist("p", &["a1", "a2", "a3"]), 1),
+            requested(QueueElement::Track(track("b1")), 2),
+            requested(QueueElement::Track(track("b2")), 2),
+            requested(QueueElement::Track(track("c1")), 3),
+        ]);
+
+        assert_eq!(queued_ids(&state), ["a1", "b1", "c1", "a2", "b2", "a3"]);
+    }
+
+    #[test]
+    fn fair_queue_lets_the_current_requester_wait() {
+        let mut state = fair_queue_with(vec![
+            requested(QueueElement::Track(track("a1")), 1),
+            requested(QueueElement::Track(track("a2")), 1),
+        ]);
+        state.play_next();
+
+        state.enqueue(requested(QueueElement::Track(track("b1")), 2), true);
+        assert_eq!(queued_ids(&state), ["b1", "a2"]);
+    }
+
+    #[test]
+    fn fair_queue_leaves_queued_tracks_in_place() {
+        let mut state = fair_queue_with(vec![
+            requested(QueueElement::Track(track("a1")), 1),
+            requested(QueueElement::Track(track("a2")), 1),
+            requested(QueueElement::Track(track("b1")), 2),
+        ]);
+        assert_eq!(queued_ids(&state), ["a1", "b1", "a2"]);
+
+        state.move_track(2, 0);
+        state.enqueue(requested(QueueElement::Track(track("c1")), 3), true);
+        assert_eq!(queued_ids(&state), ["a2", "c1", "a1", "b1"]);
+
+        state.enqueue(requested(QueueElement::Track(track("b2")), 2), true);
+        assert_eq!(queued_ids(&state), ["a2", "c1", "a1", "b1", "b2"]);
+    }
+
+    #[test]
+    fn fair_queue_keeps_playlist_runs_together() {
+        let state = fair_queue_with(vec![
+            requested(QueueElement::Track(track("b1")), 2),
+            requested(playlist("p", &["a1", "a2"]), 1),
+            requested(playlist("p", &["a3"]), 1),
+        ]);
+
+        assert_eq!(queued_ids(&state), ["b1", "a1", "a2", "a3"]);
+        assert_eq!(state.queue.len(), 2);
+    }
+
+    #[test]
+    fn drop_tracks_removes_matches_everywhere() {
+        let mut state = playing(
+            state_with(vec![
+                QueueElement::Track(track("x1")),
+                playlist("p", &["a", "x2"]),
+                playlist("q", &["x3"]),
+            ]),
+            LoopMode::Off,
+        );
+
+        assert_eq!(state.drop_tracks(|t| t.id.starts_with('x')), 3);
+        assert!(state.get_current_track().is_none());
+        assert_eq!(queued_ids(&state), ["a"]);
+        assert_eq!(state.queue.len(), 1);
+    }
+}
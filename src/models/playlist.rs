@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, fmt::Display};
+
+use super::{Track, TrackSource};
+
+/// A collection of tracks queued together, whichever backend it comes from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Playlist {
+    pub id: String,
+    pub title: String,
+    #[serde(alias = "channel")]
+    pub artist: String,
+    pub url: String,
+    #[serde(default, alias = "thumbnail_url")]
+    pub artwork_url: Option<String>,
+    #[serde(default)]
+    pub source: TrackSource,
+    pub items: VecDeque<Track>,
+}
+
+impl Playlist {
+    /// A copy of the playlist holding only `items`.
+    pub fn with_items(&self, items: VecDeque<Track>) -> Self {
+        Self {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            artist: self.artist.clone(),
+            url: self.url.clone(),
+            artwork_url: self.artwork_url.clone(),
+            source: self.source,
+            items,
+        }
+    }
+
+    /// The artwork's URL, if the playlist has any.
+    pub fn artwork(&self) -> Option<&str> {
+        self.artwork_url.as_deref().filter(|url| !url.is_empty())
+    }
+}
+
+impl Display for Playlist {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Playlist: {} - {} with {} remaining tracks",
+            self.title,
+            self.artist,
+            self.items.len()
+        )
+    }
+}
@@ -0,0 +1,202 @@
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect,
+};
+use std::{
+    io,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+use tracing::trace;
+use url::{Host, Url};
+
+use crate::server::USER_AGENT;
+
+/// Most redirects followed before a request is given up on.
+const MAX_REDIRECTS: usize = 5;
+
+/// How long the server may take to accept a connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long the server may go without sending anything.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Raised when a link leads somewhere members must not make the bot fetch from, such as the
+/// machine it runs on or the network behind it.
+#[derive(thiserror::Error, Debug)]
+pub enum AddressError {
+    #[error("I couldn't find the server that link points at.")]
+    Lookup(#[source] io::Error),
+
+    #[error("I can't play links to local or private addresses.")]
+    NotPublic,
+}
+
+/// Whether `ip` is reachable on the public internet, rather than a loopback, private,
+/// link-local, shared or unspecified address.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            let is_shared = first == 100 && (64..128).contains(&second);
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || is_shared
+                || first == 0)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(ip));
+            }
+
+            let first = ip.segments()[0];
+            let is_unique_local = first & 0xfe00 == 0xfc00;
+            let is_link_local = first & 0xffc0 == 0xfe80;
+            !(ip.is_loopback() || ip.is_unspecified() || is_unique_local || is_link_local)
+        }
+    }
+}
+
+/// Whether the host of `url` is known not to be public without resolving it: a private IP
+/// address, a name for the local machine, or no host at all.
+pub fn is_private_host(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Ipv4(ip)) => !is_public_ip(IpAddr::V4(ip)),
+        Some(Host::Ipv6(ip)) => !is_public_ip(IpAddr::V6(ip)),
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_lowercase();
+            domain == "localhost" || domain.ends_with(".localhost")
+        }
+        None => true,
+    }
+}
+
+/// Checks that every address the host of `url` resolves to is public. The check is repeated by
+/// `PublicResolver` when the bot fetches the link itself, which also covers redirects.
+pub async fn ensure_public(url: &Url) -> Result<(), AddressError> {
+    if is_private_host(url) {
+        return Err(AddressError::NotPublic);
+    }
+
+    let Some(Host::Domain(domain)) = url.host() else {
+        return Ok(());
+    };
+
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addrs = tokio::net::lookup_host((domain, port))
+        .await
+        .map_err(AddressError::Lookup)?
+        .collect::<Vec<_>>();
+    trace!(?addrs, "Resolved link host.");
+
+    if addrs.is_empty() || addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+        return Err(AddressError::NotPublic);
+    }
+
+    Ok(())
+}
+
+/// Resolves host names for reqwest, refusing those with a non-public address. Checking at
+/// connection time keeps a name from changing its address after it was checked.
+#[derive(Debug, Default)]
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .collect::<Vec<SocketAddr>>();
+
+            if addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+                return Err(AddressError::NotPublic.into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// A redirect policy refusing redirects to hosts that are known not to be public. Host names
+/// are left to `PublicResolver`, as IP addresses are connected to without resolving them.
+pub fn redirect_policy() -> redirect::Policy {
+    redirect::Policy::custom(|attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else if is_private_host(attempt.url()) {
+            attempt.error(AddressError::NotPublic)
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+/// A client builder for fetching links members hand the bot, which only connects to public
+/// addresses and gives up on servers that stall.
+pub fn client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .dns_resolver(Arc::new(PublicResolver))
+        .redirect(redirect_policy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_public(ip: &str) -> bool {
+        is_public_ip(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn is_public_ip_rejects_local_and_private_ranges() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(ip), "{ip} passed as public");
+        }
+
+        for ip in ["1.1.1.1", "162.159.128.233", "2606:4700::1111"] {
+            assert!(is_public(ip), "{ip} was rejected");
+        }
+    }
+
+    #[test]
+    fn is_private_host_catches_literals_and_localhost() {
+        let private = |url: &str| is_private_host(&Url::parse(url).unwrap());
+
+        assert!(private("http://127.0.0.1/a.mp3"));
+        assert!(private("http://[::1]:8080/a.mp3"));
+        assert!(private("http://localhost/a.mp3"));
+        assert!(private("http://api.LOCALHOST./a.mp3"));
+        assert!(!private("https://cdn.discordapp.com/a.mp3"));
+        assert!(!private("https://1.1.1.1/a.mp3"));
+    }
+
+    #[tokio::test]
+    async fn ensure_public_rejects_private_literals() {
+        let url = Url::parse("http://192.168.0.1/a.mp3").unwrap();
+        assert!(matches!(
+            ensure_public(&url).await,
+            Err(AddressError::NotPublic)
+        ));
+    }
+}
@@ -1,31 +1,88 @@
-use std::fmt::Display;
+use poise::serenity_prelude::UserId;
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, fmt::Display};
 
-use super::{PlaylistMetadata, VideoMetadata, YoutubeMetadata};
+use super::{Playlist, Track};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum QueueElement {
-    Track(VideoMetadata),
-    Playlist(PlaylistMetadata),
+    Track(Track),
+    Playlist(Playlist),
+}
+
+impl QueueElement {
+    /// Number of individual tracks held by this element.
+    pub fn number_of_tracks(&self) -> usize {
+        match self {
+            QueueElement::Track(_) => 1,
+            QueueElement::Playlist(p) => p.items.len(),
+        }
+    }
+
+    /// The tracks held by this element, in play order.
+    pub fn tracks(&self) -> impl Iterator<Item = &Track> {
+        let (track, playlist) = match self {
+            QueueElement::Track(t) => (Some(t), None),
+            QueueElement::Playlist(p) => (None, Some(p)),
+        };
+        track
+            .into_iter()
+            .chain(playlist.into_iter().flat_map(|p| p.items.iter()))
+    }
+
+    /// The member who queued this element. Unknown for radio picks.
+    pub fn requester(&self) -> Option<UserId> {
+        self.tracks().next().and_then(|t| t.requested_by)
+    }
+
+    /// Keeps only the first `n` tracks of a playlist. Single tracks are left untouched.
+    pub fn truncate(&mut self, n: usize) {
+        if let QueueElement::Playlist(p) = self {
+            p.items.truncate(n);
+        }
+    }
+
+    /// Splits the element into one element per track. Tracks from a playlist stay attributed to
+    /// it.
+    pub fn into_single_tracks(self) -> Vec<QueueElement> {
+        match self {
+            QueueElement::Track(_) => vec![self],
+            QueueElement::Playlist(mut p) => std::mem::take(&mut p.items)
+                .into_iter()
+                .map(|item| QueueElement::Playlist(p.with_items(VecDeque::from([item]))))
+                .collect(),
+        }
+    }
+
+    /// Attributes every track held by this element to `user`.
+    pub fn set_requester(&mut self, user: UserId) {
+        match self {
+            QueueElement::Track(t) => t.requested_by = Some(user),
+            QueueElement::Playlist(p) => {
+                p.items.iter_mut().for_each(|t| t.requested_by = Some(user))
+            }
+        }
+    }
 }
 
 impl Display for QueueElement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             QueueElement::Track(t) => {
-                write!(f, "Track: {} - {}\nURL: {}", t.title, t.channel, t.url)
+                write!(f, "Track: {} - {}\nURL: {}", t.title, t.artist, t.url)
             }
             QueueElement::Playlist(p) => {
                 let head = p
                     .items
                     .front()
-                    .map(|t| format!("Up next: {} - {}\n", t.title, t.channel))
+                    .map(|t| format!("Up next: {} - {}\n", t.title, t.artist))
                     .unwrap_or("".to_string());
 
                 write!(
                     f,
                     "Playlist: {} - {} with {} tracks remaining\n{head}URL: {}",
                     p.title,
-                    p.channel,
+                    p.artist,
                     p.items.len(),
                     p.url
                 )
@@ -33,12 +90,3 @@ impl Display for QueueElement {
         }
     }
 }
-
-impl From<YoutubeMetadata> for QueueElement {
-    fn from(value: YoutubeMetadata) -> Self {
-        match value {
-            YoutubeMetadata::Track(t) => Self::Track(t),
-            YoutubeMetadata::Playlist(p) => Self::Playlist(p),
-        }
-    }
-}
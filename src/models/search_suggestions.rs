@@ -0,0 +1,97 @@
+use poise::serenity_prelude::UserId;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// How long a member must pause typing before their query is looked up.
+const DEBOUNCE: Duration = Duration::from_millis(400);
+
+/// How long suggestions for a query are served from the cache.
+const SUGGESTION_TTL: Duration = Duration::from_secs(600);
+
+/// Most queries whose suggestions are kept at once.
+const MAX_CACHED_QUERIES: usize = 1000;
+
+/// A suggestion for a search query, naming a result and holding its URL.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub name: String,
+    pub url: String,
+}
+
+/// Which kind of results suggestions are for, along with the normalised query.
+type QueryKey = (bool, String);
+
+#[derive(Debug, Default)]
+struct SuggestionState {
+    /// Suggestions by query, with when they were looked up.
+    cache: HashMap<QueryKey, (Instant, Vec<Suggestion>)>,
+    /// How many lookups each member has started, to tell whether one was overtaken by the next.
+    keystrokes: HashMap<UserId, u64>,
+}
+
+/// Debounces and caches the lookups behind search query autocompletion, so typing a query
+/// doesn't cost a search per keystroke.
+#[derive(Debug, Clone, Default)]
+pub struct SearchSuggestions {
+    state: Arc<Mutex<SuggestionState>>,
+}
+
+impl SearchSuggestions {
+    fn key(query: &str, playlists: bool) -> QueryKey {
+        (playlists, query.trim().to_lowercase())
+    }
+
+    /// The suggestions cached for `query`, unless they have expired.
+    pub fn cached(&self, query: &str, playlists: bool) -> Option<Vec<Suggestion>> {
+        self.lock()
+            .cache
+            .get(&Self::key(query, playlists))
+            .filter(|(looked_up, _)| looked_up.elapsed() < SUGGESTION_TTL)
+            .map(|(_, suggestions)| suggestions.clone())
+    }
+
+    /// Caches the suggestions for `query`, making room by dropping expired ones first and the
+    /// oldest ones if that isn't enough.
+    pub fn store(&self, query: &str, playlists: bool, suggestions: Vec<Suggestion>) {
+        let mut state = self.lock();
+        state
+            .cache
+            .retain(|_, (looked_up, _)| looked_up.elapsed() < SUGGESTION_TTL);
+
+        if state.cache.len() >= MAX_CACHED_QUERIES
+            && let Some(oldest) = state
+                .cache
+                .iter()
+                .min_by_key(|(_, (looked_up, _))| *looked_up)
+                .map(|(key, _)| key.clone())
+        {
+            state.cache.remove(&oldest);
+        }
+
+        state
+            .cache
+            .insert(Self::key(query, playlists), (Instant::now(), suggestions));
+    }
+
+    /// Waits for `user` to pause typing. Returns `false` when they typed on in the meantime, in
+    /// which case the newer lookup supersedes this one.
+    pub async fn debounce(&self, user: UserId) -> bool {
+        let keystroke = {
+            let mut state = self.lock();
+            let count = state.keystrokes.entry(user).or_default();
+            *count += 1;
+            *count
+        };
+
+        tokio::time::sleep(DEBOUNCE).await;
+
+        self.lock().keystrokes.get(&user) == Some(&keystroke)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SuggestionState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
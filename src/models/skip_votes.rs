@@ -0,0 +1,61 @@
+use poise::serenity_prelude::UserId;
+use std::collections::HashSet;
+
+/// Members who voted to skip the current track. Cleared whenever a track starts, so votes never
+/// carry over to the next track, even when the same track plays again.
+#[derive(Debug, Clone, Default)]
+pub struct SkipVotes {
+    voters: HashSet<UserId>,
+}
+
+impl SkipVotes {
+    /// Records `voter`'s vote, discarding the votes of members who are no longer among
+    /// `listeners`. Returns the number of votes that still count.
+    pub fn cast(&mut self, voter: UserId, listeners: &HashSet<UserId>) -> usize {
+        self.voters.insert(voter);
+        self.voters.retain(|voter| listeners.contains(voter));
+        self.voters.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.voters.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn users(ids: &[u64]) -> HashSet<UserId> {
+        ids.iter().copied().map(UserId::new).collect()
+    }
+
+    #[test]
+    fn votes_count_once_per_member() {
+        let mut votes = SkipVotes::default();
+        let listeners = users(&[1, 2, 3]);
+
+        assert_eq!(votes.cast(UserId::new(1), &listeners), 1);
+        assert_eq!(votes.cast(UserId::new(1), &listeners), 1);
+        assert_eq!(votes.cast(UserId::new(2), &listeners), 2);
+    }
+
+    #[test]
+    fn votes_of_members_who_left_stop_counting() {
+        let mut votes = SkipVotes::default();
+        votes.cast(UserId::new(1), &users(&[1, 2, 3]));
+        votes.cast(UserId::new(2), &users(&[1, 2, 3]));
+
+        assert_eq!(votes.cast(UserId::new(3), &users(&[2, 3])), 2);
+    }
+
+    #[test]
+    fn cleared_votes_start_over() {
+        let mut votes = SkipVotes::default();
+        let listeners = users(&[1, 2]);
+        votes.cast(UserId::new(1), &listeners);
+
+        votes.clear();
+        assert_eq!(votes.cast(UserId::new(2), &listeners), 1);
+    }
+}
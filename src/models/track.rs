@@ -0,0 +1,101 @@
+use poise::serenity_prelude::UserId;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, time::Duration};
+use url::Url;
+
+/// Credited for tracks whose source doesn't name an artist.
+pub const UNKNOWN_ARTIST: &str = "Unknown Artist";
+
+/// Hosts serving Discord attachments, whose links are signed and expire.
+const ATTACHMENT_HOSTS: [&str; 2] = ["cdn.discordapp.com", "media.discordapp.net"];
+
+/// The backend a track was resolved by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrackSource {
+    #[default]
+    Youtube,
+    /// An audio file linked directly or attached to a message.
+    File,
+    /// A page on one of the other sites yt-dlp can extract audio from.
+    Web,
+}
+
+/// How a track's audio is fetched for streaming.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StreamLocator {
+    /// yt-dlp extracts the audio from the track's page.
+    #[default]
+    Extractor,
+    /// ffmpeg reads the track's URL as an audio file.
+    Direct,
+}
+
+/// A playable track, whichever backend it comes from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Track {
+    /// Identifies the track within its source.
+    pub id: String,
+    pub title: String,
+    #[serde(alias = "channel")]
+    pub artist: String,
+    /// Where members can find the track, and where its audio is located.
+    pub url: String,
+    #[serde(default, alias = "thumbnail_url")]
+    pub artwork_url: Option<String>,
+    /// Unknown for results that come without their content details.
+    #[serde(default)]
+    pub duration: Option<Duration>,
+    /// Whether the track is a live stream or premiere on air, which has no length and can't be
+    /// seeked.
+    #[serde(default)]
+    pub is_live: bool,
+    #[serde(default)]
+    pub source: TrackSource,
+    #[serde(default)]
+    pub locator: StreamLocator,
+    /// The member who queued the track. Unknown for radio picks.
+    #[serde(default)]
+    pub requested_by: Option<UserId>,
+}
+
+impl Track {
+    /// The artwork's URL, if the track has any.
+    pub fn artwork(&self) -> Option<&str> {
+        self.artwork_url.as_deref().filter(|url| !url.is_empty())
+    }
+
+    /// Whether the track's link stops working after a while, as Discord attachment links do, so
+    /// that it can't be played after a restart.
+    pub fn has_expiring_link(&self) -> bool {
+        self.locator == StreamLocator::Direct
+            && Url::parse(&self.url)
+                .ok()
+                .and_then(|url| url.host_str().map(|host| ATTACHMENT_HOSTS.contains(&host)))
+                .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+impl Track {
+    /// A track of unknown length whose title and URL are derived from `id`.
+    pub fn test(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            title: id.to_string(),
+            artist: UNKNOWN_ARTIST.to_string(),
+            url: format!("https://example.com/{id}"),
+            artwork_url: None,
+            duration: None,
+            is_live: false,
+            source: TrackSource::default(),
+            locator: StreamLocator::default(),
+            requested_by: None,
+        }
+    }
+}
+
+impl Display for Track {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Track: {} - {}", self.title, self.artist)
+    }
+}
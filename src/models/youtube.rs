@@ -1,3 +1,4 @@
+use async_trait::async_trait;
 use google_youtube3::hyper_rustls;
 use google_youtube3::{
     YouTube,
@@ -8,27 +9,33 @@ use google_youtube3::{
         rt::TokioExecutor,
     },
 };
-use std::fmt::Debug;
+use metrics::counter;
+use std::{collections::HashMap, fmt::Debug};
 use tracing::{error, info, instrument, trace, warn};
+use url::Url;
 
-mod metadata_utils;
-pub mod playlist_metadata;
-pub mod video_metadata;
+use super::{
+    MetadataError, MetadataProvider, Playlist, QueueElement, Track, YtdlpError, YtdlpProvider,
+};
+use crate::metrics::Metric;
+use quota::{ApiCall, ApiStatus};
 
-use playlist_metadata::PlaylistMetadata;
-use video_metadata::VideoMetadata;
+pub use metadata_cache::{CacheError, MetadataCache};
 
-#[derive(Debug)]
-pub enum YoutubeMetadata {
-    Track(VideoMetadata),
-    Playlist(PlaylistMetadata),
-}
+mod metadata_cache;
+mod metadata_utils;
+mod playlist_conversions;
+mod quota;
+mod track_conversions;
 
 const SINGLE_URI: &str = "https://youtube.com/watch?v=";
 const PLAYLIST_URI: &str = "https://youtube.com/playlist?list=";
 
 const PLAYLIST_CAP: u32 = 50;
 
+/// Most video IDs the API accepts in a single `videos.list` request.
+const VIDEOS_PER_REQUEST: usize = 50;
+
 #[derive(thiserror::Error, Debug)]
 pub enum YoutubeError {
     #[error("Failed to extract video information from results.")]
@@ -45,12 +52,40 @@ pub enum YoutubeError {
 
     #[error("Unsupported Error: {0}")]
     Unsupported(String),
+
+    #[error("The YouTube API quota is exhausted.")]
+    QuotaExceeded,
+
+    #[error("The YouTube API refused the request: {0}")]
+    Forbidden(String),
+
+    #[error("yt-dlp fallback failed: {0}")]
+    Fallback(#[from] YtdlpError),
+}
+
+impl YoutubeError {
+    /// Whether the error comes down to the API being out of reach rather than to the request,
+    /// in which case yt-dlp can stand in.
+    fn is_api_unavailable(&self) -> bool {
+        match self {
+            YoutubeError::QuotaExceeded | YoutubeError::Forbidden(_) => true,
+            YoutubeError::Api(google_youtube3::Error::HttpError(_)) => true,
+            YoutubeError::Api(google_youtube3::Error::Failure(response)) => {
+                response.status().is_server_error()
+            }
+            _ => false,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct YoutubeClient {
     api_key: String,
     client: YouTube<HttpsConnector<HttpConnector>>,
+    cache: MetadataCache,
+    /// Looks metadata up while the API is unavailable, such as once the quota is used up.
+    fallback: YtdlpProvider,
+    api_status: ApiStatus,
 }
 
 impl Debug for YoutubeClient {
@@ -62,7 +97,7 @@ impl Debug for YoutubeClient {
 }
 
 impl YoutubeClient {
-    pub async fn new(api_key: &str) -> Self {
+    pub async fn new(api_key: &str, cache: MetadataCache) -> Self {
         let client = Client::builder(TokioExecutor::new());
         let connector = hyper_rustls::HttpsConnectorBuilder::new()
             .with_native_roots()
@@ -77,41 +112,135 @@ impl YoutubeClient {
         Self {
             api_key: api_key.to_string(),
             client: hub,
+            cache,
+            fallback: YtdlpProvider::new(),
+            api_status: ApiStatus::default(),
         }
     }
 
-    #[instrument]
-    pub async fn process_url(&self, url: &str) -> Result<YoutubeMetadata, YoutubeError> {
-        trace!("Processing URL.");
-        let parsed_url = url::Url::parse(url).map_err(|e| {
-            trace!(err=%e, "Could not parse URL.");
-            YoutubeError::Url
-        })?;
-
-        // Attempt to extract a video ID first
-        if let Some(id) = Self::extract_video_id(&parsed_url) {
-            trace!(video_id=%id, "URL designated as video.");
-            let metadata = self.get_video_metadata(&id).await?;
-            trace!(metadata=%metadata, "Video metadata retrieved.");
-            return Ok(YoutubeMetadata::Track(metadata));
+    /// Runs `request` against the API, or `fallback` when the API is unavailable.
+    async fn with_fallback<T>(
+        &self,
+        request: impl Future<Output = Result<T, YoutubeError>>,
+        fallback: impl Future<Output = Result<T, YtdlpError>>,
+    ) -> Result<T, YoutubeError> {
+        if !self.api_status.is_paused() {
+            match request.await {
+                Err(e) if e.is_api_unavailable() => self.api_status.pause(&e),
+                result => return result,
+            }
         }
 
-        // Fallback to attempting to extract a playlist ID
-        if let Some(id) = Self::extract_playlist_id(&parsed_url) {
-            trace!(playlist_id=%id, "URL designated as playlist.");
-            let metadata = self.get_playlist_metadata(&id).await?;
-            trace!(metadata=%metadata, "Playlist metadata retrieved.");
-            return Ok(YoutubeMetadata::Playlist(metadata));
-        }
+        trace!("Looking metadata up with yt-dlp.");
+        counter!(Metric::YoutubeFallbackTotal.as_ref()).increment(1);
+        Ok(fallback.await?)
+    }
 
-        // If neither worked, the URL format is unsupported
-        trace!(url=?parsed_url, "Reporting URL as error.");
-        Err(YoutubeError::Url)
+    async fn fetch_video_search(
+        &self,
+        query: &str,
+        n_results: u32,
+    ) -> Result<Vec<Track>, YoutubeError> {
+        self.with_fallback(
+            self.request_video_search(query, n_results),
+            self.fallback.search_youtube_videos(query, n_results),
+        )
+        .await
+    }
+
+    async fn fetch_playlist_search(
+        &self,
+        query: &str,
+        n_results: u32,
+    ) -> Result<Vec<Playlist>, YoutubeError> {
+        self.with_fallback(
+            self.request_playlist_search(query, n_results),
+            self.fallback.search_youtube_playlists(query, n_results),
+        )
+        .await
+    }
+
+    async fn fetch_video_metadata(&self, video_id: &str) -> Result<Track, YoutubeError> {
+        let fallback = async {
+            match self
+                .fallback
+                .resolve(&format!("{SINGLE_URI}{video_id}"))
+                .await?
+            {
+                QueueElement::Track(track) => Ok(track),
+                QueueElement::Playlist(_) => Err(YtdlpError::Extraction(
+                    "Expected a video, got a playlist.".to_string(),
+                )),
+            }
+        };
+        self.with_fallback(self.request_video_metadata(video_id), fallback)
+            .await
+    }
+
+    async fn fetch_playlist_metadata(&self, playlist_id: &str) -> Result<Playlist, YoutubeError> {
+        let fallback = async {
+            match self
+                .fallback
+                .resolve(&format!("{PLAYLIST_URI}{playlist_id}"))
+                .await?
+            {
+                QueueElement::Playlist(playlist) => Ok(playlist),
+                QueueElement::Track(_) => Err(YtdlpError::Extraction(
+                    "Expected a playlist, got a video.".to_string(),
+                )),
+            }
+        };
+        self.with_fallback(self.request_playlist_metadata(playlist_id), fallback)
+            .await
+    }
+
+    /// The top `n_results` videos matching `query`, best match first.
+    pub async fn search_videos(
+        &self,
+        query: &str,
+        n_results: u32,
+    ) -> Result<Vec<Track>, YoutubeError> {
+        self.cache
+            .video_search(query, n_results, self.fetch_video_search(query, n_results))
+            .await
+    }
+
+    /// The top `n_results` playlists matching `query`, best match first. Their items are left
+    /// out until one is picked.
+    pub async fn search_playlists(
+        &self,
+        query: &str,
+        n_results: u32,
+    ) -> Result<Vec<Playlist>, YoutubeError> {
+        self.cache
+            .playlist_search(
+                query,
+                n_results,
+                self.fetch_playlist_search(query, n_results),
+            )
+            .await
+    }
+
+    pub async fn get_video_metadata(&self, video_id: &str) -> Result<Track, YoutubeError> {
+        self.cache
+            .video(video_id, self.fetch_video_metadata(video_id))
+            .await
+    }
+
+    pub async fn get_playlist_metadata(&self, playlist_id: &str) -> Result<Playlist, YoutubeError> {
+        self.cache
+            .playlist(playlist_id, self.fetch_playlist_metadata(playlist_id))
+            .await
     }
 
     #[instrument(skip(self))]
-    pub async fn search_video(&self, query: &str) -> Result<VideoMetadata, YoutubeError> {
-        trace!("Searching for video");
+    async fn request_video_search(
+        &self,
+        query: &str,
+        n_results: u32,
+    ) -> Result<Vec<Track>, YoutubeError> {
+        trace!("Searching for videos");
+        ApiCall::Search.spend();
         let (_, list) = self
             .client
             .search()
@@ -119,33 +248,39 @@ impl YoutubeClient {
             .q(query)
             .param("key", &self.api_key)
             .add_type("video")
-            .max_results(1)
+            .max_results(n_results)
             .doit()
             .await
             .map_err(|e| {
-                error!(err=%e, "Failed searching for video resource.");
-                YoutubeError::Api(e)
+                error!(err=%e, "Failed searching for video resources.");
+                quota::classify(e)
             })?;
 
-        let top_result = list
+        let mut results = list
             .items
-            .as_ref()
-            .and_then(|items| items.first())
-            .ok_or_else(|| {
-                info!("Failed to find video resource with given search query.");
-                YoutubeError::NotFound
-            })?;
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|item| Track::try_from(item).ok())
+            .collect::<Vec<_>>();
+
+        if results.is_empty() {
+            info!("Failed to find video resources with given search query.");
+            return Err(YoutubeError::NotFound);
+        }
 
-        VideoMetadata::try_from(top_result)
+        // Search results come without content details.
+        self.fill_details(&mut results).await;
+        Ok(results)
     }
 
     #[instrument(skip(self))]
-    pub async fn search_playlist(
+    async fn request_playlist_search(
         &self,
         query: &str,
-        n_items: u32,
-    ) -> Result<PlaylistMetadata, YoutubeError> {
-        trace!("Searching for playlist");
+        n_results: u32,
+    ) -> Result<Vec<Playlist>, YoutubeError> {
+        trace!("Searching for playlists");
+        ApiCall::Search.spend();
         let (_, list) = self
             .client
             .search()
@@ -153,44 +288,44 @@ impl YoutubeClient {
             .q(query)
             .param("key", &self.api_key)
             .add_type("playlist")
-            .max_results(1)
+            .max_results(n_results)
             .doit()
             .await
             .map_err(|e| {
-                error!(err=%e, "Failed searching for playlist resource.");
-                YoutubeError::Api(e)
+                error!(err=%e, "Failed searching for playlist resources.");
+                quota::classify(e)
             })?;
 
-        let top_result = list
+        let results = list
             .items
-            .as_ref()
-            .and_then(|items| items.first())
-            .ok_or_else(|| {
-                info!("Failed to find playlist matching given query");
-                YoutubeError::NotFound
-            })?;
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|item| Playlist::try_from(item).ok())
+            .collect::<Vec<_>>();
 
-        let mut metadata = PlaylistMetadata::try_from(top_result)?;
-        let items = self.fetch_playlist_items(&metadata.id, n_items).await?;
-        metadata.items.extend(items);
+        if results.is_empty() {
+            info!("Failed to find playlists matching given query");
+            return Err(YoutubeError::NotFound);
+        }
 
-        Ok(metadata)
+        Ok(results)
     }
 
     #[instrument(skip(self))]
-    pub async fn get_video_metadata(&self, video_id: &str) -> Result<VideoMetadata, YoutubeError> {
+    async fn request_video_metadata(&self, video_id: &str) -> Result<Track, YoutubeError> {
         trace!("Requested video metadata");
+        ApiCall::Videos.spend();
         let (_, list) = self
             .client
             .videos()
-            .list(&vec!["snippet".to_string()])
+            .list(&vec!["snippet".to_string(), "contentDetails".to_string()])
             .add_id(video_id)
             .param("key", &self.api_key)
             .doit()
             .await
             .map_err(|e| {
                 error!(err=%e, "Error fetching resource.");
-                YoutubeError::Api(e)
+                quota::classify(e)
             })?;
 
         let video = list
@@ -202,14 +337,11 @@ impl YoutubeClient {
                 YoutubeError::NotFound
             })?;
 
-        VideoMetadata::try_from(video)
+        Track::try_from(video)
     }
 
     #[instrument(skip(self))]
-    pub async fn get_playlist_metadata(
-        &self,
-        playlist_id: &str,
-    ) -> Result<PlaylistMetadata, YoutubeError> {
+    async fn request_playlist_metadata(&self, playlist_id: &str) -> Result<Playlist, YoutubeError> {
         trace!("Requested playlist metadata");
         let metadata_request = self
             .client
@@ -220,6 +352,7 @@ impl YoutubeClient {
             .max_results(1);
 
         // Run concurrently
+        ApiCall::Playlists.spend();
         let (playlist_res, items_res) = tokio::join!(
             metadata_request.doit(),
             self.fetch_playlist_items(playlist_id, PLAYLIST_CAP)
@@ -228,7 +361,7 @@ impl YoutubeClient {
         // Map and clean up
         let (_, list) = playlist_res.map_err(|e| {
             error!(err=%e, "Error fetching playlist resource.");
-            YoutubeError::Api(e)
+            quota::classify(e)
         })?;
 
         let items = items_res?;
@@ -242,7 +375,7 @@ impl YoutubeClient {
                 YoutubeError::NotFound
             })?;
 
-        let mut metadata = PlaylistMetadata::try_from(playlist)?;
+        let mut metadata = Playlist::try_from(playlist)?;
         metadata.items.extend(items);
         Ok(metadata)
     }
@@ -251,7 +384,7 @@ impl YoutubeClient {
         &self,
         playlist_id: &str,
         n_items: u32,
-    ) -> Result<Vec<VideoMetadata>, YoutubeError> {
+    ) -> Result<Vec<Track>, YoutubeError> {
         let mut page_token: Option<String> = None;
         let mut playlist_items = Vec::new();
 
@@ -268,11 +401,12 @@ impl YoutubeClient {
                 request = request.page_token(token);
             }
 
-            let (_, response) = request.doit().await.map_err(YoutubeError::Api)?;
+            ApiCall::PlaylistItems.spend();
+            let (_, response) = request.doit().await.map_err(quota::classify)?;
 
             if let Some(items) = response.items {
                 let valid_metadata = items.into_iter().filter_map(|item| {
-                    VideoMetadata::try_from(&item)
+                    Track::try_from(&item)
                         .map_err(|_| trace!("Skipped playlist item"))
                         .ok()
                 });
@@ -290,24 +424,81 @@ impl YoutubeClient {
             page_token = Some(next_token);
         }
 
+        // Playlist items come without content details.
+        self.fill_details(&mut playlist_items).await;
         Ok(playlist_items)
     }
 
-    /// Fetches a related video for radio mode.
+    /// Looks up the durations of tracks that lack one, and whether they are live, batching the
+    /// requests. Durations are a nicety, so tracks whose lookup fails are left without one.
+    #[instrument(skip_all, fields(n_tracks = tracks.len()))]
+    async fn fill_details(&self, tracks: &mut [Track]) {
+        let missing = tracks
+            .iter()
+            .filter(|t| t.duration.is_none() && !t.is_live)
+            .map(|t| t.id.clone())
+            .collect::<Vec<_>>();
+
+        let mut details = HashMap::new();
+        for ids in missing.chunks(VIDEOS_PER_REQUEST) {
+            let request = ids.iter().fold(
+                self.client
+                    .videos()
+                    .list(&vec!["snippet".to_string(), "contentDetails".to_string()])
+                    .param("key", &self.api_key)
+                    .max_results(VIDEOS_PER_REQUEST as u32),
+                |request, id| request.add_id(id),
+            );
+
+            ApiCall::Videos.spend();
+            let videos = match request.doit().await.map_err(quota::classify) {
+                Ok((_, list)) => list.items.unwrap_or_default(),
+                Err(e) if e.is_api_unavailable() => {
+                    self.api_status.pause(&e);
+                    break;
+                }
+                Err(e) => {
+                    warn!(err = %e, "Failed to fetch track durations.");
+                    continue;
+                }
+            };
+
+            details.extend(videos.into_iter().filter_map(|video| {
+                let is_live = video.snippet.as_ref().is_some_and(|snippet| {
+                    metadata_utils::is_live_stream(snippet.live_broadcast_content.as_ref())
+                });
+                // Live broadcasts report a length of zero until they end.
+                let duration = video
+                    .content_details?
+                    .duration
+                    .as_deref()
+                    .and_then(metadata_utils::parse_iso8601_duration)
+                    .filter(|_| !is_live);
+                Some((video.id?, (duration, is_live)))
+            }));
+        }
+
+        for track in tracks.iter_mut().filter(|t| t.duration.is_none()) {
+            if let Some((duration, is_live)) = details.get(&track.id) {
+                track.duration = *duration;
+                track.is_live = *is_live;
+            }
+        }
+    }
+
+    /// Fetches a related video for radio mode, preferring tracks whose IDs are not in
+    /// `recently_played`.
     /// Note: YouTube officially deprecated the `relatedToVideoId` search parameter, so we use a
     /// workaround.
-    #[instrument(skip(self))]
-    pub async fn get_related_video(&self, url: &str) -> Result<VideoMetadata, YoutubeError> {
+    #[instrument(skip(self, recently_played))]
+    pub async fn get_related_video(
+        &self,
+        seed: &Url,
+        recently_played: &[String],
+    ) -> Result<Track, YoutubeError> {
         use rand::seq::SliceRandom;
-        const N_ITEMS: u32 = 10;
-
         trace!("Requested related video for radio mode.");
-        let parsed_url = url::Url::parse(url).map_err(|e| {
-            trace!(err=%e, "Could not parse seed URL.");
-            YoutubeError::Url
-        })?;
-
-        let seed_id = Self::extract_video_id(&parsed_url).ok_or_else(|| {
+        let seed_id = Self::extract_video_id(seed).ok_or_else(|| {
             trace!("Could not extract video ID from seed URL.");
             YoutubeError::Url
         })?;
@@ -315,10 +506,16 @@ impl YoutubeClient {
         let seed_metadata = self.get_video_metadata(&seed_id).await?;
 
         let query = format!("{} playlist", seed_metadata.title);
-        let playlist_mix = self.search_playlist(&query, N_ITEMS).await.map_err(|e| {
-            error!(err=%e, "Radio mode failed to find a valid anchor playlist.");
-            e
-        })?;
+        let anchor = self
+            .search_playlists(&query, 1)
+            .await
+            .map_err(|e| {
+                error!(err=%e, "Radio mode failed to find a valid anchor playlist.");
+                e
+            })?
+            .swap_remove(0);
+        // Going through the cache lets later radio picks from the same anchor skip the API.
+        let playlist_mix = self.get_playlist_metadata(&anchor.id).await?;
 
         trace!(playlist=%playlist_mix, "Retrieved a playlist to pull a similar track from.");
 
@@ -326,16 +523,21 @@ impl YoutubeClient {
         let mut indices: Vec<usize> = (0..playlist_mix.items.len()).collect();
         indices.shuffle(&mut rng);
 
-        let related_track = indices
+        let candidates = indices
             .iter()
-            .find_map(|&i| {
-                let item = playlist_mix.items.get(i)?;
-                if !item.url.contains(&seed_id) {
-                    Some(item.clone())
-                } else {
-                    None
-                }
+            .filter_map(|&i| playlist_mix.items.get(i))
+            .filter(|item| item.id != seed_id)
+            .collect::<Vec<_>>();
+
+        // Fall back to a recently played track rather than stopping the radio altogether.
+        let related_track = candidates
+            .iter()
+            .find(|item| !recently_played.contains(&item.id))
+            .or_else(|| {
+                trace!("Every candidate was played recently.");
+                candidates.first()
             })
+            .map(|item| (*item).clone())
             .ok_or_else(|| {
                 // What are the chances?
                 warn!("Playlist search succeeded, but all tracks were duplicates of the seed.");
@@ -350,7 +552,7 @@ impl YoutubeClient {
     }
 
     /// Robustly extracts a YouTube video ID from various URL formats.
-    fn extract_video_id(url: &url::Url) -> Option<String> {
+    pub fn extract_video_id(url: &Url) -> Option<String> {
         let domain = url.domain().unwrap_or("");
 
         if domain == "youtu.be" {
@@ -385,7 +587,7 @@ impl YoutubeClient {
     }
 
     /// Extracts a playlist ID, supports Mixes and Albums.
-    fn extract_playlist_id(url: &url::Url) -> Option<String> {
+    fn extract_playlist_id(url: &Url) -> Option<String> {
         let domain = url.domain().unwrap_or("");
 
         // Playlists are almost exclusively on the main domain or subdomains
@@ -398,3 +600,57 @@ impl YoutubeClient {
         }
     }
 }
+
+#[async_trait]
+impl MetadataProvider for YoutubeClient {
+    fn supports_url(&self, url: &Url) -> bool {
+        Self::extract_video_id(url).is_some() || Self::extract_playlist_id(url).is_some()
+    }
+
+    #[instrument(skip(self))]
+    async fn resolve_url(&self, url: &Url) -> Result<QueueElement, MetadataError> {
+        // Attempt to extract a video ID first
+        if let Some(id) = Self::extract_video_id(url) {
+            trace!(video_id=%id, "URL designated as video.");
+            let metadata = self.get_video_metadata(&id).await?;
+            trace!(metadata=%metadata, "Video metadata retrieved.");
+            return Ok(QueueElement::Track(metadata));
+        }
+
+        // Fallback to attempting to extract a playlist ID
+        if let Some(id) = Self::extract_playlist_id(url) {
+            trace!(playlist_id=%id, "URL designated as playlist.");
+            let metadata = self.get_playlist_metadata(&id).await?;
+            trace!(metadata=%metadata, "Playlist metadata retrieved.");
+            return Ok(QueueElement::Playlist(metadata));
+        }
+
+        // If neither worked, the URL format is unsupported
+        trace!(?url, "Reporting URL as error.");
+        Err(YoutubeError::Url.into())
+    }
+
+    async fn search_tracks(
+        &self,
+        query: &str,
+        n_results: u32,
+    ) -> Result<Vec<Track>, MetadataError> {
+        Ok(self.search_videos(query, n_results).await?)
+    }
+
+    async fn search_playlists(
+        &self,
+        query: &str,
+        n_results: u32,
+    ) -> Result<Vec<Playlist>, MetadataError> {
+        Ok(YoutubeClient::search_playlists(self, query, n_results).await?)
+    }
+
+    async fn related_track(
+        &self,
+        seed: &Url,
+        recently_played: &[String],
+    ) -> Result<Track, MetadataError> {
+        Ok(self.get_related_video(seed, recently_played).await?)
+    }
+}
@@ -0,0 +1,468 @@
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, SystemTime},
+};
+use tracing::{instrument, trace};
+
+use crate::{
+    metrics::Metric,
+    models::{Playlist, QueueElement, Track},
+};
+
+/// How long video metadata is reused. Titles and lengths rarely change once published.
+const VIDEO_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long the metadata of a live broadcast is reused, as it stops being live once it ends.
+const LIVE_VIDEO_TTL: Duration = Duration::from_secs(60);
+
+/// How long playlists are reused, since their owners add and remove items.
+const PLAYLIST_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long search results are reused.
+const SEARCH_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long the videos YouTube mixes with a video are reused for radio mode.
+const MIX_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Most entries kept per kind of lookup.
+const MAX_ENTRIES: usize = 5000;
+
+#[derive(thiserror::Error, Debug)]
+pub enum CacheError {
+    #[error("Failed to access metadata cache file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to (de)serialize metadata cache: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry<V> {
+    value: V,
+    expires_at: SystemTime,
+}
+
+impl<V> Entry<V> {
+    fn is_fresh(&self) -> bool {
+        self.expires_at > SystemTime::now()
+    }
+}
+
+/// Lookups under way, keyed like the entries. Identical lookups wait on each other's lock.
+type InFlight = Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>;
+
+/// A share in a lookup under way. The lookup is forgotten once its last share is dropped, even
+/// when the lookup was cancelled halfway.
+struct Flight<'a> {
+    in_flight: &'a InFlight,
+    key: &'a str,
+    lock: Option<Arc<tokio::sync::Mutex<()>>>,
+}
+
+impl<'a> Flight<'a> {
+    fn join(in_flight: &'a InFlight, key: &'a str) -> Self {
+        let lock = lock(in_flight).entry(key.to_string()).or_default().clone();
+        Self {
+            in_flight,
+            key,
+            lock: Some(lock),
+        }
+    }
+
+    async fn turn(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.lock
+            .as_ref()
+            .expect("flights hold their lock until dropped")
+            .lock()
+            .await
+    }
+}
+
+impl Drop for Flight<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = lock(self.in_flight);
+        let Some(flight) = self.lock.take() else {
+            return;
+        };
+
+        // Shares are only taken and given up under the map's lock, so the count is settled.
+        let is_last_share = in_flight
+            .get(self.key)
+            .is_some_and(|entry| Arc::ptr_eq(entry, &flight) && Arc::strong_count(&flight) == 2);
+        if is_last_share {
+            in_flight.remove(self.key);
+        }
+        drop(flight);
+    }
+}
+
+/// Cached results of one kind of lookup, keyed by ID or query.
+#[derive(Debug)]
+struct CacheTable<V> {
+    /// Labels the table's metrics.
+    kind: &'static str,
+    ttl: fn(&V) -> Duration,
+    entries: Mutex<HashMap<String, Entry<V>>>,
+    /// Lookups under way, which identical lookups wait on instead of repeating them.
+    in_flight: InFlight,
+}
+
+impl<V: Clone> CacheTable<V> {
+    fn new(kind: &'static str, ttl: fn(&V) -> Duration) -> Self {
+        Self {
+            kind,
+            ttl,
+            entries: Mutex::default(),
+            in_flight: Mutex::default(),
+        }
+    }
+
+    fn fresh(&self, key: &str) -> Option<V> {
+        lock(&self.entries)
+            .get(key)
+            .filter(|entry| entry.is_fresh())
+            .map(|entry| entry.value.clone())
+    }
+
+    fn insert(&self, key: &str, value: V) {
+        let expires_at = SystemTime::now() + (self.ttl)(&value);
+        let mut entries = lock(&self.entries);
+
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, entry| entry.is_fresh());
+        }
+        if entries.len() >= MAX_ENTRIES
+            && let Some(soonest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .map(|(key, _)| key.clone())
+        {
+            entries.remove(&soonest);
+        }
+
+        entries.insert(key.to_string(), Entry { value, expires_at });
+    }
+
+    /// The cached value for `key`, or the result of `fetch` once no identical lookup is under
+    /// way. Failures aren't cached.
+    async fn get_or_fetch<E>(
+        &self,
+        key: &str,
+        fetch: impl Future<Output = Result<V, E>>,
+    ) -> Result<V, E> {
+        if let Some(value) = self.fresh(key) {
+            self.record("hit");
+            return Ok(value);
+        }
+
+        let flight = Flight::join(&self.in_flight, key);
+        let _turn = flight.turn().await;
+
+        // The lookup we waited on may have filled the entry in.
+        if let Some(value) = self.fresh(key) {
+            self.record("coalesced");
+            return Ok(value);
+        }
+
+        self.record("miss");
+        let result = fetch.await;
+        if let Ok(value) = &result {
+            self.insert(key, value.clone());
+        }
+        result
+    }
+
+    fn record(&self, result: &'static str) {
+        trace!(kind = self.kind, result, "Metadata cache lookup.");
+        counter!(Metric::MetadataCacheTotal.as_ref(), "kind" => self.kind, "result" => result)
+            .increment(1);
+    }
+
+    fn snapshot(&self) -> HashMap<String, Entry<V>> {
+        lock(&self.entries)
+            .iter()
+            .filter(|(_, entry)| entry.is_fresh())
+            .map(|(key, entry)| (key.clone(), entry.clone()))
+            .collect()
+    }
+
+    fn restore(&self, entries: HashMap<String, Entry<V>>) {
+        lock(&self.entries).extend(entries.into_iter().filter(|(_, entry)| entry.is_fresh()));
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[derive(Debug)]
+struct Tables {
+    videos: CacheTable<Track>,
+    playlists: CacheTable<Playlist>,
+    video_searches: CacheTable<Vec<Track>>,
+    playlist_searches: CacheTable<Vec<Playlist>>,
+    /// Links resolved with yt-dlp, keyed by URL.
+    links: CacheTable<QueueElement>,
+    /// YouTube mixes read with yt-dlp, keyed by the ID of the video they were made for.
+    mixes: CacheTable<Vec<Track>>,
+}
+
+/// The cache as written to disk.
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    #[serde(default)]
+    videos: HashMap<String, Entry<Track>>,
+    #[serde(default)]
+    playlists: HashMap<String, Entry<Playlist>>,
+    #[serde(default)]
+    video_searches: HashMap<String, Entry<Vec<Track>>>,
+    #[serde(default)]
+    playlist_searches: HashMap<String, Entry<Vec<Playlist>>>,
+    #[serde(default)]
+    links: HashMap<String, Entry<QueueElement>>,
+    #[serde(default)]
+    mixes: HashMap<String, Entry<Vec<Track>>>,
+}
+
+/// Reuses lookups for a while to spare the YouTube API quota and yt-dlp runs, keyed by video and
+/// playlist ID, link and search query. Clones share the same cache.
+#[derive(Debug, Clone)]
+pub struct MetadataCache {
+    tables: Arc<Tables>,
+}
+
+impl Default for MetadataCache {
+    fn default() -> Self {
+        Self {
+            tables: Arc::new(Tables {
+                videos: CacheTable::new("video", |track: &Track| {
+                    if track.is_live {
+                        LIVE_VIDEO_TTL
+                    } else {
+                        VIDEO_TTL
+                    }
+                }),
+                playlists: CacheTable::new("playlist", |_| PLAYLIST_TTL),
+                video_searches: CacheTable::new("video_search", |_| SEARCH_TTL),
+                playlist_searches: CacheTable::new("playlist_search", |_| SEARCH_TTL),
+                links: CacheTable::new("link", |element: &QueueElement| match element {
+                    QueueElement::Track(track) if track.is_live => LIVE_VIDEO_TTL,
+                    QueueElement::Track(_) => VIDEO_TTL,
+                    QueueElement::Playlist(_) => PLAYLIST_TTL,
+                }),
+                mixes: CacheTable::new("mix", |_| MIX_TTL),
+            }),
+        }
+    }
+}
+
+/// Identical queries differ only in case and surrounding whitespace.
+fn search_key(query: &str, n_results: u32) -> String {
+    format!("{n_results}:{}", query.trim().to_lowercase())
+}
+
+impl MetadataCache {
+    pub async fn video<E>(
+        &self,
+        video_id: &str,
+        fetch: impl Future<Output = Result<Track, E>>,
+    ) -> Result<Track, E> {
+        self.tables.videos.get_or_fetch(video_id, fetch).await
+    }
+
+    pub async fn playlist<E>(
+        &self,
+        playlist_id: &str,
+        fetch: impl Future<Output = Result<Playlist, E>>,
+    ) -> Result<Playlist, E> {
+        self.tables.playlists.get_or_fetch(playlist_id, fetch).await
+    }
+
+    pub async fn video_search<E>(
+        &self,
+        query: &str,
+        n_results: u32,
+        fetch: impl Future<Output = Result<Vec<Track>, E>>,
+    ) -> Result<Vec<Track>, E> {
+        let key = search_key(query, n_results);
+        self.tables.video_searches.get_or_fetch(&key, fetch).await
+    }
+
+    pub async fn playlist_search<E>(
+        &self,
+        query: &str,
+        n_results: u32,
+        fetch: impl Future<Output = Result<Vec<Playlist>, E>>,
+    ) -> Result<Vec<Playlist>, E> {
+        let key = search_key(query, n_results);
+        self.tables
+            .playlist_searches
+            .get_or_fetch(&key, fetch)
+            .await
+    }
+
+    pub async fn link<E>(
+        &self,
+        url: &str,
+        fetch: impl Future<Output = Result<QueueElement, E>>,
+    ) -> Result<QueueElement, E> {
+        self.tables.links.get_or_fetch(url, fetch).await
+    }
+
+    pub async fn mix<E>(
+        &self,
+        video_id: &str,
+        fetch: impl Future<Output = Result<Vec<Track>, E>>,
+    ) -> Result<Vec<Track>, E> {
+        self.tables.mixes.get_or_fetch(video_id, fetch).await
+    }
+
+    /// Reads a cache written by `save`, leaving out expired entries. Starts empty when there is
+    /// no file yet.
+    #[instrument]
+    pub fn load(path: &Path) -> Result<Self, CacheError> {
+        let cache = Self::default();
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                trace!("No metadata cache file found.");
+                return Ok(cache);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let file: CacheFile = serde_json::from_str(&contents)?;
+        cache.tables.videos.restore(file.videos);
+        cache.tables.playlists.restore(file.playlists);
+        cache.tables.video_searches.restore(file.video_searches);
+        cache
+            .tables
+            .playlist_searches
+            .restore(file.playlist_searches);
+        cache.tables.links.restore(file.links);
+        cache.tables.mixes.restore(file.mixes);
+        Ok(cache)
+    }
+
+    /// Writes the entries that haven't expired to `path`, replacing any previous file.
+    #[instrument(skip(self))]
+    pub fn save(&self, path: &Path) -> Result<(), CacheError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = CacheFile {
+            videos: self.tables.videos.snapshot(),
+            playlists: self.tables.playlists.snapshot(),
+            video_searches: self.tables.video_searches.snapshot(),
+            playlist_searches: self.tables.playlist_searches.snapshot(),
+            links: self.tables.links.snapshot(),
+            mixes: self.tables.mixes.snapshot(),
+        };
+
+        serde_json::to_writer(fs::File::create(path)?, &file)?;
+        trace!(
+            n_videos = file.videos.len(),
+            "Metadata cache written to disk."
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn table() -> Arc<CacheTable<u32>> {
+        Arc::new(CacheTable::new("test", |_| Duration::from_secs(60)))
+    }
+
+    #[test]
+    fn search_key_ignores_case_and_surrounding_whitespace() {
+        assert_eq!(search_key("  Some Song ", 5), search_key("some song", 5));
+        assert_ne!(search_key("some song", 5), search_key("some song", 1));
+        assert_ne!(search_key("some  song", 5), search_key("some song", 5));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_lookups_fetch_once() {
+        let table = table();
+        let fetches = Arc::new(AtomicUsize::new(0));
+
+        let lookups = (0..16)
+            .map(|_| {
+                let (table, fetches) = (table.clone(), fetches.clone());
+                tokio::spawn(async move {
+                    table
+                        .get_or_fetch("key", async {
+                            fetches.fetch_add(1, Ordering::SeqCst);
+                            tokio::task::yield_now().await;
+                            Ok::<_, ()>(7)
+                        })
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for lookup in lookups {
+            assert_eq!(lookup.await.unwrap(), Ok(7));
+        }
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert!(lock(&table.in_flight).is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_and_cancelled_lookups_are_forgotten() {
+        let table = table();
+
+        assert_eq!(table.get_or_fetch("key", async { Err(()) }).await, Err(()));
+        assert!(lock(&table.in_flight).is_empty());
+
+        let cancelled = tokio::time::timeout(
+            Duration::from_millis(10),
+            table.get_or_fetch("key", std::future::pending::<Result<u32, ()>>()),
+        )
+        .await;
+        assert!(cancelled.is_err());
+        assert!(lock(&table.in_flight).is_empty());
+
+        assert_eq!(
+            table.get_or_fetch("key", async { Ok::<_, ()>(3) }).await,
+            Ok(3)
+        );
+    }
+
+    #[tokio::test]
+    async fn ytdlp_lookups_survive_a_restart() {
+        let dir = std::env::temp_dir().join(format!("luna-cache-{}", std::process::id()));
+        let path = dir.join("metadata_cache.json");
+
+        let cache = MetadataCache::default();
+        let link = QueueElement::Track(Track::test("a"));
+        cache
+            .link("https://example.com/a", async { Ok::<_, ()>(link) })
+            .await
+            .unwrap();
+        cache
+            .mix("seed", async { Ok::<_, ()>(vec![Track::test("b")]) })
+            .await
+            .unwrap();
+        cache.save(&path).unwrap();
+
+        let restored = MetadataCache::load(&path).unwrap();
+        let link = restored
+            .link("https://example.com/a", async { Err(()) })
+            .await
+            .unwrap();
+        assert!(matches!(link, QueueElement::Track(track) if track.id == "a"));
+
+        let mix = restored.mix("seed", async { Err(()) }).await.unwrap();
+        assert_eq!(mix[0].id, "b");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
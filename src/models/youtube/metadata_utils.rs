@@ -1,14 +1,19 @@
 use crate::models::{
-    PlaylistMetadata, VideoMetadata,
+    Playlist, StreamLocator, Track, TrackSource,
     youtube::{PLAYLIST_URI, SINGLE_URI},
 };
 use google_youtube3::api::ThumbnailDetails;
 use html_escape::decode_html_entities;
-use std::collections::VecDeque;
+use std::{collections::VecDeque, time::Duration};
 
-/// Safely evaluates if the live broadcast state implies an active live stream.
+/// Safely evaluates if the live broadcast state implies an active live stream or premiere.
 pub fn is_live_stream(live_content: Option<&String>) -> bool {
-    live_content.is_some_and(|status| status != "none")
+    live_content.is_some_and(|status| status == "live")
+}
+
+/// Safely evaluates if the live broadcast state implies a stream or premiere that has not started.
+pub fn is_upcoming(live_content: Option<&String>) -> bool {
+    live_content.is_some_and(|status| status == "upcoming")
 }
 
 /// Cascades down thumbnail options from highest resolution to lowest fallback.
@@ -23,39 +28,115 @@ pub fn extract_thumbnail(details: Option<&ThumbnailDetails>) -> Option<&str> {
         .and_then(|t| t.url.as_deref())
 }
 
-/// Houses the shared core assembly logic for constructing VideoMetadata structures.
+/// Houses the shared core assembly logic for constructing Track structures.
 pub fn assemble_metadata(
     id: Option<&str>,
     title: Option<&str>,
     channel: Option<&str>,
     thumbnail_url: Option<&str>,
-) -> Option<VideoMetadata> {
+    duration: Option<Duration>,
+    is_live: bool,
+) -> Option<Track> {
     let (id, title, channel, thumb) = (id?, title?, channel?, thumbnail_url?);
 
-    Some(VideoMetadata {
+    Some(Track {
         id: id.to_string(),
         title: decode_html_entities(title).to_string(),
-        channel: decode_html_entities(channel).to_string(),
+        artist: decode_html_entities(channel).to_string(),
         url: format!("{SINGLE_URI}{id}"),
-        thumbnail_url: thumb.to_string(),
+        artwork_url: Some(thumb.to_string()),
+        // Live broadcasts report a length of zero until they end.
+        duration: duration.filter(|_| !is_live),
+        is_live,
+        source: TrackSource::Youtube,
+        locator: StreamLocator::Extractor,
+        requested_by: None,
     })
 }
 
-/// Core assembly constructor for PlaylistMetadata structures.
+/// Parses the ISO-8601 durations YouTube reports, such as `PT1H2M3S` or `P1DT2H`.
+pub fn parse_iso8601_duration(value: &str) -> Option<Duration> {
+    let value = value.strip_prefix('P')?;
+    let (date, time) = value.split_once('T').unwrap_or((value, ""));
+
+    let mut seconds = 0.0;
+    for (part, units) in [
+        (date, [('W', 604_800.0), ('D', 86_400.0)].as_slice()),
+        (time, &[('H', 3_600.0), ('M', 60.0), ('S', 1.0)]),
+    ] {
+        let mut rest = part;
+        for (unit, scale) in units {
+            if let Some((amount, tail)) = rest.split_once(*unit) {
+                let amount = amount.parse::<f64>().ok()?;
+                if !amount.is_finite() || amount < 0.0 {
+                    return None;
+                }
+                seconds += amount * scale;
+                rest = tail;
+            }
+        }
+
+        // Anything left over is a unit we do not understand.
+        if !rest.is_empty() {
+            return None;
+        }
+    }
+
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// Core assembly constructor for Playlist structures.
 pub fn assemble_playlist_metadata(
     id: Option<&str>,
     title: Option<&str>,
     channel: Option<&str>,
     thumbnail_url: Option<&str>,
-) -> Option<PlaylistMetadata> {
+) -> Option<Playlist> {
     let (id, title, channel, thumb) = (id?, title?, channel?, thumbnail_url?);
 
-    Some(PlaylistMetadata {
+    Some(Playlist {
         id: id.to_string(),
         title: decode_html_entities(title).to_string(),
-        channel: decode_html_entities(channel).to_string(),
+        artist: decode_html_entities(channel).to_string(),
         url: format!("{PLAYLIST_URI}{id}"),
-        thumbnail_url: thumb.to_string(),
+        artwork_url: Some(thumb.to_string()),
+        source: TrackSource::Youtube,
         items: VecDeque::new(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_iso8601_duration_reads_youtube_lengths() {
+        assert_eq!(
+            parse_iso8601_duration("PT1H2M3S"),
+            Some(Duration::from_secs(3723))
+        );
+        assert_eq!(
+            parse_iso8601_duration("PT4M"),
+            Some(Duration::from_secs(240))
+        );
+        assert_eq!(
+            parse_iso8601_duration("P1DT2H"),
+            Some(Duration::from_secs(93_600))
+        );
+        assert_eq!(
+            parse_iso8601_duration("PT1.5S"),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(parse_iso8601_duration("P0D"), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn parse_iso8601_duration_rejects_malformed_values() {
+        assert_eq!(parse_iso8601_duration("1H"), None);
+        assert_eq!(parse_iso8601_duration("PT5X"), None);
+        assert_eq!(parse_iso8601_duration("PT3S2M"), None);
+        assert_eq!(parse_iso8601_duration("PT-5S"), None);
+        assert_eq!(parse_iso8601_duration("PTinfS"), None);
+        assert_eq!(parse_iso8601_duration("PTNaNS"), None);
+    }
+}
@@ -1,35 +1,13 @@
-use super::{YoutubeError, metadata_utils, video_metadata::VideoMetadata};
-use google_youtube3::api::{Playlist, SearchResult};
-use std::{collections::VecDeque, fmt::Display};
+use super::{YoutubeError, metadata_utils};
+use crate::models::Playlist;
+use google_youtube3::api::{Playlist as YoutubePlaylist, SearchResult};
 use tracing::{error, instrument};
 
-#[derive(Debug, Clone)]
-pub struct PlaylistMetadata {
-    pub id: String,
-    pub title: String,
-    pub channel: String,
-    pub url: String,
-    pub thumbnail_url: String,
-    pub items: VecDeque<VideoMetadata>,
-}
-
-impl Display for PlaylistMetadata {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "Playlist: {} - {} with {} remaining tracks",
-            self.title,
-            self.channel,
-            self.items.len()
-        )
-    }
-}
-
-impl TryFrom<&Playlist> for PlaylistMetadata {
+impl TryFrom<&YoutubePlaylist> for Playlist {
     type Error = YoutubeError;
 
     #[instrument(skip_all)]
-    fn try_from(value: &Playlist) -> Result<Self, Self::Error> {
+    fn try_from(value: &YoutubePlaylist) -> Result<Self, Self::Error> {
         let snippet = value.snippet.as_ref().ok_or(YoutubeError::Conversion)?;
 
         metadata_utils::assemble_playlist_metadata(
@@ -39,13 +17,13 @@ impl TryFrom<&Playlist> for PlaylistMetadata {
             metadata_utils::extract_thumbnail(snippet.thumbnails.as_ref()),
         )
         .ok_or_else(|| {
-            error!("Playlist to PlaylistMetadata conversion failed.");
+            error!("YouTube playlist to Playlist conversion failed.");
             YoutubeError::Conversion
         })
     }
 }
 
-impl TryFrom<&SearchResult> for PlaylistMetadata {
+impl TryFrom<&SearchResult> for Playlist {
     type Error = YoutubeError;
 
     #[instrument(skip_all)]
@@ -61,7 +39,7 @@ impl TryFrom<&SearchResult> for PlaylistMetadata {
             metadata_utils::extract_thumbnail(snippet.thumbnails.as_ref()),
         )
         .ok_or_else(|| {
-            error!("SearchResult to PlaylistMetadata conversion failed.");
+            error!("SearchResult to Playlist conversion failed.");
             YoutubeError::Conversion
         })
     }
@@ -0,0 +1,158 @@
+use metrics::counter;
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+use super::YoutubeError;
+use crate::metrics::Metric;
+
+/// How long the API is left alone after it turned requests away, before it is tried again.
+const API_BACKOFF: Duration = Duration::from_secs(15 * 60);
+
+/// Error reasons the API gives once the project's quota or rate limit is used up.
+const QUOTA_REASONS: [&str; 4] = [
+    "quotaExceeded",
+    "dailyLimitExceeded",
+    "rateLimitExceeded",
+    "userRateLimitExceeded",
+];
+
+/// Calls to the YouTube Data API, each billed against the daily quota.
+#[derive(Debug, Clone, Copy, strum::IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum ApiCall {
+    Search,
+    Videos,
+    Playlists,
+    PlaylistItems,
+}
+
+impl ApiCall {
+    /// Quota units the call costs, as published by Google. Failed calls are billed too.
+    fn cost(self) -> u64 {
+        match self {
+            ApiCall::Search => 100,
+            ApiCall::Videos | ApiCall::Playlists | ApiCall::PlaylistItems => 1,
+        }
+    }
+
+    /// Counts the units the call is about to spend.
+    pub fn spend(self) {
+        let call: &'static str = self.into();
+        counter!(Metric::YoutubeQuotaUnitsTotal.as_ref(), "call" => call).increment(self.cost());
+    }
+}
+
+/// Tells quota and permission errors apart from the other errors the API returns.
+pub fn classify(err: google_youtube3::Error) -> YoutubeError {
+    let err = match &err {
+        google_youtube3::Error::BadRequest(body) => {
+            let error = &body["error"];
+            let has_quota_reason = error["errors"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|e| e["reason"].as_str())
+                .any(|reason| QUOTA_REASONS.contains(&reason));
+
+            if has_quota_reason {
+                YoutubeError::QuotaExceeded
+            } else if error["code"].as_u64() == Some(403) {
+                let message = error["message"].as_str().unwrap_or_default();
+                YoutubeError::Forbidden(message.to_string())
+            } else {
+                YoutubeError::Api(err)
+            }
+        }
+        google_youtube3::Error::Failure(response) if response.status().as_u16() == 403 => {
+            YoutubeError::Forbidden(response.status().to_string())
+        }
+        _ => YoutubeError::Api(err),
+    };
+
+    let kind = match err {
+        YoutubeError::QuotaExceeded => "quota",
+        YoutubeError::Forbidden(_) => "forbidden",
+        _ => "other",
+    };
+    counter!(Metric::YoutubeApiErrorsTotal.as_ref(), "kind" => kind).increment(1);
+    err
+}
+
+/// Whether the API should be skipped in favour of the fallback, after it turned requests away.
+#[derive(Debug, Clone, Default)]
+pub struct ApiStatus {
+    paused_until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl ApiStatus {
+    pub fn is_paused(&self) -> bool {
+        self.paused_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Skips the API for a while after `err` showed it is unavailable.
+    pub fn pause(&self, err: &YoutubeError) {
+        warn!(err = %err, backoff = ?API_BACKOFF, "YouTube API unavailable, falling back to yt-dlp.");
+        *self
+            .paused_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Instant::now() + API_BACKOFF);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn bad_request(code: u64, reason: &str) -> google_youtube3::Error {
+        google_youtube3::Error::BadRequest(json!({
+            "error": {
+                "code": code,
+                "message": "The request was turned away.",
+                "errors": [{ "reason": reason }],
+            }
+        }))
+    }
+
+    #[test]
+    fn classify_recognises_quota_errors() {
+        for reason in QUOTA_REASONS {
+            assert!(matches!(
+                classify(bad_request(403, reason)),
+                YoutubeError::QuotaExceeded
+            ));
+        }
+    }
+
+    #[test]
+    fn classify_tells_forbidden_apart_from_other_errors() {
+        assert!(matches!(
+            classify(bad_request(403, "accessNotConfigured")),
+            YoutubeError::Forbidden(msg) if msg == "The request was turned away."
+        ));
+        assert!(matches!(
+            classify(bad_request(400, "invalidParameter")),
+            YoutubeError::Api(_)
+        ));
+        assert!(matches!(
+            classify(google_youtube3::Error::Cancelled),
+            YoutubeError::Api(_)
+        ));
+    }
+
+    #[test]
+    fn api_status_pauses_after_errors() {
+        let status = ApiStatus::default();
+        assert!(!status.is_paused());
+
+        status.pause(&YoutubeError::QuotaExceeded);
+        assert!(status.is_paused());
+        assert!(status.clone().is_paused());
+    }
+}
@@ -1,34 +1,19 @@
 use super::{YoutubeError, metadata_utils};
+use crate::models::Track;
 use google_youtube3::api::{PlaylistItem, SearchResult, Video};
-use std::fmt::Display;
 use tracing::{error, instrument, trace};
 
-#[derive(Debug, Clone)]
-pub struct VideoMetadata {
-    pub id: String,
-    pub title: String,
-    pub channel: String,
-    pub url: String,
-    pub thumbnail_url: String,
-}
-
-impl Display for VideoMetadata {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Track: {} - {}", self.title, self.channel)
-    }
-}
-
-impl TryFrom<&Video> for VideoMetadata {
+impl TryFrom<&Video> for Track {
     type Error = YoutubeError;
 
     #[instrument(skip_all)]
     fn try_from(value: &Video) -> Result<Self, Self::Error> {
         let snippet = value.snippet.as_ref().ok_or(YoutubeError::Conversion)?;
 
-        if metadata_utils::is_live_stream(snippet.live_broadcast_content.as_ref()) {
-            trace!("Live stream detected.");
+        if metadata_utils::is_upcoming(snippet.live_broadcast_content.as_ref()) {
+            trace!("Upcoming live stream detected.");
             return Err(YoutubeError::Unsupported(
-                "Live streams are not supported yet.".to_string(),
+                "This live stream or premiere has not started yet.".to_string(),
             ));
         }
 
@@ -37,25 +22,31 @@ impl TryFrom<&Video> for VideoMetadata {
             snippet.title.as_deref(),
             snippet.channel_title.as_deref(),
             metadata_utils::extract_thumbnail(snippet.thumbnails.as_ref()),
+            value
+                .content_details
+                .as_ref()
+                .and_then(|details| details.duration.as_deref())
+                .and_then(metadata_utils::parse_iso8601_duration),
+            metadata_utils::is_live_stream(snippet.live_broadcast_content.as_ref()),
         )
         .ok_or_else(|| {
-            error!("Video to VideoMetadata conversion failed.");
+            error!("Video to Track conversion failed.");
             YoutubeError::Conversion
         })
     }
 }
 
-impl TryFrom<&SearchResult> for VideoMetadata {
+impl TryFrom<&SearchResult> for Track {
     type Error = YoutubeError;
 
     #[instrument(skip_all)]
     fn try_from(value: &SearchResult) -> Result<Self, Self::Error> {
         let snippet = value.snippet.as_ref().ok_or(YoutubeError::Conversion)?;
 
-        if metadata_utils::is_live_stream(snippet.live_broadcast_content.as_ref()) {
-            trace!("Live stream detected.");
+        if metadata_utils::is_upcoming(snippet.live_broadcast_content.as_ref()) {
+            trace!("Upcoming live stream detected.");
             return Err(YoutubeError::Unsupported(
-                "Live streams are not supported yet.".to_string(),
+                "This live stream or premiere has not started yet.".to_string(),
             ));
         }
 
@@ -66,15 +57,17 @@ impl TryFrom<&SearchResult> for VideoMetadata {
             snippet.title.as_deref(),
             snippet.channel_title.as_deref(),
             metadata_utils::extract_thumbnail(snippet.thumbnails.as_ref()),
+            None,
+            metadata_utils::is_live_stream(snippet.live_broadcast_content.as_ref()),
         )
         .ok_or_else(|| {
-            error!("SearchResult to VideoMetadata conversion failed.");
+            error!("SearchResult to Track conversion failed.");
             YoutubeError::Conversion
         })
     }
 }
 
-impl TryFrom<&PlaylistItem> for VideoMetadata {
+impl TryFrom<&PlaylistItem> for Track {
     type Error = YoutubeError;
 
     #[instrument(skip_all)]
@@ -91,9 +84,12 @@ impl TryFrom<&PlaylistItem> for VideoMetadata {
             snippet.title.as_deref(),
             snippet.video_owner_channel_title.as_deref(),
             metadata_utils::extract_thumbnail(snippet.thumbnails.as_ref()),
+            None,
+            // Filled in along with the duration.
+            false,
         )
         .ok_or_else(|| {
-            error!("PlaylistItem to VideoMetadata conversion failed.");
+            error!("PlaylistItem to Track conversion failed.");
             YoutubeError::Conversion
         })
     }
@@ -0,0 +1,549 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::{
+    collections::VecDeque,
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
+    process::Stdio,
+    time::Duration,
+};
+use tokio::process::Command;
+use tracing::{instrument, trace, warn};
+use url::Url;
+
+use super::{
+    AddressError, MetadataCache, MetadataError, MetadataProvider, Playlist, QueueElement,
+    StreamLocator, Track, TrackSource, UNKNOWN_ARTIST, YoutubeClient, public_address,
+};
+
+/// Extractors yt-dlp may use. The generic extractor would fetch any page it is handed, so only
+/// sites with an extractor of their own are played.
+pub const EXTRACTORS: &str = "default,-generic";
+
+/// Longest yt-dlp may take to extract a page before the request is given up on.
+const EXTRACTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Most entries read from a playlist, matching the cap on YouTube playlists.
+const PLAYLIST_CAP: u32 = 50;
+
+/// Longest track ID kept as is. Longer ones are hashed so they fit into component custom IDs.
+const MAX_ID_LENGTH: usize = 64;
+
+/// Named for entries that flat extraction returns without a title.
+const UNTITLED: &str = "Untitled";
+
+/// Where YouTube lists search results, and the filter that narrows them down to playlists.
+const YOUTUBE_RESULTS_URL: &str = "https://www.youtube.com/results";
+const PLAYLIST_SEARCH_FILTER: &str = "EgIQAw==";
+
+/// Where YouTube serves videos, along with the mix of related videos it puts together for them.
+const YOUTUBE_WATCH_URL: &str = "https://www.youtube.com/watch";
+
+/// Fragments of yt-dlp error messages that mean the content exists but is off limits.
+const UNAVAILABLE_MARKERS: [&str; 10] = [
+    "private",
+    "sign in",
+    "log in",
+    "login",
+    "members",
+    "not available",
+    "unavailable",
+    "removed",
+    "does not exist",
+    "404",
+];
+
+#[derive(thiserror::Error, Debug)]
+pub enum YtdlpError {
+    #[error("Failed to run yt-dlp: {0}")]
+    Spawn(#[from] io::Error),
+
+    #[error("yt-dlp did not finish in time.")]
+    Timeout,
+
+    #[error("yt-dlp does not support the URL.")]
+    Unsupported,
+
+    #[error("{0}")]
+    Address(#[from] AddressError),
+
+    #[error("The content is unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("The live stream or premiere has not started yet.")]
+    Upcoming,
+
+    #[error("The playlist has no entries.")]
+    Empty,
+
+    #[error("The search has no results.")]
+    NoResults,
+
+    #[error("yt-dlp failed: {0}")]
+    Extraction(String),
+
+    #[error("Failed to parse yt-dlp output: {0}")]
+    Parse(#[from] serde_json::Error),
+}
+
+impl YtdlpError {
+    /// Classifies the last error yt-dlp printed to stderr.
+    fn from_stderr(stderr: &str) -> Self {
+        let msg = stderr
+            .lines()
+            .rev()
+            .find_map(|line| line.strip_prefix("ERROR:"))
+            .unwrap_or(stderr)
+            .trim()
+            .to_string();
+        let lowercase = msg.to_lowercase();
+
+        if lowercase.contains("unsupported url") {
+            YtdlpError::Unsupported
+        } else if UNAVAILABLE_MARKERS
+            .iter()
+            .any(|marker| lowercase.contains(marker))
+        {
+            YtdlpError::Unavailable(msg)
+        } else {
+            YtdlpError::Extraction(msg)
+        }
+    }
+
+    /// What to tell members when the link itself is to blame.
+    pub fn user_message(&self) -> Option<String> {
+        let msg = match self {
+            YtdlpError::Unsupported => "I don't know how to play that link.",
+            YtdlpError::Unavailable(_) => "That link is private, removed or not available to me.",
+            YtdlpError::Upcoming => "This live stream or premiere has not started yet.",
+            YtdlpError::Empty => "That playlist has nothing I can play.",
+            YtdlpError::NoResults => "I couldn't find anything matching that.",
+            YtdlpError::Timeout => "That site took too long to respond, try again later.",
+            YtdlpError::Extraction(_) => "I couldn't find anything playable at that link.",
+            YtdlpError::Address(e) => return Some(e.to_string()),
+            YtdlpError::Spawn(_) | YtdlpError::Parse(_) => return None,
+        };
+        Some(msg.to_string())
+    }
+}
+
+/// The parts of yt-dlp's info JSON used to build tracks. Flat playlist entries carry only some.
+#[derive(Deserialize, Debug)]
+struct YtdlpInfo {
+    #[serde(rename = "_type")]
+    kind: Option<String>,
+    id: Option<String>,
+    title: Option<String>,
+    artist: Option<String>,
+    uploader: Option<String>,
+    channel: Option<String>,
+    webpage_url: Option<String>,
+    url: Option<String>,
+    thumbnail: Option<String>,
+    #[serde(default)]
+    thumbnails: Vec<YtdlpThumbnail>,
+    duration: Option<f64>,
+    is_live: Option<bool>,
+    live_status: Option<String>,
+    extractor_key: Option<String>,
+    ie_key: Option<String>,
+    #[serde(default)]
+    entries: Vec<Option<YtdlpInfo>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct YtdlpThumbnail {
+    url: String,
+}
+
+impl YtdlpInfo {
+    fn page_url(&self) -> Option<&str> {
+        self.webpage_url.as_deref().or(self.url.as_deref())
+    }
+
+    fn artist(&self) -> String {
+        self.artist
+            .as_ref()
+            .or(self.uploader.as_ref())
+            .or(self.channel.as_ref())
+            .cloned()
+            .unwrap_or_else(|| UNKNOWN_ARTIST.to_string())
+    }
+
+    /// The largest artwork, which yt-dlp lists last.
+    fn artwork_url(&self) -> Option<String> {
+        self.thumbnail
+            .clone()
+            .or_else(|| self.thumbnails.last().map(|t| t.url.clone()))
+    }
+
+    fn is_upcoming(&self) -> bool {
+        self.live_status.as_deref() == Some("is_upcoming")
+    }
+
+    /// The item's ID and source, naming the ID after `extractor` when the info doesn't say
+    /// which extractor it came from. YouTube items keep their own IDs, so they match the ones
+    /// the YouTube API hands out.
+    fn identity(&self, extractor: &str, fallback_id: &str) -> (String, TrackSource) {
+        let extractor = self
+            .extractor_key
+            .as_deref()
+            .or(self.ie_key.as_deref())
+            .unwrap_or(extractor);
+        let id = self.id.as_deref().unwrap_or(fallback_id);
+
+        if extractor.starts_with("Youtube") {
+            (id.to_string(), TrackSource::Youtube)
+        } else {
+            (track_id(extractor, id), TrackSource::Web)
+        }
+    }
+
+    fn into_track(self, extractor: &str) -> Option<Track> {
+        let url = self.page_url()?.to_string();
+        let (id, source) = self.identity(extractor, &url);
+        let is_live =
+            self.is_live.unwrap_or_default() || self.live_status.as_deref() == Some("is_live");
+
+        Some(Track {
+            id,
+            artist: self.artist(),
+            artwork_url: self.artwork_url(),
+            duration: self
+                .duration
+                .filter(|secs| !is_live && secs.is_finite() && *secs > 0.0)
+                .map(Duration::from_secs_f64),
+            is_live,
+            title: self.title.unwrap_or_else(|| UNTITLED.to_string()),
+            url,
+            source,
+            locator: StreamLocator::Extractor,
+            requested_by: None,
+        })
+    }
+
+    /// Builds a playlist without its entries.
+    fn to_playlist(&self, extractor: &str, fallback_url: &str) -> Playlist {
+        let url = self.page_url().unwrap_or(fallback_url);
+        let (id, source) = self.identity(extractor, url);
+
+        Playlist {
+            id,
+            title: self.title.clone().unwrap_or_else(|| UNTITLED.to_string()),
+            artist: self.artist(),
+            url: url.to_string(),
+            artwork_url: self.artwork_url(),
+            source,
+            items: VecDeque::new(),
+        }
+    }
+
+    /// Builds a track, or a playlist of the entries, from the info extracted for `target`.
+    fn into_element(self, target: &str) -> Result<QueueElement, YtdlpError> {
+        let extractor = self
+            .extractor_key
+            .clone()
+            .unwrap_or_else(|| "web".to_string());
+
+        if self.is_upcoming() {
+            return Err(YtdlpError::Upcoming);
+        }
+
+        if self.kind.as_deref() != Some("playlist") {
+            let track = self
+                .into_track(&extractor)
+                .ok_or_else(|| YtdlpError::Extraction("No URL in the extracted info.".into()))?;
+            trace!(metadata = %track, "Track extracted.");
+            return Ok(QueueElement::Track(track));
+        }
+
+        let mut playlist = self.to_playlist(&extractor, target);
+        playlist.items = self
+            .entries
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.into_track(&extractor))
+            .collect();
+
+        if playlist.items.is_empty() {
+            return Err(YtdlpError::Empty);
+        }
+
+        trace!(metadata = %playlist, "Playlist extracted.");
+        Ok(QueueElement::Playlist(playlist))
+    }
+}
+
+/// A track ID prefixed with the extractor, such as `soundcloud:123`, hashed when too long.
+fn track_id(extractor: &str, id: &str) -> String {
+    let id = format!("{}:{id}", extractor.to_lowercase());
+    if id.len() <= MAX_ID_LENGTH {
+        return id;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    id.hash(&mut hasher);
+    format!("web:{:016x}", hasher.finish())
+}
+
+/// Resolves links to any of the sites yt-dlp can extract from, such as SoundCloud, Bandcamp,
+/// Vimeo or Twitch. Meant to go last, after the providers for specific sites. Searches YouTube
+/// and finds related videos for radio mode when no YouTube API provider goes before it.
+#[derive(Debug, Clone, Default)]
+pub struct YtdlpProvider {
+    /// Reuses lookups made as a provider. Without one, every lookup runs yt-dlp.
+    cache: Option<MetadataCache>,
+}
+
+impl YtdlpProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A provider reusing its links, searches and mixes through `cache`.
+    pub fn with_cache(cache: MetadataCache) -> Self {
+        Self { cache: Some(cache) }
+    }
+
+    /// Runs yt-dlp on `target`, a URL or search, without resolving playlist entries and parses
+    /// the info it prints.
+    #[instrument(skip(self))]
+    async fn extract(&self, target: &str) -> Result<YtdlpInfo, YtdlpError> {
+        let output = Command::new("yt-dlp")
+            .args([
+                "--dump-single-json",
+                "--flat-playlist",
+                "--no-warnings",
+                "--use-extractors",
+                EXTRACTORS,
+                "--playlist-end",
+                &PLAYLIST_CAP.to_string(),
+                "--",
+                target,
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output();
+
+        let output = tokio::time::timeout(EXTRACTION_TIMEOUT, output)
+            .await
+            .map_err(|_| {
+                warn!("yt-dlp extraction timed out.");
+                YtdlpError::Timeout
+            })??;
+
+        if !output.status.success() {
+            let err = YtdlpError::from_stderr(&String::from_utf8_lossy(&output.stderr));
+            trace!(err = %err, "yt-dlp extraction failed.");
+            return Err(err);
+        }
+
+        Ok(serde_json::from_slice(&output.stdout)?)
+    }
+
+    /// Resolves `url` into a track or playlist.
+    pub async fn resolve(&self, url: &str) -> Result<QueueElement, YtdlpError> {
+        self.extract(url).await?.into_element(url)
+    }
+
+    /// Up to `n_results` YouTube videos matching `query`, best match first.
+    #[instrument(skip(self))]
+    pub async fn search_youtube_videos(
+        &self,
+        query: &str,
+        n_results: u32,
+    ) -> Result<Vec<Track>, YtdlpError> {
+        let info = self
+            .extract(&format!("ytsearch{n_results}:{query}"))
+            .await?;
+        let results = info
+            .entries
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.into_track("Youtube"))
+            .collect::<Vec<_>>();
+
+        if results.is_empty() {
+            return Err(YtdlpError::NoResults);
+        }
+        Ok(results)
+    }
+
+    /// Up to `n_results` YouTube playlists matching `query`, best match first, without their
+    /// items.
+    #[instrument(skip(self))]
+    pub async fn search_youtube_playlists(
+        &self,
+        query: &str,
+        n_results: u32,
+    ) -> Result<Vec<Playlist>, YtdlpError> {
+        let url = Url::parse_with_params(
+            YOUTUBE_RESULTS_URL,
+            [("search_query", query), ("sp", PLAYLIST_SEARCH_FILTER)],
+        )
+        .map_err(|e| YtdlpError::Extraction(e.to_string()))?;
+
+        let info = self.extract(url.as_str()).await?;
+        let results = info
+            .entries
+            .iter()
+            .flatten()
+            .filter(|entry| entry.page_url().is_some())
+            .map(|entry| entry.to_playlist("YoutubeTab", url.as_str()))
+            .take(n_results as usize)
+            .collect::<Vec<_>>();
+
+        if results.is_empty() {
+            return Err(YtdlpError::NoResults);
+        }
+        Ok(results)
+    }
+
+    /// The videos YouTube mixes with the video `seed_id`, leaving the video itself out.
+    async fn youtube_mix(&self, seed_id: &str) -> Result<Vec<Track>, YtdlpError> {
+        let mix = format!("{YOUTUBE_WATCH_URL}?v={seed_id}&list=RD{seed_id}");
+        let candidates = self
+            .extract(&mix)
+            .await?
+            .entries
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.into_track("Youtube"))
+            .filter(|track| track.id != seed_id)
+            .collect::<Vec<_>>();
+
+        if candidates.is_empty() {
+            return Err(YtdlpError::NoResults);
+        }
+        Ok(candidates)
+    }
+
+    /// A random video from the YouTube mix of the video `seed_id`, preferring videos whose IDs
+    /// are not in `recently_played`.
+    #[instrument(skip(self, recently_played))]
+    pub async fn related_youtube_video(
+        &self,
+        seed_id: &str,
+        recently_played: &[String],
+    ) -> Result<Track, YtdlpError> {
+        use rand::seq::SliceRandom;
+
+        let mix = self.youtube_mix(seed_id);
+        let mut candidates = match &self.cache {
+            Some(cache) => cache.mix(seed_id, mix).await?,
+            None => mix.await?,
+        };
+        candidates.shuffle(&mut rand::rng());
+
+        // Fall back to a recently played video rather than stopping the radio altogether.
+        let pick = candidates
+            .iter()
+            .position(|track| !recently_played.contains(&track.id))
+            .unwrap_or_else(|| {
+                trace!("Every candidate was played recently.");
+                0
+            });
+        let related_track = candidates.swap_remove(pick);
+        trace!(metadata = %related_track, "Picked a related video from the mix.");
+        Ok(related_track)
+    }
+}
+
+#[async_trait]
+impl MetadataProvider for YtdlpProvider {
+    fn supports_url(&self, url: &Url) -> bool {
+        matches!(url.scheme(), "http" | "https") && !public_address::is_private_host(url)
+    }
+
+    /// Links come from members, so they are only handed to yt-dlp when their host is public.
+    #[instrument(skip(self))]
+    async fn resolve_url(&self, url: &Url) -> Result<QueueElement, MetadataError> {
+        public_address::ensure_public(url)
+            .await
+            .map_err(YtdlpError::from)?;
+
+        let lookup = self.resolve(url.as_str());
+        Ok(match &self.cache {
+            Some(cache) => cache.link(url.as_str(), lookup).await?,
+            None => lookup.await?,
+        })
+    }
+
+    async fn search_tracks(
+        &self,
+        query: &str,
+        n_results: u32,
+    ) -> Result<Vec<Track>, MetadataError> {
+        let lookup = self.search_youtube_videos(query, n_results);
+        Ok(match &self.cache {
+            Some(cache) => cache.video_search(query, n_results, lookup).await?,
+            None => lookup.await?,
+        })
+    }
+
+    async fn search_playlists(
+        &self,
+        query: &str,
+        n_results: u32,
+    ) -> Result<Vec<Playlist>, MetadataError> {
+        let lookup = self.search_youtube_playlists(query, n_results);
+        Ok(match &self.cache {
+            Some(cache) => cache.playlist_search(query, n_results, lookup).await?,
+            None => lookup.await?,
+        })
+    }
+
+    /// Only YouTube puts together mixes of related videos.
+    async fn related_track(
+        &self,
+        seed: &Url,
+        recently_played: &[String],
+    ) -> Result<Track, MetadataError> {
+        let seed_id = YoutubeClient::extract_video_id(seed).ok_or(MetadataError::NotSupported)?;
+        Ok(self
+            .related_youtube_video(&seed_id, recently_played)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_stderr_reads_the_last_error() {
+        let stderr = "WARNING: something\nERROR: first\nERROR: [generic] Unsupported URL: x\n";
+        assert!(matches!(
+            YtdlpError::from_stderr(stderr),
+            YtdlpError::Unsupported
+        ));
+    }
+
+    #[test]
+    fn from_stderr_recognises_unavailable_content() {
+        let err = YtdlpError::from_stderr("ERROR: [youtube] abc: Private video. Sign in");
+        assert!(matches!(&err, YtdlpError::Unavailable(msg) if msg.starts_with("[youtube] abc")));
+
+        let err = YtdlpError::from_stderr("ERROR: HTTP Error 404: Not Found");
+        assert!(matches!(err, YtdlpError::Unavailable(_)));
+    }
+
+    #[test]
+    fn from_stderr_falls_back_to_the_whole_output() {
+        let err = YtdlpError::from_stderr("  something broke  ");
+        assert!(matches!(&err, YtdlpError::Extraction(msg) if msg == "something broke"));
+    }
+
+    #[test]
+    fn private_hosts_are_not_handed_to_ytdlp() {
+        let provider = YtdlpProvider::new();
+        let supports = |url: &str| provider.supports_url(&Url::parse(url).unwrap());
+
+        assert!(supports("https://soundcloud.com/a/b"));
+        assert!(!supports("http://127.0.0.1:8080/"));
+        assert!(!supports("http://localhost/"));
+        assert!(!supports("file:///etc/passwd"));
+    }
+}
@@ -1,10 +1,22 @@
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
 
 use crate::{
-    commands,
-    configuration::ConfigurationVariables,
+    actions::{panel_actions, session_actions, vote_actions},
+    checks, commands,
+    configuration::{ConfigurationVariables, MetadataBackend},
     metrics::Metric,
-    models::{self, DiscordError, RuntimeError},
+    models::{
+        self, AudioFileProvider, DiscordError, GuildSettings, GuildSnapshot, LrclibProvider,
+        LyricsProvider, MetadataCache, MetadataProvider, MetadataProviders, RuntimeError,
+        SearchSuggestions, YtdlpProvider,
+    },
+    stream::StreamPrefetcher,
 };
 use poise::{
     FrameworkError,
@@ -15,12 +27,29 @@ use tracing::{error, info};
 
 pub type Context<'a> = poise::Context<'a, ServerState, RuntimeError>;
 
+/// How the bot introduces itself to the web services it calls.
+pub const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// How long a web request may take to connect.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Debug, Clone)]
 pub struct ServerState {
     pub configuration_variables: ConfigurationVariables,
     pub request_client: reqwest::Client,
-    pub youtube_client: models::YoutubeClient,
+    /// Backends tracks are resolved with, in order of preference.
+    pub metadata_providers: MetadataProviders,
     pub guild_map: Arc<RwLock<HashMap<String, models::GuildState>>>,
+    /// Sessions saved by the previous run that are waiting for `/resume-session`.
+    pub pending_sessions: Arc<RwLock<HashMap<String, GuildSnapshot>>>,
+    /// Administrator preferences for each guild, persisted as they change.
+    pub guild_settings: Arc<RwLock<HashMap<String, GuildSettings>>>,
+    /// Streams warmed up for each guild's next track.
+    pub stream_prefetcher: StreamPrefetcher,
+    /// Where `/lyrics` looks lyrics up.
+    pub lyrics_provider: Arc<dyn LyricsProvider>,
+    /// Suggestions for the search queries members are typing.
+    pub search_suggestions: SearchSuggestions,
 }
 
 struct GuildMapKey;
@@ -28,8 +57,16 @@ impl TypeMapKey for GuildMapKey {
     type Value = Arc<RwLock<HashMap<String, models::GuildState>>>;
 }
 
+struct PendingSessionsKey;
+impl TypeMapKey for PendingSessionsKey {
+    type Value = Arc<RwLock<HashMap<String, GuildSnapshot>>>;
+}
+
 pub struct Server {
     serenity_client: poise::serenity_prelude::Client,
+    session_file: PathBuf,
+    metadata_cache: MetadataCache,
+    metadata_cache_file: Option<PathBuf>,
 }
 
 impl Server {
@@ -38,10 +75,25 @@ impl Server {
         use songbird::SerenityInit;
 
         let discord_token = vars.discord_token().to_string();
+        let session_file = vars.session_file().to_path_buf();
+        let metadata_cache_file = vars.metadata_cache_file().map(Path::to_path_buf);
+
+        let metadata_cache = match &metadata_cache_file {
+            Some(path) => MetadataCache::load(path).unwrap_or_else(|e| {
+                error!(err = %e, "Failed to load metadata cache. Starting with an empty one.");
+                MetadataCache::default()
+            }),
+            None => MetadataCache::default(),
+        };
 
         let framework = poise::Framework::builder()
             .options(Self::framework_options())
-            .setup(move |ctx, _ready, fw| Box::pin(Self::setup_framework(ctx, fw, vars)))
+            .setup({
+                let metadata_cache = metadata_cache.clone();
+                move |ctx, _ready, fw| {
+                    Box::pin(Self::setup_framework(ctx, fw, vars, metadata_cache))
+                }
+            })
             .build();
 
         let serenity_client = serenity_prelude::Client::builder(discord_token, Self::intents())
@@ -50,7 +102,12 @@ impl Server {
             .await
             .expect("Failed to build serenity client.");
 
-        Self { serenity_client }
+        Self {
+            serenity_client,
+            session_file,
+            metadata_cache,
+            metadata_cache_file,
+        }
     }
 
     pub async fn start(&mut self) -> Result<(), RuntimeError> {
@@ -60,18 +117,39 @@ impl Server {
         })
     }
 
-    // Graceful shutdown. Leave all channels and close gateway.
+    // Graceful shutdown. Save active sessions, leave all channels and close gateway.
     pub async fn stop(&mut self) {
-        let (sb_manager, guild_map) = {
+        let (sb_manager, guild_map, pending_sessions) = {
             let data = self.serenity_client.data.read().await;
             let sb_manager = data.get::<songbird::SongbirdKey>().cloned();
             let guild_map = data.get::<GuildMapKey>().cloned();
-            (sb_manager, guild_map)
+            let pending_sessions = data.get::<PendingSessionsKey>().cloned();
+            (sb_manager, guild_map, pending_sessions)
         };
 
         if let (Some(sb), Some(gm)) = (sb_manager, guild_map) {
             let map = gm.read().await;
 
+            let mut snapshots = session_actions::capture_snapshots(&map).await;
+
+            // Sessions nobody resumed yet are kept for the next run, unless the guild has
+            // started a new one since.
+            if let Some(pending_sessions) = pending_sessions {
+                snapshots.extend(
+                    pending_sessions
+                        .read()
+                        .await
+                        .iter()
+                        .filter(|(guild_key, _)| !map.contains_key(*guild_key))
+                        .map(|(_, snapshot)| snapshot.clone()),
+                );
+            }
+
+            match GuildSnapshot::save_all(&self.session_file, &snapshots) {
+                Ok(()) => info!(n_sessions = snapshots.len(), "Saved active sessions."),
+                Err(e) => error!(err = %e, "Failed to save active sessions."),
+            }
+
             for guild_id_str in map.keys() {
                 if let Ok(guild_id) = serenity_prelude::GuildId::from_str(guild_id_str)
                     && let Err(e) = sb.remove(guild_id).await
@@ -83,6 +161,13 @@ impl Server {
             info!("Disconnected from all voice channels.");
         }
 
+        if let Some(path) = &self.metadata_cache_file {
+            match self.metadata_cache.save(path) {
+                Ok(()) => info!("Saved metadata cache."),
+                Err(e) => error!(err = %e, "Failed to save metadata cache."),
+            }
+        }
+
         self.serenity_client.shard_manager.shutdown_all().await;
         info!("Gateway connection closed.");
     }
@@ -91,14 +176,32 @@ impl Server {
     fn framework_options() -> poise::FrameworkOptions<ServerState, RuntimeError> {
         poise::FrameworkOptions {
             commands: vec![
+                commands::crossfade::crossfade(),
+                commands::default_volume::default_volume(),
+                commands::dj::dj(),
+                commands::fair_queue::fair_queue(),
+                commands::filter::filter(),
+                commands::history::history(),
+                commands::loop_mode::loop_mode(),
+                commands::lyrics::lyrics(),
                 commands::pause::pause(),
                 commands::play::play(),
+                commands::previous::previous(),
                 commands::queue::queue(),
+                commands::queue_limits::queue_limits(),
                 commands::radio::radio(),
+                commands::replay::replay(),
                 commands::resume::resume(),
+                commands::resume_session::resume_session(),
+                commands::seek::seek(),
+                commands::shuffle::shuffle(),
                 commands::skip::skip(),
+                commands::skipto::skipto(),
                 commands::stop::stop(),
+                commands::volume::volume(),
+                commands::vote_skip::vote_skip(),
             ],
+            command_check: Some(|ctx| Box::pin(checks::command_policy(ctx))),
             pre_command: |ctx| {
                 Box::pin(async move {
                     let command_name = ctx.command().name.clone();
@@ -127,6 +230,7 @@ impl Server {
                     );
                 })
             },
+            event_handler: |ctx, event, _fw, data| Box::pin(Self::event_handler(ctx, event, data)),
             on_error: |err| Box::pin(Self::error_handler(err)),
             require_cache_for_guild_check: true,
             ..Default::default()
@@ -138,6 +242,7 @@ impl Server {
         ctx: &serenity_prelude::Context,
         fw: &poise::Framework<ServerState, RuntimeError>,
         vars: ConfigurationVariables,
+        metadata_cache: MetadataCache,
     ) -> Result<ServerState, RuntimeError> {
         // Initialize crypto provider
         rustls::crypto::ring::default_provider()
@@ -148,20 +253,61 @@ impl Server {
         Self::register_commands(ctx, &fw.options().commands, &vars).await?;
 
         // Initialize State
-        let youtube_client = models::YoutubeClient::new(vars.youtube_api_key()).await;
         let guild_map = Arc::new(RwLock::new(HashMap::new()));
 
+        let guild_settings =
+            GuildSettings::load_all(vars.guild_settings_file()).unwrap_or_else(|e| {
+                error!(err = %e, "Failed to load guild settings. Falling back to defaults.");
+                HashMap::new()
+            });
+
+        let pending_sessions = Arc::new(RwLock::new(HashMap::new()));
+
         {
             let mut data = ctx.data.write().await;
             data.insert::<GuildMapKey>(guild_map.clone());
+            data.insert::<PendingSessionsKey>(pending_sessions.clone());
         }
 
-        Ok(ServerState {
-            youtube_client,
-            request_client: reqwest::Client::new(),
+        let request_client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .expect("Failed to build HTTP client");
+        let mut providers: Vec<Arc<dyn MetadataProvider>> = Vec::new();
+        match vars.metadata_backend() {
+            MetadataBackend::YoutubeApi { api_key } => {
+                providers.push(Arc::new(
+                    models::YoutubeClient::new(api_key, metadata_cache.clone()).await,
+                ));
+            }
+            // yt-dlp goes last anyway, and takes over YouTube links, searches and radio.
+            MetadataBackend::Ytdlp => info!("No YouTube API in use, resolving with yt-dlp alone."),
+        }
+        providers.push(Arc::new(AudioFileProvider::new()));
+        providers.push(Arc::new(YtdlpProvider::with_cache(metadata_cache)));
+        let metadata_providers = MetadataProviders::new(providers);
+        let lyrics_provider = Arc::new(LrclibProvider::new(
+            request_client.clone(),
+            vars.lyrics_api_url(),
+        ));
+
+        let state = ServerState {
+            metadata_providers,
+            request_client,
             configuration_variables: vars,
             guild_map,
-        })
+            pending_sessions,
+            guild_settings: Arc::new(RwLock::new(guild_settings)),
+            stream_prefetcher: StreamPrefetcher::default(),
+            lyrics_provider,
+            search_suggestions: SearchSuggestions::default(),
+        };
+
+        // Restore sessions in the background so setup is not held up by voice connections.
+        tokio::spawn(session_actions::load_sessions(ctx.clone(), state.clone()));
+
+        Ok(state)
     }
 
     /// Defines the required Discord Gateway intents.
@@ -197,6 +343,28 @@ impl Server {
         Ok(())
     }
 
+    /// Handles gateway events that are not tied to a command invocation.
+    async fn event_handler(
+        ctx: &serenity_prelude::Context,
+        event: &serenity_prelude::FullEvent,
+        data: &ServerState,
+    ) -> Result<(), RuntimeError> {
+        if let serenity_prelude::FullEvent::InteractionCreate {
+            interaction: serenity_prelude::Interaction::Component(interaction),
+        } = event
+        {
+            let custom_id = &interaction.data.custom_id;
+
+            if custom_id.starts_with(panel_actions::PANEL_ID_PREFIX) {
+                panel_actions::handle_interaction(ctx, data, interaction).await?;
+            } else if custom_id.starts_with(vote_actions::VOTE_SKIP_ID_PREFIX) {
+                vote_actions::handle_interaction(ctx, data, interaction).await?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Global framework error handler.
     async fn error_handler(err: FrameworkError<'_, ServerState, RuntimeError>) {
         // TODO: Explore simplifying with spans?
@@ -266,6 +434,15 @@ impl Server {
                 let _ = ctx.send(reply).await;
             }
 
+            FrameworkError::MissingUserPermissions { ctx, .. } => {
+                info!(command=%ctx.command().name, user_id=%ctx.author().id.get(), "Missing user permissions.");
+                let reply = poise::CreateReply::default().embed(crate::embeds::create_error_embed(
+                    "You do not have permission to run this command.",
+                ));
+
+                let _ = ctx.send(reply).await;
+            }
+
             FrameworkError::EventHandler { error, event, .. } => {
                 metrics::counter!(Metric::EventHandlerErrorsTotal.as_ref(), "type" => "background_error", "event" => event.snake_case_name()).increment(1);
                 error!(event=?event.snake_case_name(), err=%error, "Background event handler failed.");
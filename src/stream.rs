@@ -1,9 +1,20 @@
 use metrics::{counter, histogram};
 use songbird::input::{AudioStream, Input, LiveInput, core::io::ReadOnlySource};
-use std::process::{Command, Stdio};
-use tokio::time::Instant;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+use tokio::{io::AsyncWriteExt, time::Instant};
+use tracing::{trace, warn};
+use url::Url;
 
-use crate::metrics::{Metric, instruments::instrumented_reader::InstrumentedReader};
+use crate::{
+    metrics::{Metric, instruments::instrumented_reader::InstrumentedReader},
+    models,
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum StreamError {
@@ -11,20 +22,136 @@ pub enum StreamError {
     Spawn(String, std::io::Error),
     #[error("Failed to capture stdout from {0}")]
     Capture(String),
+    #[error("Not a link to a supported audio file: {0}")]
+    UnsupportedFile(String),
 }
 
-/// Spawns yt-dlp and pipes it into ffmpeg to deliver a stream to Songbird.
-pub fn create_audio_stream(url: &str) -> Result<Input, StreamError> {
+/// Bitrate used when filters or the source's format force the audio to be re-encoded.
+const ENCODED_BITRATE: &str = "192k";
+
+/// yt-dlp format selector for regular videos, preferring the Opus audio track.
+const VIDEO_FORMAT: &str = "251/bestaudio";
+
+/// yt-dlp format selector for other sites, many of which only serve audio along with video.
+const EXTRACTED_FORMAT: &str = "bestaudio/best";
+
+/// yt-dlp format selector for live streams, which are only served as HLS with video. A low
+/// resolution keeps the download small without lowering the audio quality.
+const LIVE_FORMAT: &str = "bestaudio/best[height<=360]/best";
+
+/// How a stream should be shaped on its way to Songbird.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamOptions {
+    /// Position in the source to start streaming from.
+    pub start: Duration,
+    /// An ffmpeg audio filter chain. Without one, the audio is passed through untouched.
+    pub filters: Option<String>,
+    /// Whether the source is a live stream, which starts at the live edge and can't be seeked.
+    pub live: bool,
+    /// Whether the URL points straight at an audio file, which ffmpeg reads without yt-dlp.
+    pub direct: bool,
+    /// Whether the source's audio may be in a codec other than Opus, which can't be copied
+    /// into Ogg as is.
+    pub transcode: bool,
+}
+
+impl StreamOptions {
+    /// Seconds into the source to start from, unless the stream starts at the beginning or at
+    /// the live edge.
+    fn seek_position(&self) -> Option<String> {
+        (!self.start.is_zero() && !self.live).then(|| format!("{:.3}", self.start.as_secs_f64()))
+    }
+
+    /// Seeks by only downloading the part of the media from the start position onwards, rather
+    /// than having ffmpeg decode its way up to it.
+    fn ytdlp_args(&self, url: &str) -> Vec<String> {
+        let format = if self.live {
+            LIVE_FORMAT
+        } else if self.transcode {
+            EXTRACTED_FORMAT
+        } else {
+            VIDEO_FORMAT
+        };
+
+        let mut args = vec![
+            "--use-extractors".to_string(),
+            models::EXTRACTORS.to_string(),
+            "-f".to_string(),
+            format.to_string(),
+        ];
+        if let Some(start) = self.seek_position() {
+            args.extend(["--download-sections".to_string(), format!("*{start}-inf")]);
+        }
+        // Stream to stdout. The URL can't be taken for an option after `--`.
+        args.extend(["-o", "-", "--", url].map(String::from));
+        args
+    }
+
+    /// `input_format` forces the demuxer used for direct sources.
+    fn ffmpeg_args(&self, input_format: Option<&str>) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.direct {
+            if let Some(format) = input_format {
+                args.extend(["-f".to_string(), format.to_string()]);
+            }
+
+            // Sources from yt-dlp are already cut by it, so only files skip ahead to the
+            // position here.
+            if let Some(start) = self.seek_position() {
+                args.extend(["-ss".to_string(), start]);
+            }
+        }
+        args.extend(["-i", "pipe:0"].map(String::from));
+
+        if let Some(filters) = &self.filters {
+            args.extend(["-af".to_string(), filters.clone()]);
+        }
+
+        // Live streams arrive as AAC in MPEG-TS and other sources come in any format, neither
+        // of which can be copied into Ogg.
+        if self.filters.is_some() || self.live || self.transcode {
+            args.extend(["-c:a", "libopus", "-b:a", ENCODED_BITRATE].map(String::from));
+        } else {
+            args.extend(["-c:a", "copy"].map(String::from));
+        }
+
+        args.extend(["-f", "ogg", "-vn", "pipe:1"].map(String::from));
+        args
+    }
+}
+
+/// Spawns yt-dlp and pipes it into ffmpeg to deliver a stream to Songbird. Direct links to audio
+/// files are downloaded by the bot and piped into ffmpeg instead.
+pub fn create_audio_stream(url: &str, options: &StreamOptions) -> Result<Input, StreamError> {
     let start_time = Instant::now();
+
+    if options.direct {
+        let (url, input_format) = Url::parse(url)
+            .ok()
+            .and_then(|url| {
+                let input_format = models::input_format(&url)?;
+                Some((url, input_format))
+            })
+            .ok_or_else(|| StreamError::UnsupportedFile(url.to_string()))?;
+
+        let mut ffmpeg = Command::new("ffmpeg")
+            .args(options.ffmpeg_args(Some(input_format)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+
+        // ffmpeg never sees the link, so it can't be led anywhere the address checks on the
+        // bot's own client wouldn't allow.
+        if let Some(stdin) = ffmpeg.as_mut().ok().and_then(|ffmpeg| ffmpeg.stdin.take()) {
+            tokio::spawn(feed_audio_file(url, stdin));
+        }
+        return finish_stream(ffmpeg, start_time);
+    }
+
     // Spawn yt-dlp to download the raw audio stream
     let mut ytdl = Command::new("yt-dlp")
-        .args([
-            "-f",
-            "251/bestaudio",
-            "-o",
-            "-", // Stream to stdout
-            url,
-        ])
+        .args(options.ytdlp_args(url))
         .stdout(Stdio::piped())
         .stderr(Stdio::null())
         .spawn()
@@ -41,17 +168,57 @@ pub fn create_audio_stream(url: &str) -> Result<Input, StreamError> {
     })?;
 
     // Spawn ffmpeg to transcode on-the-fly into raw/probe-friendly MP3 data
-    let mut ffmpeg = Command::new("ffmpeg")
-        .args(["-i", "pipe:0", "-c:a", "copy", "-f", "ogg", "-vn", "pipe:1"])
+    let ffmpeg = Command::new("ffmpeg")
+        .args(options.ffmpeg_args(None))
         .stdin(ytdl_stdout)
         .stdout(Stdio::piped())
         .stderr(Stdio::null())
-        .spawn()
-        .map_err(|e| {
-            counter!(Metric::StreamCreationTotal.as_ref(), "status" => "error", "process" => "ffmpeg")
-                .increment(1);
-            StreamError::Spawn("ffmpeg".to_string(), e)
-        })?;
+        .spawn();
+    finish_stream(ffmpeg, start_time)
+}
+
+/// Downloads the audio file at `url` into ffmpeg as it plays. A failed download ends ffmpeg's
+/// input early, which ends the track.
+async fn feed_audio_file(url: Url, stdin: ChildStdin) {
+    let mut response = match models::open_audio_file(&url).await {
+        Ok(response) => response,
+        Err(e) => {
+            warn!(err = %e, %url, "Failed to open audio file.");
+            return;
+        }
+    };
+
+    let Ok(mut stdin) = tokio::process::ChildStdin::from_std(stdin) else {
+        return;
+    };
+
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                // Writing only fails once ffmpeg has exited, as it does when the track stops.
+                if stdin.write_all(&chunk).await.is_err() {
+                    return;
+                }
+            }
+            Ok(None) => return,
+            Err(e) => {
+                warn!(err = %e, %url, "Audio file download broke off.");
+                return;
+            }
+        }
+    }
+}
+
+/// Wraps the output of the ffmpeg process that ends a pipeline into an input for Songbird.
+fn finish_stream(
+    ffmpeg: std::io::Result<Child>,
+    start_time: Instant,
+) -> Result<Input, StreamError> {
+    let mut ffmpeg = ffmpeg.map_err(|e| {
+        counter!(Metric::StreamCreationTotal.as_ref(), "status" => "error", "process" => "ffmpeg")
+            .increment(1);
+        StreamError::Spawn("ffmpeg".to_string(), e)
+    })?;
 
     let ffmpeg_stdout = ffmpeg.stdout.take().ok_or_else(|| {
         counter!(Metric::StreamCreationTotal.as_ref(), "status" => "error", "process" => "ffmpeg_stdout")
@@ -83,3 +250,173 @@ pub fn create_audio_stream(url: &str) -> Result<Input, StreamError> {
 
     Ok(Input::Live(raw_src, None))
 }
+
+/// A stream spawned ahead of time for the track a guild is expected to play next.
+struct PrefetchedStream {
+    url: String,
+    options: StreamOptions,
+    input: Input,
+}
+
+impl PrefetchedStream {
+    fn matches(&self, url: &str, options: &StreamOptions) -> bool {
+        self.url == url && self.options == *options
+    }
+}
+
+/// Holds a warm stream per guild so track transitions skip the cold start of yt-dlp and ffmpeg.
+/// Dropping a stream closes its pipe, which shuts both processes down.
+#[derive(Clone, Default)]
+pub struct StreamPrefetcher {
+    streams: Arc<Mutex<HashMap<String, PrefetchedStream>>>,
+}
+
+impl Debug for StreamPrefetcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamPrefetcher")
+            .field("guilds", &self.lock().keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl StreamPrefetcher {
+    /// Makes sure the guild's prefetched stream is for `url` with `options`, replacing any other.
+    pub fn prefetch(
+        &self,
+        guild_key: &str,
+        url: &str,
+        options: &StreamOptions,
+    ) -> Result<(), StreamError> {
+        let mut streams = self.lock();
+        if streams
+            .get(guild_key)
+            .is_some_and(|stream| stream.matches(url, options))
+        {
+            return Ok(());
+        }
+
+        streams.remove(guild_key);
+        trace!(guild_key, url, "Prefetching stream.");
+        let input = create_audio_stream(url, options)?;
+        streams.insert(
+            guild_key.to_string(),
+            PrefetchedStream {
+                url: url.to_string(),
+                options: options.clone(),
+                input,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Hands over the guild's prefetched stream if it was prepared for `url` with `options`, or
+    /// creates a fresh one otherwise.
+    pub fn take_or_create(
+        &self,
+        guild_key: &str,
+        url: &str,
+        options: &StreamOptions,
+    ) -> Result<Input, StreamError> {
+        let prefetched = self.lock().remove(guild_key);
+
+        match prefetched {
+            Some(stream) if stream.matches(url, options) => {
+                counter!(Metric::StreamPrefetchTotal.as_ref(), "result" => "hit").increment(1);
+                Ok(stream.input)
+            }
+            _ => {
+                counter!(Metric::StreamPrefetchTotal.as_ref(), "result" => "miss").increment(1);
+                create_audio_stream(url, options)
+            }
+        }
+    }
+
+    /// Tears down the guild's prefetched stream, if any.
+    pub fn discard(&self, guild_key: &str) {
+        if self.lock().remove(guild_key).is_some() {
+            trace!(guild_key, "Discarded prefetched stream.");
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, PrefetchedStream>> {
+        self.streams.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeking_to(secs: u64) -> StreamOptions {
+        StreamOptions {
+            start: Duration::from_secs(secs),
+            ..Default::default()
+        }
+    }
+
+    fn position(args: &[String], arg: &str) -> Option<usize> {
+        args.iter().position(|a| a == arg)
+    }
+
+    #[test]
+    fn extracted_streams_are_cut_by_ytdlp() {
+        let options = seeking_to(90);
+
+        let ytdlp = options.ytdlp_args("https://example.com/v");
+        let sections = position(&ytdlp, "--download-sections").unwrap();
+        assert_eq!(ytdlp[sections + 1], "*90.000-inf");
+        assert_eq!(ytdlp.last().unwrap(), "https://example.com/v");
+
+        assert!(position(&options.ffmpeg_args(None), "-ss").is_none());
+    }
+
+    #[test]
+    fn direct_streams_seek_before_opening_the_input() {
+        let options = StreamOptions {
+            direct: true,
+            ..seeking_to(90)
+        };
+
+        let args = options.ffmpeg_args(Some("mp3"));
+        assert!(position(&args, "-ss").unwrap() < position(&args, "-i").unwrap());
+    }
+
+    #[test]
+    fn direct_streams_are_read_from_the_pipe() {
+        let options = StreamOptions {
+            direct: true,
+            ..Default::default()
+        };
+
+        let args = options.ffmpeg_args(Some("mp3"));
+        let input = position(&args, "-i").unwrap();
+        assert_eq!(args[input + 1], "pipe:0");
+
+        let format = position(&args, "-f").unwrap();
+        assert_eq!(args[format + 1], "mp3");
+        assert!(format < input);
+    }
+
+    #[test]
+    fn ytdlp_streams_use_dedicated_extractors_only() {
+        let args = seeking_to(0).ytdlp_args("--exec=touch x");
+
+        let extractors = position(&args, "--use-extractors").unwrap();
+        assert_eq!(args[extractors + 1], models::EXTRACTORS);
+
+        let separator = position(&args, "--").unwrap();
+        assert_eq!(separator, args.len() - 2);
+        assert_eq!(args.last().unwrap(), "--exec=touch x");
+    }
+
+    #[test]
+    fn live_streams_and_fresh_starts_are_not_seeked() {
+        let live = StreamOptions {
+            live: true,
+            ..seeking_to(90)
+        };
+        assert!(position(&live.ytdlp_args("u"), "--download-sections").is_none());
+        assert!(position(&seeking_to(0).ytdlp_args("u"), "--download-sections").is_none());
+    }
+}